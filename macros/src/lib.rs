@@ -0,0 +1,501 @@
+// 过程宏
+// ch09::first::third 中提到, 过程宏是用 Rust 写的编译器扩展。它是一个单独的 crate,
+// Cargo.toml 中写上 proc-macro = true, 编译器把宏的输入作为 TokenStream 交给它, 再把返回的
+// TokenStream 放回原来的位置。
+// 这里没有使用 syn 和 quote, 只用编译器自带的 proc_macro, 所以解析和生成代码都是手写的:
+// 1. #[derive(Describe)]: 列出结构体每个成员的名字、类型和值
+// 2. #[derive(FieldCount)]: 结构体成员的个数和名字, 是编译期常量
+// 3. #[lesson("9.2")]: 把一个函数登记到课程列表中, 不用再修改 main.rs
+// 生成的代码通过 ::dive_into_rust_of_fcc::ch09::third 访问 trait, 用法和测试见那个文件。
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Spacing, Span, TokenStream, TokenTree};
+
+// ---------- 错误 ----------
+
+struct Error {
+    message: String,
+    span: Span,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn error<T>(message: &str, span: Span) -> Result<T> {
+    Err(Error {
+        message: message.to_string(),
+        span,
+    })
+}
+
+impl Error {
+    // 展开成 compile_error!, 并把位置指向出错的代码, 编译器会在那里报错
+    fn into_compile_error(self) -> TokenStream {
+        let tokens: TokenStream = format!("::core::compile_error!({:?});", self.message)
+            .parse()
+            .unwrap();
+        tokens
+            .into_iter()
+            .map(|mut t| {
+                t.set_span(self.span);
+                t
+            })
+            .collect()
+    }
+}
+
+// ---------- 解析 ----------
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(i)) if i.to_string() == name)
+}
+
+fn is_group(token: Option<&TokenTree>, delimiter: Delimiter) -> bool {
+    matches!(token, Some(TokenTree::Group(g)) if g.delimiter() == delimiter)
+}
+
+// 跳过属性 #[...] 和可见性 pub、pub(crate)
+fn skip_attrs_and_vis(tokens: &[TokenTree], i: &mut usize) {
+    loop {
+        if is_punct(tokens.get(*i), '#') && is_group(tokens.get(*i + 1), Delimiter::Bracket) {
+            *i += 2;
+        } else if is_ident(tokens.get(*i), "pub") {
+            *i += 1;
+            if is_group(tokens.get(*i), Delimiter::Parenthesis) {
+                *i += 1;
+            }
+        } else {
+            return;
+        }
+    }
+}
+
+// 尖括号不是 Group, 需要自己数层数; -> 中的 > 不算
+fn angle_depth_change(tokens: &[TokenTree], i: usize) -> i32 {
+    match &tokens[i] {
+        TokenTree::Punct(p) if p.as_char() == '<' => 1,
+        TokenTree::Punct(p) if p.as_char() == '>' => {
+            let arrow = i > 0
+                && matches!(&tokens[i - 1], TokenTree::Punct(q)
+                    if q.as_char() == '-' && q.spacing() == Spacing::Joint);
+            if arrow {
+                0
+            } else {
+                -1
+            }
+        }
+        _ => 0,
+    }
+}
+
+// 在尖括号外面的 sep 处切开, 空的部分(比如末尾的逗号之后)被丢掉
+fn split_top_level(tokens: &[TokenTree], sep: char) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0;
+    for i in 0..tokens.len() {
+        depth += angle_depth_change(tokens, i);
+        if depth == 0 && is_punct(tokens.get(i), sep) {
+            parts.push(Vec::new());
+        } else {
+            parts.last_mut().unwrap().push(tokens[i].clone());
+        }
+    }
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+fn to_source(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
+
+// 把类型写成人习惯的样子: Vec<i32> 而不是 Vec < i32 >
+fn pretty(tokens: &[TokenTree]) -> String {
+    let mut out = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let text = match token {
+            TokenTree::Group(g) => {
+                let inner: Vec<TokenTree> = g.stream().into_iter().collect();
+                let inner = pretty(&inner);
+                let inner = inner.trim();
+                match g.delimiter() {
+                    Delimiter::Parenthesis => format!("({})", inner),
+                    Delimiter::Bracket => format!("[{}]", inner),
+                    Delimiter::Brace => format!("{{{}}}", inner),
+                    Delimiter::None => inner.to_string(),
+                }
+            }
+            TokenTree::Punct(p) => match p.as_char() {
+                ',' => ", ".to_string(),
+                ';' => "; ".to_string(),
+                // :: 是路径, 单独的 : 后面加空格
+                ':' if p.spacing() == Spacing::Alone
+                    && !is_punct(tokens.get(i.wrapping_sub(1)), ':') =>
+                {
+                    ": ".to_string()
+                }
+                '+' => " + ".to_string(),
+                '-' if is_punct(tokens.get(i + 1), '>') => " -".to_string(),
+                '>' if i > 0 && is_punct(tokens.get(i - 1), '-') => "> ".to_string(),
+                c => c.to_string(),
+            },
+            other => other.to_string(),
+        };
+        // 两个单词之间需要空格, 比如 dyn Trait、&'a str、mut T
+        let word = |c: char| c.is_alphanumeric() || c == '_';
+        if out.ends_with(word) && text.starts_with(word) {
+            out.push(' ');
+        }
+        out.push_str(&text);
+    }
+    out.trim().to_string()
+}
+
+struct Field {
+    /// 成员名, 元组结构体是 0、1、2...
+    name: String,
+    ty: Vec<TokenTree>,
+}
+
+impl Field {
+    // r#type 这样的原始标识符显示时去掉 r#
+    fn display_name(&self) -> &str {
+        self.name.trim_start_matches("r#")
+    }
+}
+
+enum Fields {
+    Named(Vec<Field>),
+    Tuple(Vec<Field>),
+    Unit,
+}
+
+struct Struct {
+    name: String,
+    /// 泛型参数, 已经去掉了默认值, impl<...> 中不能写默认值
+    params: Vec<Vec<TokenTree>>,
+    where_clause: Vec<TokenTree>,
+    fields: Fields,
+}
+
+impl Struct {
+    // 泛型参数的名字: 'a、T、N(const N: usize)
+    fn param_names(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .map(|p| {
+                if is_punct(p.first(), '\'') {
+                    to_source(&p[..2]).replace(' ', "")
+                } else if is_ident(p.first(), "const") {
+                    p[1].to_string()
+                } else {
+                    p[0].to_string()
+                }
+            })
+            .collect()
+    }
+
+    // impl<...> Trait for Name<...> where ..., extra 是额外的 where 条件
+    fn impl_header(&self, trait_path: &str, extra: &[String]) -> String {
+        let (impl_generics, ty_generics) = if self.params.is_empty() {
+            (String::new(), String::new())
+        } else {
+            let params: Vec<String> = self.params.iter().map(|p| to_source(p)).collect();
+            (
+                format!("<{}>", params.join(", ")),
+                format!("<{}>", self.param_names().join(", ")),
+            )
+        };
+        let mut predicates: Vec<String> = split_top_level(&self.where_clause, ',')
+            .iter()
+            .map(|p| to_source(p))
+            .collect();
+        predicates.extend(extra.iter().cloned());
+        let where_clause = if predicates.is_empty() {
+            String::new()
+        } else {
+            format!("where {}", predicates.join(", "))
+        };
+        format!(
+            "impl{} {} for {}{} {}",
+            impl_generics, trait_path, self.name, ty_generics, where_clause
+        )
+    }
+
+    fn fields(&self) -> &[Field] {
+        match &self.fields {
+            Fields::Named(f) | Fields::Tuple(f) => f,
+            Fields::Unit => &[],
+        }
+    }
+}
+
+fn parse_fields(group: &proc_macro::Group, named: bool) -> Result<Vec<Field>> {
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+    let mut fields = Vec::new();
+    for (index, part) in split_top_level(&tokens, ',').into_iter().enumerate() {
+        let mut i = 0;
+        skip_attrs_and_vis(&part, &mut i);
+        if named {
+            let name = match part.get(i) {
+                Some(TokenTree::Ident(ident)) => ident.to_string(),
+                _ => return error("expected a field name", group.span()),
+            };
+            if !is_punct(part.get(i + 1), ':') {
+                return error("expected `:` after the field name", part[i].span());
+            }
+            fields.push(Field {
+                name,
+                ty: part[i + 2..].to_vec(),
+            });
+        } else {
+            fields.push(Field {
+                name: index.to_string(),
+                ty: part[i..].to_vec(),
+            });
+        }
+    }
+    Ok(fields)
+}
+
+fn parse_struct(input: TokenStream, derive: &str) -> Result<Struct> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    let mut i = 0;
+    skip_attrs_and_vis(&tokens, &mut i);
+    match tokens.get(i) {
+        Some(TokenTree::Ident(kw)) if kw.to_string() == "struct" => i += 1,
+        Some(other) => {
+            let message = format!("#[derive({})] only supports structs", derive);
+            return error(&message, other.span());
+        }
+        None => return error("expected a struct", Span::call_site()),
+    }
+    let name = match tokens.get(i) {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return error("expected the struct name", Span::call_site()),
+    };
+    i += 1;
+
+    let mut params = Vec::new();
+    if is_punct(tokens.get(i), '<') {
+        let start = i + 1;
+        let mut depth = 0;
+        loop {
+            if i >= tokens.len() {
+                return error("unclosed generic parameters", Span::call_site());
+            }
+            depth += angle_depth_change(&tokens, i);
+            if depth == 0 {
+                break;
+            }
+            i += 1;
+        }
+        for param in split_top_level(&tokens[start..i], ',') {
+            // 去掉默认值 T = i32
+            let without_default = split_top_level(&param, '=').swap_remove(0);
+            params.push(without_default);
+        }
+        i += 1;
+    }
+
+    let mut where_clause = Vec::new();
+    let mut fields = Fields::Unit;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Ident(kw) if kw.to_string() == "where" => {
+                i += 1;
+                while i < tokens.len()
+                    && !is_group(tokens.get(i), Delimiter::Brace)
+                    && !is_punct(tokens.get(i), ';')
+                {
+                    where_clause.push(tokens[i].clone());
+                    i += 1;
+                }
+                continue;
+            }
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                fields = Fields::Named(parse_fields(g, true)?);
+            }
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
+                fields = Fields::Tuple(parse_fields(g, false)?);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(Struct {
+        name,
+        params,
+        where_clause,
+        fields,
+    })
+}
+
+fn expand(result: Result<String>) -> TokenStream {
+    match result {
+        Ok(code) => code.parse().unwrap(),
+        Err(e) => e.into_compile_error(),
+    }
+}
+
+const THIRD: &str = "::dive_into_rust_of_fcc::ch09::third";
+
+// ---------- derive ----------
+
+/// 为结构体实现 ch09::third::Describe, 每个成员的类型都必须实现 Debug
+#[proc_macro_derive(Describe)]
+pub fn derive_describe(input: TokenStream) -> TokenStream {
+    expand(parse_struct(input, "Describe").map(|s| {
+        // 对每个成员的类型要求 Debug, 而不是对每个泛型参数, 这样 PhantomData<T> 之类的成员不会多出约束
+        let bounds: Vec<String> = s
+            .fields()
+            .iter()
+            .map(|f| format!("{}: ::core::fmt::Debug", to_source(&f.ty)))
+            .collect();
+        let infos: Vec<String> = s
+            .fields()
+            .iter()
+            .map(|f| {
+                format!(
+                    "{third}::FieldInfo {{ name: {name:?}, ty: {ty:?}, value: ::std::format!(\"{{:?}}\", &self.{field}) }}",
+                    third = THIRD,
+                    name = f.display_name(),
+                    ty = pretty(&f.ty),
+                    field = f.name,
+                )
+            })
+            .collect();
+        format!(
+            "{header} {{
+                fn type_name(&self) -> &'static str {{ {name:?} }}
+                fn fields(&self) -> ::std::vec::Vec<{third}::FieldInfo> {{
+                    ::std::vec![{infos}]
+                }}
+            }}",
+            header = s.impl_header(&format!("{}::Describe", THIRD), &bounds),
+            name = s.name,
+            third = THIRD,
+            infos = infos.join(", "),
+        )
+    }))
+}
+
+/// 为结构体实现 ch09::third::FieldCount
+#[proc_macro_derive(FieldCount)]
+pub fn derive_field_count(input: TokenStream) -> TokenStream {
+    expand(parse_struct(input, "FieldCount").map(|s| {
+        let names: Vec<String> = s
+            .fields()
+            .iter()
+            .map(|f| format!("{:?}", f.display_name()))
+            .collect();
+        format!(
+            "{header} {{
+                const FIELD_COUNT: usize = {count};
+                const FIELD_NAMES: &'static [&'static str] = &[{names}];
+            }}",
+            header = s.impl_header(&format!("{}::FieldCount", THIRD), &[]),
+            count = names.len(),
+            names = names.join(", "),
+        )
+    }))
+}
+
+// ---------- #[lesson] ----------
+
+// 课程编号只能是用 . 隔开的数字, 比如 9.2
+fn parse_lesson_id(attr: TokenStream) -> Result<String> {
+    let tokens: Vec<TokenTree> = attr.into_iter().collect();
+    let literal = match tokens.as_slice() {
+        [TokenTree::Literal(l)] => l,
+        [] => {
+            return error(
+                "expected a lesson id, e.g. #[lesson(\"9.2\")]",
+                Span::call_site(),
+            )
+        }
+        [first, ..] => return error("expected a single string literal", first.span()),
+    };
+    let text = literal.to_string();
+    let id = match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+        Some(id) => id.to_string(),
+        None => return error("the lesson id must be a string literal", literal.span()),
+    };
+    let valid = !id.is_empty()
+        && id
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if !valid {
+        return error(
+            "a lesson id is made of numbers separated by dots, e.g. \"9.2\"",
+            literal.span(),
+        );
+    }
+    Ok(id)
+}
+
+/// 把一个没有参数的函数登记到课程列表中: #[lesson("9.2")]
+/// 程序启动时(main 之前)登记, 做法与 ctor 这类库相同: 把登记函数的指针放在可执行文件的初始化段中,
+/// 加载程序时操作系统的加载器会依次调用它们。
+/// 其他平台没有这样的段, 课程会悄悄地消失, 所以在那些平台上直接编译失败
+#[proc_macro_attribute]
+pub fn lesson(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let id = match parse_lesson_id(attr) {
+        Ok(id) => id,
+        Err(e) => return e.into_compile_error(),
+    };
+    let tokens: Vec<TokenTree> = item.clone().into_iter().collect();
+    let fn_index = tokens.iter().position(|t| is_ident(Some(t), "fn"));
+    let name = match fn_index.and_then(|i| tokens.get(i + 1)) {
+        Some(TokenTree::Ident(name)) => name.to_string(),
+        _ => {
+            let span = tokens.first().map_or(Span::call_site(), |t| t.span());
+            return Error {
+                message: "#[lesson] can only be applied to functions".to_string(),
+                span,
+            }
+            .into_compile_error();
+        }
+    };
+    let i = fn_index.unwrap() + 2;
+    let check = if is_punct(tokens.get(i), '<') {
+        error("lesson functions cannot be generic", tokens[i].span())
+    } else {
+        match tokens.get(i) {
+            Some(TokenTree::Group(args)) if !args.stream().is_empty() => {
+                error("lesson functions cannot take arguments", args.span())
+            }
+            _ => Ok(()),
+        }
+    };
+    if let Err(e) = check {
+        let mut out = item;
+        out.extend(e.into_compile_error());
+        return out;
+    }
+
+    let registration: TokenStream = format!(
+        "const _: () = {{
+            extern \"C\" fn register() {{
+                {third}::register_lesson({third}::Lesson {{ id: {id:?}, name: {name:?}, run: {name} }});
+            }}
+            #[used]
+            #[cfg_attr(any(target_os = \"linux\", target_os = \"android\", target_os = \"freebsd\"), link_section = \".init_array\")]
+            #[cfg_attr(target_vendor = \"apple\", link_section = \"__DATA,__mod_init_func\")]
+            #[cfg_attr(windows, link_section = \".CRT$XCU\")]
+            static REGISTER: extern \"C\" fn() = register;
+            #[cfg(not(any(target_os = \"linux\", target_os = \"android\", target_os = \"freebsd\", target_vendor = \"apple\", windows)))]
+            compile_error!(\"#[lesson] is not supported on this target, add the lesson to the LESSONS table in main.rs instead\");
+        }};",
+        third = THIRD,
+        id = id,
+        name = name,
+    )
+    .parse()
+    .unwrap();
+    let mut out = item;
+    out.extend(registration);
+    out
+}
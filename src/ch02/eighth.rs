@@ -0,0 +1,331 @@
+#![allow(dead_code)]
+
+// 线程安全的全局配置
+// first::use_global 演示了 static mut G2, 读写它都必须写 unsafe,
+// 因为多个线程同时修改同一个全局变量就是数据竞争, 编译器无法检查。
+// 实际上几乎所有需要"可变全局变量"的场景, 都可以用标准库提供的安全类型代替:
+// 1. 简单的开关和计数器: AtomicBool / AtomicU64, 它们的构造函数是 const fn, 可以直接放在 static 中
+// 2. 只初始化一次的值: OnceLock, 第一次使用时才初始化, 也就是 lazy_static 做的事
+// 3. 读多写少的值: RwLock, 允许多个线程同时读, 写的时候独占
+// 这里把它们组合成一个注册表 Registry, 用带类型的 Key 存取任意类型的配置项:
+// static VERBOSE: Key<bool> = Key::new("verbose", || false);
+// global().set(&VERBOSE, true);
+// assert!(global().get(&VERBOSE));
+// Key 本身是普通的 static, 里面只有名字和默认值, 取出来的值的类型由 Key 的类型参数决定,
+// 不需要调用方做类型转换。
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+
+/// 带类型的键, 值的类型为 T, 没有设置过时取默认值
+pub struct Key<T: 'static> {
+    name: &'static str,
+    default: fn() -> T,
+}
+
+impl<T> Key<T> {
+    /// const fn, 所以可以用来初始化 static
+    pub const fn new(name: &'static str, default: fn() -> T) -> Key<T> {
+        Key { name, default }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> fmt::Debug for Key<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Key<{}>({:?})", std::any::type_name::<T>(), self.name)
+    }
+}
+
+type Value = Arc<dyn Any + Send + Sync>;
+type InitCell = Arc<OnceLock<Value>>;
+
+/// 配置注册表
+/// 普通的值放在 RwLock<HashMap> 中, 只在读写 HashMap 的那一刻持有锁, 不会在持有锁的时候调用
+/// 调用方的闭包; 开关和计数器在第一次使用时分配, 交给调用方的是 Arc,
+/// 以后的读写都是无锁的原子操作, 注册表释放之后它们也跟着释放
+#[derive(Default)]
+pub struct Registry {
+    values: RwLock<HashMap<&'static str, Value>>,
+    // get_or_init 正在初始化的键, 每个键一个 OnceLock
+    inits: Mutex<HashMap<&'static str, InitCell>>,
+    flags: RwLock<HashMap<&'static str, Arc<AtomicBool>>>,
+    counters: RwLock<HashMap<&'static str, Arc<AtomicU64>>>,
+}
+
+// 某个线程在持有锁的时候 panic 会让锁"中毒"。
+// 注册表中的值总是整体替换的, 不会留下修改了一半的状态, 所以可以忽略中毒标记继续使用
+macro_rules! read_lock {
+    ($lock:expr) => {
+        $lock.read().unwrap_or_else(PoisonError::into_inner)
+    };
+}
+
+macro_rules! lock {
+    ($lock:expr) => {
+        $lock.lock().unwrap_or_else(PoisonError::into_inner)
+    };
+}
+
+macro_rules! write_lock {
+    ($lock:expr) => {
+        $lock.write().unwrap_or_else(PoisonError::into_inner)
+    };
+}
+
+// 同一个名字被不同类型的 Key 使用是程序的错误, 直接 panic
+fn downcast<T: Any + Send + Sync>(key: &Key<T>, value: &Value) -> Arc<T> {
+    value.clone().downcast::<T>().unwrap_or_else(|_| {
+        panic!(
+            "config key {:?} holds a value of another type, not {}",
+            key.name,
+            std::any::type_name::<T>()
+        )
+    })
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// 读取一个值, 没有设置过时返回默认值
+    pub fn get<T: Any + Send + Sync + Clone>(&self, key: &Key<T>) -> T {
+        match self.get_arc(key) {
+            Some(v) => (*v).clone(),
+            None => (key.default)(),
+        }
+    }
+
+    /// 读取一个值但不复制它, 适合比较大的值
+    pub fn get_arc<T: Any + Send + Sync>(&self, key: &Key<T>) -> Option<Arc<T>> {
+        read_lock!(self.values)
+            .get(key.name)
+            .map(|v| downcast(key, v))
+    }
+
+    pub fn is_set<T>(&self, key: &Key<T>) -> bool {
+        read_lock!(self.values).contains_key(key.name)
+    }
+
+    pub fn set<T: Any + Send + Sync>(&self, key: &Key<T>, value: T) {
+        write_lock!(self.values).insert(key.name, Arc::new(value));
+    }
+
+    /// 恢复成默认值
+    pub fn reset<T>(&self, key: &Key<T>) {
+        lock!(self.inits).remove(key.name);
+        write_lock!(self.values).remove(key.name);
+    }
+
+    /// 读取-修改-写回, 不会丢失其他线程的修改
+    /// f 在锁外执行, 所以可以读取注册表中的其他键; 写回时如果发现值已经被其他线程换掉了,
+    /// 就基于新的值重新执行 f, 因此 f 可能执行不止一次, 除了修改 value 之外不应该有副作用
+    pub fn update<T, R, F>(&self, key: &Key<T>, mut f: F) -> R
+    where
+        T: Any + Send + Sync + Clone,
+        F: FnMut(&mut T) -> R,
+    {
+        loop {
+            let current = read_lock!(self.values).get(key.name).cloned();
+            let mut value = match &current {
+                Some(v) => (*downcast(key, v)).clone(),
+                None => (key.default)(),
+            };
+            let r = f(&mut value);
+            let mut values = write_lock!(self.values);
+            // current 还被我们持有, 它的地址不会被复用, 比较指针就能知道值有没有被换掉
+            let unchanged = match (values.get(key.name), &current) {
+                (Some(now), Some(old)) => Arc::ptr_eq(now, old),
+                (None, None) => true,
+                _ => false,
+            };
+            if unchanged {
+                values.insert(key.name, Arc::new(value));
+                return r;
+            }
+        }
+    }
+
+    /// 一次性初始化: 只有第一次调用成功, 之后的调用把值原样退回
+    pub fn init<T: Any + Send + Sync>(&self, key: &Key<T>, value: T) -> Result<(), T> {
+        let mut values = write_lock!(self.values);
+        if values.contains_key(key.name) {
+            return Err(value);
+        }
+        values.insert(key.name, Arc::new(value));
+        Ok(())
+    }
+
+    /// 第一次读取时才计算, 多个线程同时调用时 f 也只会执行一次
+    /// 每个键有自己的 OnceLock, f 在注册表的锁外执行: 它可以读取其他键,
+    /// 执行得慢也只会让等待同一个键的线程等待; 但 f 不能对同一个键再调用 get_or_init
+    pub fn get_or_init<T, F>(&self, key: &Key<T>, f: F) -> Arc<T>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        // 先只加读锁检查, 绝大多数时候值已经存在
+        if let Some(v) = self.get_arc(key) {
+            return v;
+        }
+        let cell = lock!(self.inits).entry(key.name).or_default().clone();
+        let v = cell.get_or_init(|| Arc::new(f()) as Value).clone();
+        // 插入时再检查一次, 初始化的时候可能已经有其他线程 set 过了
+        let v = write_lock!(self.values)
+            .entry(key.name)
+            .or_insert(v)
+            .clone();
+        // 值已经在 values 中, 以后走上面的快速路径, OnceLock 可以丢掉了
+        let mut inits = lock!(self.inits);
+        if inits.get(key.name).is_some_and(|c| Arc::ptr_eq(c, &cell)) {
+            inits.remove(key.name);
+        }
+        downcast(key, &v)
+    }
+
+    /// 按名字取得一个开关, 第一次使用时创建, 初始值为 false
+    pub fn flag(&self, name: &'static str) -> Arc<AtomicBool> {
+        if let Some(f) = read_lock!(self.flags).get(name) {
+            return f.clone();
+        }
+        write_lock!(self.flags).entry(name).or_default().clone()
+    }
+
+    /// 按名字取得一个计数器, 第一次使用时创建, 初始值为 0
+    pub fn counter(&self, name: &'static str) -> Arc<AtomicU64> {
+        if let Some(c) = read_lock!(self.counters).get(name) {
+            return c.clone();
+        }
+        write_lock!(self.counters).entry(name).or_default().clone()
+    }
+
+    /// 所有已经设置过的键, 按名字排序
+    pub fn keys(&self) -> Vec<&'static str> {
+        let mut keys: Vec<_> = read_lock!(self.values).keys().copied().collect();
+        keys.sort_unstable();
+        keys
+    }
+}
+
+/// 全局的注册表, 第一次调用时创建
+pub fn global() -> &'static Registry {
+    static GLOBAL: OnceLock<Registry> = OnceLock::new();
+    GLOBAL.get_or_init(Registry::new)
+}
+
+pub static VERBOSE: Key<bool> = Key::new("verbose", || false);
+pub static NAME: Key<String> = Key::new("name", || "dive-into-rust".to_string());
+pub static LEVELS: Key<Vec<u32>> = Key::new("levels", Vec::new);
+pub static STARTED_BY: Key<usize> = Key::new("started_by", || usize::MAX);
+pub static ANSWER: Key<u64> = Key::new("answer", || 0);
+
+pub fn learn_global_registry() {
+    let registry = global();
+    // 全局状态在整个进程中只有一份, 先恢复默认值, 这一课才能重复运行
+    registry.reset(&VERBOSE);
+    registry.reset(&NAME);
+    registry.reset(&LEVELS);
+    registry.reset(&STARTED_BY);
+    registry.reset(&ANSWER);
+    registry.counter("requests").store(0, Ordering::Relaxed);
+    registry
+        .counter("answer_computed")
+        .store(0, Ordering::Relaxed);
+
+    // 代替 first::use_global 中的 static mut G2, 不需要 unsafe
+    static G2: Key<i32> = Key::new("g2", || 4);
+    registry.reset(&G2);
+    assert_eq!(registry.get(&G2), 4);
+    registry.set(&G2, 5);
+    assert_eq!(registry.get(&G2), 5);
+
+    assert!(!registry.get(&VERBOSE));
+    registry.set(&VERBOSE, true);
+    assert!(registry.get(&VERBOSE));
+    assert_eq!(registry.get(&NAME), "dive-into-rust");
+
+    // 很多线程同时修改
+    const THREADS: usize = 8;
+    const ROUNDS: usize = 1000;
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            std::thread::spawn(move || {
+                let registry = global();
+                // 一次性初始化, 只有一个线程能成功
+                let won = registry.init(&STARTED_BY, t).is_ok();
+                // 计数器是无锁的
+                let counter = registry.counter("requests");
+                for i in 0..ROUNDS {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    if i % 100 == 0 {
+                        // update 写回时检查值有没有被换掉, 不会丢失其他线程的修改
+                        registry.update(&LEVELS, |v| v.push(t as u32));
+                    }
+                    // 读多写少: 大量的读和少量的写同时进行
+                    let name = registry.get_arc(&NAME);
+                    if i == ROUNDS / 2 {
+                        registry.set(&NAME, format!("thread-{}", t));
+                    }
+                    assert!(name.is_none_or(|n| !n.is_empty()));
+                }
+                registry.flag("done").store(true, Ordering::Release);
+                // get_or_init 的初始化函数在所有线程中只会执行一次
+                let expensive = registry.get_or_init(&ANSWER, || {
+                    registry
+                        .counter("answer_computed")
+                        .fetch_add(1, Ordering::Relaxed);
+                    42
+                });
+                assert_eq!(*expensive, 42);
+                won
+            })
+        })
+        .collect();
+    let winners = handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .filter(|&won| won)
+        .count();
+
+    assert_eq!(winners, 1);
+    assert!(registry.get(&STARTED_BY) < THREADS);
+    assert_eq!(
+        registry.counter("requests").load(Ordering::Relaxed),
+        (THREADS * ROUNDS) as u64
+    );
+    assert_eq!(registry.get(&LEVELS).len(), THREADS * ROUNDS / 100);
+    assert_eq!(
+        registry.counter("answer_computed").load(Ordering::Relaxed),
+        1
+    );
+    assert!(registry.flag("done").load(Ordering::Acquire));
+    assert!(registry.get(&NAME).starts_with("thread-"));
+
+    // 初始化函数和 update 的闭包在锁外执行, 可以读取注册表中的其他键, 不会死锁
+    static GREETING: Key<String> = Key::new("greeting", String::new);
+    let local = Registry::new();
+    local.set(&NAME, "local".to_string());
+    let greeting = local.get_or_init(&GREETING, || format!("hello, {}", local.get(&NAME)));
+    assert_eq!(*greeting, "hello, local");
+    local.update(&LEVELS, |v| v.push(local.get(&NAME).len() as u32));
+    assert_eq!(local.get(&LEVELS), [5]);
+    // 不是全局的注册表中的计数器随最后一个 Arc 一起释放, 不会泄漏
+    let hits = local.counter("hits");
+    hits.fetch_add(1, Ordering::Relaxed);
+    assert_eq!(local.counter("hits").load(Ordering::Relaxed), 1);
+    drop(local);
+    assert_eq!(Arc::strong_count(&hits), 1);
+
+    println!(
+        "keys {:?}, started by thread {}, name {:?}",
+        registry.keys(),
+        registry.get(&STARTED_BY),
+        registry.get(&NAME)
+    );
+}
@@ -0,0 +1,545 @@
+#![allow(dead_code)]
+
+// 单位量纲
+// third::learn_tuple_struct 里的 struct Inches(i32) 说明了 newtype 可以防止把不同含义的值混用,
+// 而 first::type_alias 里的 type Age = u32 只是起了个别名, Age 和 u32 依然可以随便混着用。
+// 这里把 newtype 用到物理量上: 长度、质量、时间、温度各是一个类型,
+// 只有量纲上说得通的运算才会有对应的运算符重载, 比如 长度 / 时间 = 速度,
+// 而 长度 + 质量 根本找不到 impl, 在编译阶段就会报错。
+// 每个类型内部统一用国际单位制(SI)存储, 不同单位制之间的换算必须显式地写出来。
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// 同一种量之间的加减、与标量的乘除、比较和格式化都是一样的, 用宏一次生成
+macro_rules! quantity {
+    ($(#[$meta:meta])* $name:ident, $si:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Default)]
+        pub struct $name(f64);
+
+        impl $name {
+            /// 国际单位制下的数值
+            pub fn si(self) -> f64 {
+                self.0
+            }
+
+            pub fn abs(self) -> $name {
+                $name(self.0.abs())
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $name(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = $name;
+            fn mul(self, rhs: f64) -> $name {
+                $name(self.0 * rhs)
+            }
+        }
+
+        impl Mul<$name> for f64 {
+            type Output = $name;
+            fn mul(self, rhs: $name) -> $name {
+                $name(self * rhs.0)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = $name;
+            fn div(self, rhs: f64) -> $name {
+                $name(self.0 / rhs)
+            }
+        }
+
+        // 同量纲相除, 得到的是一个没有单位的比值
+        impl Div for $name {
+            type Output = f64;
+            fn div(self, rhs: $name) -> f64 {
+                self.0 / rhs.0
+            }
+        }
+
+        impl std::iter::Sum for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> $name {
+                $name(iter.map(|q| q.0).sum())
+            }
+        }
+
+        // 默认用国际单位输出, 支持 {:.2} 这样的精度控制
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match f.precision() {
+                    Some(p) => write!(f, "{:.*} {}", p, self.0, $si),
+                    None => write!(f, "{} {}", self.0, $si),
+                }
+            }
+        }
+    };
+}
+
+// 两个量相乘/相除得到第三个量
+macro_rules! derived {
+    ($a:ident * $b:ident = $c:ident) => {
+        impl Mul<$b> for $a {
+            type Output = $c;
+            fn mul(self, rhs: $b) -> $c {
+                $c(self.0 * rhs.0)
+            }
+        }
+    };
+    ($a:ident / $b:ident = $c:ident) => {
+        impl Div<$b> for $a {
+            type Output = $c;
+            fn div(self, rhs: $b) -> $c {
+                $c(self.0 / rhs.0)
+            }
+        }
+    };
+}
+
+quantity!(
+    /// 长度, 内部以米存储
+    Length,
+    "m"
+);
+quantity!(
+    /// 面积, 内部以平方米存储
+    Area,
+    "m²"
+);
+quantity!(
+    /// 质量, 内部以千克存储
+    Mass,
+    "kg"
+);
+quantity!(
+    /// 时间, 内部以秒存储
+    Time,
+    "s"
+);
+quantity!(
+    /// 速度, 内部以米每秒存储
+    Velocity,
+    "m/s"
+);
+quantity!(
+    /// 加速度, 内部以米每二次方秒存储
+    Acceleration,
+    "m/s²"
+);
+quantity!(
+    /// 温差, 内部以开尔文存储
+    /// 温差可以相加, 而温度本身不行: 20℃ + 30℃ 没有物理意义
+    TemperatureDelta,
+    "K"
+);
+
+derived!(Length * Length = Area);
+derived!(Area / Length = Length);
+derived!(Length / Time = Velocity);
+derived!(Velocity * Time = Length);
+derived!(Time * Velocity = Length);
+derived!(Length / Velocity = Time);
+derived!(Velocity / Time = Acceleration);
+derived!(Acceleration * Time = Velocity);
+
+/// 长度单位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthUnit {
+    Millimeter,
+    Meter,
+    Kilometer,
+    Inch,
+    Foot,
+    Mile,
+}
+
+impl LengthUnit {
+    fn meters(self) -> f64 {
+        match self {
+            LengthUnit::Millimeter => 0.001,
+            LengthUnit::Meter => 1.0,
+            LengthUnit::Kilometer => 1000.0,
+            LengthUnit::Inch => 0.0254,
+            LengthUnit::Foot => 0.3048,
+            LengthUnit::Mile => 1609.344,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            LengthUnit::Millimeter => "mm",
+            LengthUnit::Meter => "m",
+            LengthUnit::Kilometer => "km",
+            LengthUnit::Inch => "in",
+            LengthUnit::Foot => "ft",
+            LengthUnit::Mile => "mi",
+        }
+    }
+}
+
+/// 质量单位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MassUnit {
+    Gram,
+    Kilogram,
+    Tonne,
+    Ounce,
+    Pound,
+}
+
+impl MassUnit {
+    fn kilograms(self) -> f64 {
+        match self {
+            MassUnit::Gram => 0.001,
+            MassUnit::Kilogram => 1.0,
+            MassUnit::Tonne => 1000.0,
+            MassUnit::Ounce => 0.028_349_523_125,
+            MassUnit::Pound => 0.453_592_37,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            MassUnit::Gram => "g",
+            MassUnit::Kilogram => "kg",
+            MassUnit::Tonne => "t",
+            MassUnit::Ounce => "oz",
+            MassUnit::Pound => "lb",
+        }
+    }
+}
+
+/// 时间单位
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    /// 按 365 天计算
+    Year,
+}
+
+impl TimeUnit {
+    fn seconds(self) -> f64 {
+        match self {
+            TimeUnit::Second => 1.0,
+            TimeUnit::Minute => 60.0,
+            TimeUnit::Hour => 3600.0,
+            TimeUnit::Day => 86400.0,
+            TimeUnit::Year => 365.0 * 86400.0,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            TimeUnit::Second => "s",
+            TimeUnit::Minute => "min",
+            TimeUnit::Hour => "h",
+            TimeUnit::Day => "d",
+            TimeUnit::Year => "yr",
+        }
+    }
+}
+
+/// 温度单位
+/// 温度换算不是简单的乘法, 摄氏度和华氏度的零点都和开尔文不一样
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Kelvin,
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    // 开尔文 = 数值 * scale + offset
+    fn scale(self) -> f64 {
+        match self {
+            TemperatureUnit::Kelvin | TemperatureUnit::Celsius => 1.0,
+            TemperatureUnit::Fahrenheit => 5.0 / 9.0,
+        }
+    }
+
+    fn offset(self) -> f64 {
+        match self {
+            TemperatureUnit::Kelvin => 0.0,
+            TemperatureUnit::Celsius => 273.15,
+            TemperatureUnit::Fahrenheit => 273.15 - 32.0 * 5.0 / 9.0,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Kelvin => "K",
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+}
+
+/// 换算到某个单位之后的数值, 只用于输出
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InUnit {
+    pub value: f64,
+    pub symbol: &'static str,
+}
+
+impl fmt::Display for InUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.precision() {
+            Some(p) => write!(f, "{:.*} {}", p, self.value, self.symbol),
+            None => write!(f, "{} {}", self.value, self.symbol),
+        }
+    }
+}
+
+impl Length {
+    pub fn new(value: f64, unit: LengthUnit) -> Length {
+        Length(value * unit.meters())
+    }
+
+    pub fn meters(value: f64) -> Length {
+        Length(value)
+    }
+
+    pub fn inches(value: f64) -> Length {
+        Length::new(value, LengthUnit::Inch)
+    }
+
+    pub fn feet(value: f64) -> Length {
+        Length::new(value, LengthUnit::Foot)
+    }
+
+    pub fn get(self, unit: LengthUnit) -> f64 {
+        self.0 / unit.meters()
+    }
+
+    pub fn to(self, unit: LengthUnit) -> InUnit {
+        InUnit {
+            value: self.get(unit),
+            symbol: unit.symbol(),
+        }
+    }
+}
+
+impl Mass {
+    pub fn new(value: f64, unit: MassUnit) -> Mass {
+        Mass(value * unit.kilograms())
+    }
+
+    pub fn kilograms(value: f64) -> Mass {
+        Mass(value)
+    }
+
+    pub fn get(self, unit: MassUnit) -> f64 {
+        self.0 / unit.kilograms()
+    }
+
+    pub fn to(self, unit: MassUnit) -> InUnit {
+        InUnit {
+            value: self.get(unit),
+            symbol: unit.symbol(),
+        }
+    }
+}
+
+impl Time {
+    pub fn new(value: f64, unit: TimeUnit) -> Time {
+        Time(value * unit.seconds())
+    }
+
+    pub fn seconds(value: f64) -> Time {
+        Time(value)
+    }
+
+    pub fn get(self, unit: TimeUnit) -> f64 {
+        self.0 / unit.seconds()
+    }
+
+    pub fn to(self, unit: TimeUnit) -> InUnit {
+        InUnit {
+            value: self.get(unit),
+            symbol: unit.symbol(),
+        }
+    }
+}
+
+impl Velocity {
+    pub fn new(length: f64, lu: LengthUnit, time: f64, tu: TimeUnit) -> Velocity {
+        Length::new(length, lu) / Time::new(time, tu)
+    }
+
+    pub fn get(self, lu: LengthUnit, tu: TimeUnit) -> f64 {
+        self.0 / lu.meters() * tu.seconds()
+    }
+}
+
+/// 温度, 内部以开尔文存储
+/// 温度是一个"点", 温差是一个"向量":
+/// 温度 - 温度 = 温差, 温度 + 温差 = 温度, 但 温度 + 温度 没有定义
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct Temperature(f64);
+
+/// 低于绝对零度的温度, NaN 也算在内
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BelowAbsoluteZero(pub f64);
+
+impl fmt::Display for BelowAbsoluteZero {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} K is below absolute zero", self.0)
+    }
+}
+
+impl Temperature {
+    pub fn new(value: f64, unit: TemperatureUnit) -> Result<Temperature, BelowAbsoluteZero> {
+        Temperature::from_kelvin(value * unit.scale() + unit.offset())
+    }
+
+    // 所有构造温度的地方都经过这里, 保证不低于绝对零度
+    fn from_kelvin(kelvin: f64) -> Result<Temperature, BelowAbsoluteZero> {
+        // NaN 与任何数比较都是 false, 只写 kelvin < 0.0 会放过它
+        if kelvin.is_nan() || kelvin < 0.0 {
+            Err(BelowAbsoluteZero(kelvin))
+        } else {
+            Ok(Temperature(kelvin))
+        }
+    }
+
+    pub fn celsius(value: f64) -> Result<Temperature, BelowAbsoluteZero> {
+        Temperature::new(value, TemperatureUnit::Celsius)
+    }
+
+    pub fn kelvin(self) -> f64 {
+        self.0
+    }
+
+    pub fn get(self, unit: TemperatureUnit) -> f64 {
+        (self.0 - unit.offset()) / unit.scale()
+    }
+
+    pub fn to(self, unit: TemperatureUnit) -> InUnit {
+        InUnit {
+            value: self.get(unit),
+            symbol: unit.symbol(),
+        }
+    }
+}
+
+impl Sub for Temperature {
+    type Output = TemperatureDelta;
+    fn sub(self, rhs: Temperature) -> TemperatureDelta {
+        TemperatureDelta(self.0 - rhs.0)
+    }
+}
+
+/// 温度加上一个负的温差可能低于绝对零度, 所以结果是 Result
+impl Add<TemperatureDelta> for Temperature {
+    type Output = Result<Temperature, BelowAbsoluteZero>;
+    fn add(self, rhs: TemperatureDelta) -> Result<Temperature, BelowAbsoluteZero> {
+        Temperature::from_kelvin(self.0 + rhs.0)
+    }
+}
+
+impl Sub<TemperatureDelta> for Temperature {
+    type Output = Result<Temperature, BelowAbsoluteZero>;
+    fn sub(self, rhs: TemperatureDelta) -> Result<Temperature, BelowAbsoluteZero> {
+        Temperature::from_kelvin(self.0 - rhs.0)
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.precision() {
+            Some(p) => write!(f, "{:.*} K", p, self.0),
+            None => write!(f, "{} K", self.0),
+        }
+    }
+}
+
+pub fn learn_units() {
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * a.abs().max(1.0)
+    }
+
+    // 单位换算必须显式写出来
+    let height = Length::feet(6.0);
+    println!("{:.4} = {:.2}", height, height.to(LengthUnit::Inch));
+    assert!(close(height.get(LengthUnit::Inch), 72.0));
+
+    // 量纲正确的运算
+    let distance = Length::new(42.195, LengthUnit::Kilometer);
+    let time = Time::new(2.0, TimeUnit::Hour) + Time::new(1.0, TimeUnit::Minute);
+    let speed: Velocity = distance / time;
+    println!(
+        "{} / {:.0} = {:.3} = {:.2} km/h",
+        distance,
+        time,
+        speed,
+        speed.get(LengthUnit::Kilometer, TimeUnit::Hour)
+    );
+    assert!(close(speed * time / distance, 1.0));
+
+    let room: Area = Length::meters(4.0) * Length::meters(3.5);
+    println!("room {}", room);
+    assert!(close((room / Length::meters(4.0)).si(), 3.5));
+
+    // 量纲不对的运算在编译阶段就会报错
+    // let wrong = distance + time; // 编译错误: Length 没有实现 Add<Time>
+    // let wrong = distance * Mass::kilograms(1.0); // 编译错误
+    // let x: f64 = distance; // 编译错误, 也不能悄悄地退化成 f64
+
+    let total: Mass = vec![
+        Mass::new(1.0, MassUnit::Pound),
+        Mass::new(500.0, MassUnit::Gram),
+    ]
+    .into_iter()
+    .sum();
+    println!("{:.3} = {:.3}", total, total.to(MassUnit::Ounce));
+
+    // 温度与温差
+    let morning = Temperature::celsius(12.0).unwrap();
+    let noon = Temperature::new(77.0, TemperatureUnit::Fahrenheit).unwrap();
+    let delta = noon - morning;
+    println!(
+        "{:.1} -> {:.1}, delta {:.1}",
+        morning.to(TemperatureUnit::Celsius),
+        noon.to(TemperatureUnit::Celsius),
+        delta
+    );
+    assert!(close(noon.get(TemperatureUnit::Celsius), 25.0));
+    assert!(close((morning + delta).unwrap().kelvin(), noon.kelvin()));
+    assert!(close((noon - delta).unwrap().kelvin(), morning.kelvin()));
+    // 运算的结果同样不能低于绝对零度
+    let zero = Temperature::celsius(0.0).unwrap();
+    assert!((zero - TemperatureDelta(500.0)).is_err());
+    assert!((zero + TemperatureDelta(-500.0)).is_err());
+    // let wrong = morning + noon; // 编译错误: 两个温度不能相加
+    assert!(Temperature::celsius(-300.0).is_err());
+    assert!(Temperature::celsius(f64::NAN).is_err());
+    assert!((zero + TemperatureDelta(f64::NAN)).is_err());
+
+    // 与 type Age = u32 对比: 别名不会阻止混用, newtype 会
+    let age = Time::new(20.0, TimeUnit::Year);
+    let later = age + Time::new(20.0, TimeUnit::Year);
+    println!("20 years later: {:.0}", later.to(TimeUnit::Year));
+}
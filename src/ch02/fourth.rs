@@ -0,0 +1,558 @@
+#![allow(dead_code)]
+
+// # 数值类型转换
+// second::type_case 只演示了 `i8 as i16` 这种安全的转换。
+// 数值类型之间的 as 转换有几种完全不同的语义:
+// 1. 整数变宽: 无符号数补 0(zero-extend), 有符号数补符号位(sign-extend)
+// 2. 整数变窄: 直接丢弃高位(truncate)
+// 3. 同宽度的有/无符号互转: 位模式不变, 只是换一种方式解读(reinterpret)
+// 4. 整数转浮点: 就近舍入, 超出尾数位数的部分会丢失精度
+// 5. 浮点转整数: 向 0 截断, 超出范围的值饱和到 MIN/MAX, NaN 变成 0(Rust 1.45 起)
+// 6. f64 转 f32: 就近舍入, 超出范围变成 inf
+// as 永远不会失败, 也永远不会 panic, 所以它可能悄悄地丢失数据。
+// 标准库用 From 表示"一定不丢数据"的转换, 用 TryFrom 表示"可能失败, 失败时返回 Err"的转换。
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// 所有的原生数值类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    F32,
+    F64,
+}
+
+/// 一个带类型的数值
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Num {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    Isize(isize),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Usize(usize),
+    F32(f32),
+    F64(f64),
+}
+
+// 用一个宏把"所有数值类型"这张表只写一次, 其他宏通过回调的方式使用它
+macro_rules! with_num_types {
+    ($cb:ident! $($args:tt)*) => {
+        $cb! {
+            $($args)*
+            [
+                (I8, i8) (I16, i16) (I32, i32) (I64, i64) (I128, i128) (Isize, isize)
+                (U8, u8) (U16, u16) (U32, u32) (U64, u64) (U128, u128) (Usize, usize)
+                (F32, f32) (F64, f64)
+            ]
+        }
+    };
+}
+
+// 对 (源类型, 目标类型) 的每一种组合展开一次 $arm!
+// 外层 match 源数值的类型, 内层 match 目标类型, 一共 14 x 14 个分支
+// 每个分支里的类型都是具体类型, 这样才能直接写 as, 也才能用下面的 autoref 技巧探测 trait
+macro_rules! cross {
+    ($arm:ident, $value:expr, $to:expr, [$(($k:ident, $t:ty))*]) => {
+        cross!(@src $arm, $value, $to, [$(($k, $t))*], [$(($k, $t))*])
+    };
+    (@src $arm:ident, $value:expr, $to:expr, [$(($k:ident, $t:ty))*], $all:tt) => {
+        match $value {
+            $(Num::$k(v) => cross!(@dst $arm, v, $t, $to, $all),)*
+        }
+    };
+    (@dst $arm:ident, $v:ident, $s:ty, $to:expr, [$(($k:ident, $t:ty))*]) => {
+        match $to {
+            $(NumKind::$k => $arm!($v, $s, $t, $k),)*
+        }
+    };
+}
+
+macro_rules! impl_kind_table {
+    ([$(($k:ident, $t:ty))*]) => {
+        impl NumKind {
+            pub const ALL: [NumKind; 14] = [$(NumKind::$k),*];
+
+            /// 类型名, 如 "i32"
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(NumKind::$k => stringify!($t),)*
+                }
+            }
+
+            /// 占用的 bit 数, isize/usize 与平台相关
+            pub fn bits(self) -> u32 {
+                match self {
+                    $(NumKind::$k => (std::mem::size_of::<$t>() * 8) as u32,)*
+                }
+            }
+        }
+
+        impl Num {
+            pub fn kind(self) -> NumKind {
+                match self {
+                    $(Num::$k(_) => NumKind::$k,)*
+                }
+            }
+
+            /// 某个类型的 0 值, 用来在只知道类型的时候驱动 cross! 宏
+            pub fn zero(kind: NumKind) -> Num {
+                match kind {
+                    $(NumKind::$k => Num::$k(0 as $t),)*
+                }
+            }
+        }
+
+        impl fmt::Display for Num {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    $(Num::$k(v) => write!(f, "{:?}_{}", v, stringify!($t)),)*
+                }
+            }
+        }
+    };
+}
+
+with_num_types!(impl_kind_table!);
+
+impl NumKind {
+    pub fn is_float(self) -> bool {
+        matches!(self, NumKind::F32 | NumKind::F64)
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            NumKind::I8
+                | NumKind::I16
+                | NumKind::I32
+                | NumKind::I64
+                | NumKind::I128
+                | NumKind::Isize
+                | NumKind::F32
+                | NumKind::F64
+        )
+    }
+
+    /// 浮点数能精确表示的整数位数(包含隐含的那一位)
+    fn mantissa_digits(self) -> u32 {
+        match self {
+            NumKind::F32 => f32::MANTISSA_DIGITS,
+            NumKind::F64 => f64::MANTISSA_DIGITS,
+            _ => self.bits(),
+        }
+    }
+
+    /// 整数类型表示"数值大小"的 bit 数, 有符号类型要扣掉符号位
+    fn magnitude_bits(self) -> u32 {
+        if self.is_signed() {
+            self.bits() - 1
+        } else {
+            self.bits()
+        }
+    }
+}
+
+impl fmt::Display for NumKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// as 转换在类型层面的语义
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CastEffect {
+    /// 同一个类型, 什么也不做
+    Identity,
+    /// 整数变宽, 补 0 或补符号位, 不丢数据
+    Extend,
+    /// 整数变窄, 丢弃高位
+    Truncate,
+    /// 同宽度整数有/无符号互转, 位模式不变
+    Reinterpret,
+    /// 整数转浮点或 f64 转 f32, 就近舍入
+    Round,
+    /// f32 转 f64, 精确
+    Widen,
+    /// 浮点转整数, 向 0 截断并饱和, NaN 变为 0
+    Saturate,
+}
+
+impl CastEffect {
+    pub fn of(from: NumKind, to: NumKind) -> CastEffect {
+        use CastEffect::*;
+        if from == to {
+            return Identity;
+        }
+        match (from.is_float(), to.is_float()) {
+            (true, false) => Saturate,
+            (true, true) => {
+                if from.bits() < to.bits() {
+                    Widen
+                } else {
+                    Round
+                }
+            }
+            // 整数转浮点, 尾数放得下就是精确的
+            (false, true) => {
+                if from.magnitude_bits() <= to.mantissa_digits() {
+                    Widen
+                } else {
+                    Round
+                }
+            }
+            (false, false) => {
+                if from.bits() > to.bits() {
+                    Truncate
+                } else if from.is_signed() && !to.is_signed() {
+                    // i8 -> u16: 先补符号位, 负数会变成很大的正数
+                    Reinterpret
+                } else if from.bits() == to.bits() && from.is_signed() != to.is_signed() {
+                    Reinterpret
+                } else {
+                    // 包括 isize -> i64 这种在当前平台上宽度相同的情况
+                    Extend
+                }
+            }
+        }
+    }
+
+    /// 这种转换是否可能改变数值
+    pub fn can_lose_data(self) -> bool {
+        !matches!(
+            self,
+            CastEffect::Identity | CastEffect::Extend | CastEffect::Widen
+        )
+    }
+
+    pub fn explain(self) -> &'static str {
+        match self {
+            CastEffect::Identity => "同类型, 原样返回",
+            CastEffect::Extend => "整数变宽: 无符号补 0, 有符号补符号位, 数值不变",
+            CastEffect::Truncate => "整数变窄: 直接丢弃高位, 超出范围的值会回绕",
+            CastEffect::Reinterpret => "有/无符号互转: 位模式不变, 负数会变成大正数, 反之亦然",
+            CastEffect::Round => "就近舍入: 超出尾数精度的部分丢失, 超出范围变为 inf",
+            CastEffect::Widen => "精确转换: 目标类型能表示源类型的所有值",
+            CastEffect::Saturate => "浮点转整数: 向 0 截断, 越界饱和到 MIN/MAX, NaN 变为 0",
+        }
+    }
+}
+
+/// 标准库为这一对类型提供了哪种转换 trait
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StdConversion {
+    /// impl From<S> for T, 一定成功
+    From,
+    /// 只有 impl TryFrom<S> for T, 可能返回 Err
+    TryFrom,
+    /// 标准库没有提供, 只能用 as
+    AsOnly,
+}
+
+// 利用 autoref 的方法查找顺序来"探测"一个 trait 是否被实现:
+// 对 (&probe).method() 来说, 编译器先找 self 类型为 &Probe 的方法(即 impl for Probe 的 &self 方法),
+// 找不到才会再自动取一次引用, 找 impl for &Probe 的方法。
+// 所以把"实现了 trait"的版本写在 Probe 上, 把兜底的版本写在 &Probe 上,
+// 编译器就会在满足约束时选前者, 不满足时选后者。这个技巧只对具体类型有效, 因此只能在宏里展开使用。
+struct Probe<S, T>(PhantomData<(S, T)>);
+
+trait ViaFrom<S> {
+    fn has_from(&self) -> bool {
+        true
+    }
+}
+impl<S, T: From<S>> ViaFrom<S> for Probe<S, T> {}
+
+trait NoFrom {
+    fn has_from(&self) -> bool {
+        false
+    }
+}
+impl<S, T> NoFrom for &Probe<S, T> {}
+
+trait ViaTryFrom<S, T> {
+    fn try_convert(&self, v: S) -> Option<Result<T, String>>;
+}
+impl<S, T> ViaTryFrom<S, T> for Probe<S, T>
+where
+    T: TryFrom<S>,
+    T::Error: fmt::Display,
+{
+    fn try_convert(&self, v: S) -> Option<Result<T, String>> {
+        Some(T::try_from(v).map_err(|e| e.to_string()))
+    }
+}
+
+trait NoTryFrom<S, T> {
+    fn try_convert(&self, _v: S) -> Option<Result<T, String>> {
+        None
+    }
+}
+impl<S, T> NoTryFrom<S, T> for &Probe<S, T> {}
+
+macro_rules! as_arm {
+    ($v:ident, $s:ty, $t:ty, $k:ident) => {
+        Num::$k($v as $t)
+    };
+}
+
+macro_rules! from_arm {
+    ($v:ident, $s:ty, $t:ty, $k:ident) => {{
+        let _ = $v;
+        (&Probe::<$s, $t>(PhantomData)).has_from()
+    }};
+}
+
+macro_rules! try_from_arm {
+    ($v:ident, $s:ty, $t:ty, $k:ident) => {
+        (&Probe::<$s, $t>(PhantomData))
+            .try_convert($v)
+            .map(|r| r.map(Num::$k))
+    };
+}
+
+/// 计算 `value as to` 的精确结果
+pub fn cast_as(value: Num, to: NumKind) -> Num {
+    macro_rules! go {
+        ($value:expr, $to:expr, $list:tt) => {
+            cross!(as_arm, $value, $to, $list)
+        };
+    }
+    with_num_types!(go! value, to,)
+}
+
+/// 用标准库的 From/TryFrom 做转换, 标准库没有提供对应 trait 时返回 None
+pub fn std_convert(value: Num, to: NumKind) -> Option<Result<Num, String>> {
+    macro_rules! go {
+        ($value:expr, $to:expr, $list:tt) => {
+            cross!(try_from_arm, $value, $to, $list)
+        };
+    }
+    with_num_types!(go! value, to,)
+}
+
+impl StdConversion {
+    pub fn of(from: NumKind, to: NumKind) -> StdConversion {
+        macro_rules! go {
+            ($value:expr, $to:expr, $list:tt) => {
+                cross!(from_arm, $value, $to, $list)
+            };
+        }
+        if with_num_types!(go! Num::zero(from), to,) {
+            StdConversion::From
+        } else if std_convert(Num::zero(from), to).is_some() {
+            StdConversion::TryFrom
+        } else {
+            StdConversion::AsOnly
+        }
+    }
+}
+
+// 比较两个不同类型的数值在数学意义上是否相等
+enum Exact {
+    Int(i128),
+    BigUint(u128),
+    Float(f64),
+}
+
+impl Num {
+    fn exact(self) -> Exact {
+        match self {
+            Num::I8(v) => Exact::Int(v as i128),
+            Num::I16(v) => Exact::Int(v as i128),
+            Num::I32(v) => Exact::Int(v as i128),
+            Num::I64(v) => Exact::Int(v as i128),
+            Num::I128(v) => Exact::Int(v),
+            Num::Isize(v) => Exact::Int(v as i128),
+            Num::U8(v) => Exact::Int(v as i128),
+            Num::U16(v) => Exact::Int(v as i128),
+            Num::U32(v) => Exact::Int(v as i128),
+            Num::U64(v) => Exact::Int(v as i128),
+            Num::U128(v) => match i128::try_from(v) {
+                Ok(i) => Exact::Int(i),
+                Err(_) => Exact::BigUint(v),
+            },
+            Num::Usize(v) => Exact::Int(v as i128),
+            // f32 -> f64 是精确的
+            Num::F32(v) => Exact::Float(v as f64),
+            Num::F64(v) => Exact::Float(v),
+        }
+    }
+
+    /// 数学意义上的相等, NaN 与 NaN 视为相等
+    pub fn same_value(self, other: Num) -> bool {
+        fn int_eq_float(i: &Exact, f: f64) -> bool {
+            // 2^127 以内的整数值浮点数转 i128 是精确的
+            let limit = 2f64.powi(127);
+            if !f.is_finite() || f.fract() != 0.0 {
+                return false;
+            }
+            match *i {
+                Exact::Int(i) => f >= -limit && f < limit && f as i128 == i,
+                Exact::BigUint(u) => f >= limit && f as u128 == u,
+                Exact::Float(_) => unreachable!(),
+            }
+        }
+        match (self.exact(), other.exact()) {
+            (Exact::Float(a), Exact::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Exact::Float(f), i) | (i, Exact::Float(f)) => int_eq_float(&i, f),
+            (Exact::Int(a), Exact::Int(b)) => a == b,
+            (Exact::BigUint(a), Exact::BigUint(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// 对一个具体数值做转换检查的结果
+#[derive(Debug)]
+pub struct CastReport {
+    pub value: Num,
+    pub to: NumKind,
+    pub effect: CastEffect,
+    pub as_result: Num,
+    /// as 的结果是否与原值相等
+    pub preserved: bool,
+    pub std: StdConversion,
+    pub std_result: Option<Result<Num, String>>,
+}
+
+pub fn check(value: Num, to: NumKind) -> CastReport {
+    let as_result = cast_as(value, to);
+    CastReport {
+        value,
+        to,
+        effect: CastEffect::of(value.kind(), to),
+        as_result,
+        preserved: value.same_value(as_result),
+        std: StdConversion::of(value.kind(), to),
+        std_result: std_convert(value, to),
+    }
+}
+
+impl fmt::Display for CastReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} as {} = {}", self.value, self.to, self.as_result)?;
+        writeln!(f, "    {}", self.effect.explain())?;
+        if !self.preserved {
+            writeln!(f, "    注意: 数值发生了变化")?;
+        }
+        match &self.std_result {
+            None => write!(
+                f,
+                "    标准库没有 {} -> {} 的 From/TryFrom",
+                self.value.kind(),
+                self.to
+            ),
+            Some(Ok(v)) => write!(f, "    {:?}: Ok({})", self.std, v),
+            Some(Err(e)) => write!(f, "    {:?}: Err({})", self.std, e),
+        }
+    }
+}
+
+/// 生成所有数值类型两两之间的转换矩阵
+/// 行是源类型, 列是目标类型
+pub fn matrix_report() -> String {
+    use std::fmt::Write;
+    let mut out = String::new();
+    out.push_str("     ");
+    for to in NumKind::ALL.iter() {
+        let _ = write!(out, "{:>6}", to.name());
+    }
+    out.push('\n');
+    for &from in NumKind::ALL.iter() {
+        let _ = write!(out, "{:<5}", from.name());
+        for &to in NumKind::ALL.iter() {
+            let lossy = CastEffect::of(from, to).can_lose_data();
+            let mark = match (StdConversion::of(from, to), lossy) {
+                _ if from == to => "=",
+                (StdConversion::From, _) => "F",
+                (StdConversion::TryFrom, false) => "T",
+                (StdConversion::TryFrom, true) => "t",
+                (StdConversion::AsOnly, false) => "a",
+                (StdConversion::AsOnly, true) => "~",
+            };
+            let _ = write!(out, "{:>6}", mark);
+        }
+        out.push('\n');
+    }
+    out.push_str("= 同类型\n");
+    out.push_str("F 无损, 标准库提供 From\n");
+    out.push_str("T 在当前平台无损, 但标准库只提供 TryFrom (isize/usize 的宽度与平台相关)\n");
+    out.push_str("t 可能丢数据, 标准库提供 TryFrom, 越界时返回 Err\n");
+    out.push_str("a 无损, 但标准库没有提供 From, 只能用 as\n");
+    out.push_str("~ 可能丢数据, 只能用 as\n");
+    out
+}
+
+pub fn learn_cast() {
+    // 变宽: 补符号位
+    let r = check(Num::I8(-1), NumKind::I16);
+    println!("{}", r);
+    assert_eq!(r.as_result, Num::I16(-1));
+    assert_eq!(r.std, StdConversion::From);
+
+    // 变窄: 丢弃高位, 300 = 0b1_0010_1100, 只剩下 0b0010_1100 = 44
+    let r = check(Num::I32(300), NumKind::U8);
+    println!("{}", r);
+    assert_eq!(r.as_result, Num::U8(44));
+    assert!(!r.preserved);
+    assert!(matches!(r.std_result, Some(Err(_))));
+
+    // 同宽度: 位模式不变
+    let r = check(Num::I32(-1), NumKind::U32);
+    println!("{}", r);
+    assert_eq!(r.as_result, Num::U32(u32::MAX));
+
+    // 浮点转整数: 截断, 饱和, NaN
+    assert_eq!(cast_as(Num::F64(3.99), NumKind::U8), Num::U8(3));
+    assert_eq!(cast_as(Num::F64(-1.5), NumKind::U8), Num::U8(0));
+    assert_eq!(cast_as(Num::F64(1e10), NumKind::I32), Num::I32(i32::MAX));
+    assert_eq!(cast_as(Num::F64(f64::NAN), NumKind::I32), Num::I32(0));
+    println!("{}", check(Num::F64(f64::NAN), NumKind::I32));
+
+    // 整数转浮点: 2^24 + 1 在 f32 里放不下
+    let r = check(Num::I32(16_777_217), NumKind::F32);
+    println!("{}", r);
+    assert_eq!(r.as_result, Num::F32(16_777_216.0));
+    assert!(!r.preserved);
+    assert!(r.std_result.is_none());
+    // 而 f64 的尾数足够, 标准库提供了 From
+    assert_eq!(
+        StdConversion::of(NumKind::I32, NumKind::F64),
+        StdConversion::From
+    );
+
+    // 标准库提供 From 的转换, 一定是不会丢数据的
+    for &from in NumKind::ALL.iter() {
+        for &to in NumKind::ALL.iter() {
+            if StdConversion::of(from, to) == StdConversion::From {
+                assert!(
+                    !CastEffect::of(from, to).can_lose_data(),
+                    "{} -> {}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    println!("{}", matrix_report());
+}
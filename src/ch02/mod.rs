@@ -1,3 +1,8 @@
+pub mod eighth;
+pub mod fifth;
 pub mod first;
+pub mod fourth;
 pub mod second;
+pub mod seventh;
+pub mod sixth;
 pub mod third;
@@ -0,0 +1,1072 @@
+#![allow(dead_code)]
+
+// 递归数据结构
+// third::learn_recursive 说明了 struct 不能直接包含自己, 必须通过 Box 这样的指针间接包含,
+// 否则编译器无法确定它的大小。这里用 Box 实现三种常见的递归类型:
+// 1. List<T>: 单向链表
+// 2. BstMap<K, V>: 二叉搜索树
+// 3. Expr: 表达式树
+// 递归类型有一个容易被忽略的问题: 编译器自动生成的析构也是递归的。
+// 销毁链表头的时候会先销毁它的 next, next 又会销毁它的 next ... 链表有多长, 调用栈就有多深,
+// 一个足够长的链表在离开作用域时会让程序栈溢出。
+// 所以这里的每个类型都手动实现了 Drop, 用循环代替递归。
+// 插入、删除和遍历也都是用循环写的, 退化成一条直线的二叉树同样不会栈溢出。
+// Expr 的 Clone、PartialEq 和 Debug 同样是手写的循环, 没有用 derive。
+use std::cell::Cell;
+use std::fmt;
+use std::iter::FromIterator;
+use std::thread;
+
+// ---------- 单向链表 ----------
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    next: Link<T>,
+}
+
+/// 单向链表
+pub struct List<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> List<T> {
+        List { head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let next = self.head.take();
+        self.head = Some(Box::new(Node { value, next }));
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|node| {
+            let node = *node;
+            self.head = node.next;
+            self.len -= 1;
+            node.value
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.value)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.value)
+    }
+
+    // 返回第 index 个 Link 的可变引用, 在它上面就可以做插入和删除
+    fn link_mut(&mut self, index: usize) -> &mut Link<T> {
+        let mut link = &mut self.head;
+        for _ in 0..index {
+            link = &mut link.as_mut().unwrap().next;
+        }
+        link
+    }
+
+    /// 插入到第 index 个位置, index 超过长度时 panic
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len, "insertion index {} out of range", index);
+        let link = self.link_mut(index);
+        let next = link.take();
+        *link = Some(Box::new(Node { value, next }));
+        self.len += 1;
+    }
+
+    /// 删除第 index 个元素
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let link = self.link_mut(index);
+        let node = *link.take().unwrap();
+        *link = node.next;
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.insert(self.len, value);
+    }
+
+    /// 原地反转, 只修改指针, 不移动元素
+    pub fn reverse(&mut self) {
+        let mut prev: Link<T> = None;
+        let mut cur = self.head.take();
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+            node.next = prev;
+            prev = Some(node);
+        }
+        self.head = prev;
+    }
+
+    pub fn iter(&self) -> ListIter<'_, T> {
+        ListIter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> ListIterMut<'_, T> {
+        ListIterMut {
+            next: self.head.as_deref_mut(),
+        }
+    }
+}
+
+// 手动实现的析构: 每次只摘下一个节点, 这个节点的 next 已经是 None, 销毁它不会再递归
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut cur = self.head.take();
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+        }
+    }
+}
+
+pub struct ListIter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for ListIter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.value
+        })
+    }
+}
+
+pub struct ListIterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+}
+
+impl<'a, T> Iterator for ListIterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        // &mut 不能复制, 所以要先 take 出来
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            &mut node.value
+        })
+    }
+}
+
+pub struct ListIntoIter<T>(List<T>);
+
+impl<T> Iterator for ListIntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = ListIntoIter<T>;
+    fn into_iter(self) -> ListIntoIter<T> {
+        ListIntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = ListIter<'a, T>;
+    fn into_iter(self) -> ListIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = ListIterMut<'a, T>;
+    fn into_iter(self) -> ListIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// 按顺序收集, 需要一直持有尾部的 Link
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> List<T> {
+        let mut list = List::new();
+        let mut tail = &mut list.head;
+        for value in iter {
+            *tail = Some(Box::new(Node { value, next: None }));
+            tail = &mut tail.as_mut().unwrap().next;
+            list.len += 1;
+        }
+        list
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// ---------- 二叉搜索树 ----------
+
+type Tree<K, V> = Option<Box<TreeNode<K, V>>>;
+
+struct TreeNode<K, V> {
+    key: K,
+    value: V,
+    left: Tree<K, V>,
+    right: Tree<K, V>,
+}
+
+/// 二叉搜索树实现的有序映射, 不做平衡
+pub struct BstMap<K, V> {
+    root: Tree<K, V>,
+    len: usize,
+}
+
+impl<K: Ord, V> Default for BstMap<K, V> {
+    fn default() -> Self {
+        BstMap::new()
+    }
+}
+
+impl<K: Ord, V> BstMap<K, V> {
+    pub fn new() -> BstMap<K, V> {
+        BstMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // 找到 key 所在的 Link, 不存在时返回它应该被插入的位置(一个 None)
+    fn find_mut(&mut self, key: &K) -> &mut Tree<K, V> {
+        let mut link = &mut self.root;
+        loop {
+            // 先只读地比较, 再取可变引用, 否则借用检查器不允许在循环中返回 link
+            let less = match link.as_deref() {
+                Some(node) if *key != node.key => *key < node.key,
+                _ => return link,
+            };
+            let node = link.as_mut().unwrap();
+            link = if less {
+                &mut node.left
+            } else {
+                &mut node.right
+            };
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            if *key < node.key {
+                cur = node.left.as_deref();
+            } else if *key > node.key {
+                cur = node.right.as_deref();
+            } else {
+                return Some(&node.value);
+            }
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find_mut(key).as_mut().map(|node| &mut node.value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// 插入, key 已经存在时替换并返回旧值
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let link = self.find_mut(&key);
+        match link {
+            Some(node) => Some(std::mem::replace(&mut node.value, value)),
+            None => {
+                *link = Some(Box::new(TreeNode {
+                    key,
+                    value,
+                    left: None,
+                    right: None,
+                }));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// 删除, 有两个子节点时用右子树中最小的节点顶替
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let link = self.find_mut(key);
+        let mut node = link.take()?;
+        *link = match (node.left.take(), node.right.take()) {
+            (None, None) => None,
+            (Some(child), None) | (None, Some(child)) => Some(child),
+            (Some(left), Some(right)) => {
+                let (mut min, rest) = take_min(right);
+                min.left = Some(left);
+                min.right = rest;
+                Some(min)
+            }
+        };
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn iter(&self) -> BstIter<'_, K, V> {
+        let mut it = BstIter { stack: Vec::new() };
+        it.push_left(self.root.as_deref());
+        it
+    }
+
+    pub fn iter_mut(&mut self) -> BstIterMut<'_, K, V> {
+        let mut it = BstIterMut { stack: Vec::new() };
+        it.push_left(self.root.as_deref_mut());
+        it
+    }
+
+    /// 树的高度, 用层序遍历计算, 不递归
+    pub fn height(&self) -> usize {
+        let mut level: Vec<&TreeNode<K, V>> = self.root.as_deref().into_iter().collect();
+        let mut height = 0;
+        while !level.is_empty() {
+            height += 1;
+            level = level
+                .iter()
+                .flat_map(|n| n.left.as_deref().into_iter().chain(n.right.as_deref()))
+                .collect();
+        }
+        height
+    }
+}
+
+// 从子树中摘下最小的节点, 返回它和剩下的子树
+fn take_min<K, V>(mut root: Box<TreeNode<K, V>>) -> (Box<TreeNode<K, V>>, Tree<K, V>) {
+    if root.left.is_none() {
+        let rest = root.right.take();
+        return (root, rest);
+    }
+    let mut link = &mut root.left;
+    while link.as_ref().unwrap().left.is_some() {
+        link = &mut link.as_mut().unwrap().left;
+    }
+    let mut min = link.take().unwrap();
+    *link = min.right.take();
+    (min, Some(root))
+}
+
+// 把整棵树拆成一个个孤立的节点再逐个销毁
+impl<K, V> Drop for BstMap<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<TreeNode<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+/// 中序遍历, 用一个显式的栈代替递归
+pub struct BstIter<'a, K, V> {
+    stack: Vec<&'a TreeNode<K, V>>,
+}
+
+impl<'a, K, V> BstIter<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a TreeNode<K, V>>) {
+        while let Some(n) = node {
+            self.stack.push(n);
+            node = n.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for BstIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+/// 可变的中序遍历
+/// 栈里不能同时保存节点的 &mut 和它左子树的 &mut, 所以先把节点拆成几个字段的借用再入栈
+pub struct BstIterMut<'a, K, V> {
+    stack: Vec<SplitNode<'a, K, V>>,
+}
+
+// 一个节点的 key、value 和右子树
+type SplitNode<'a, K, V> = (&'a K, &'a mut V, Option<&'a mut TreeNode<K, V>>);
+
+impl<'a, K, V> BstIterMut<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a mut TreeNode<K, V>>) {
+        while let Some(n) = node {
+            let TreeNode {
+                key,
+                value,
+                left,
+                right,
+            } = n;
+            self.stack.push((&*key, value, right.as_deref_mut()));
+            node = left.as_deref_mut();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for BstIterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        let (key, value, right) = self.stack.pop()?;
+        self.push_left(right);
+        Some((key, value))
+    }
+}
+
+/// 按 key 从小到大取出所有元素
+pub struct BstIntoIter<K, V> {
+    stack: Vec<Box<TreeNode<K, V>>>,
+}
+
+impl<K, V> BstIntoIter<K, V> {
+    fn push_left(&mut self, mut node: Tree<K, V>) {
+        while let Some(mut n) = node {
+            node = n.left.take();
+            self.stack.push(n);
+        }
+    }
+}
+
+impl<K, V> Iterator for BstIntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        let mut node = self.stack.pop()?;
+        self.push_left(node.right.take());
+        let node = *node;
+        Some((node.key, node.value))
+    }
+}
+
+// 没有遍历完就被丢弃时, 栈里的节点还挂着右子树, 同样要逐个拆开
+impl<K, V> Drop for BstIntoIter<K, V> {
+    fn drop(&mut self) {
+        for _ in self {}
+    }
+}
+
+impl<K: Ord, V> IntoIterator for BstMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = BstIntoIter<K, V>;
+    fn into_iter(mut self) -> BstIntoIter<K, V> {
+        let mut it = BstIntoIter { stack: Vec::new() };
+        it.push_left(self.root.take());
+        it
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BstMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> BstMap<K, V> {
+        let mut map = BstMap::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for BstMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// ---------- 表达式树 ----------
+
+/// 算术表达式树
+/// Clone、PartialEq 和 Debug 都是手写的, 编译器生成的版本与析构一样是递归的
+pub enum Expr {
+    Num(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn num(v: f64) -> Expr {
+        Expr::Num(v)
+    }
+
+    pub fn var(name: &str) -> Expr {
+        Expr::Var(name.to_string())
+    }
+
+    fn children(&self) -> (Option<&Expr>, Option<&Expr>) {
+        match self {
+            Expr::Num(_) | Expr::Var(_) => (None, None),
+            Expr::Neg(a) => (Some(a), None),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) => {
+                (Some(a), Some(b))
+            }
+        }
+    }
+
+    fn children_mut(&mut self) -> (Option<&mut Expr>, Option<&mut Expr>) {
+        match self {
+            Expr::Num(_) | Expr::Var(_) => (None, None),
+            Expr::Neg(a) => (Some(a), None),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) => {
+                (Some(a), Some(b))
+            }
+        }
+    }
+
+    // 把子树从 Box 里移出来, 原地留下一个叶子节点
+    fn take_children(&mut self, out: &mut Vec<Expr>) {
+        let (a, b) = self.children_mut();
+        for child in a.into_iter().chain(b) {
+            if child.children().0.is_some() {
+                out.push(std::mem::replace(child, Expr::Num(0.0)));
+            }
+        }
+    }
+
+    /// 先序遍历所有节点
+    pub fn iter(&self) -> ExprIter<'_> {
+        ExprIter { stack: vec![self] }
+    }
+
+    /// 遍历所有数值叶子的可变引用, 可以就地修改常量
+    pub fn numbers_mut(&mut self) -> NumbersMut<'_> {
+        NumbersMut { stack: vec![self] }
+    }
+
+    /// 取出所有的叶子节点, 按从左到右的顺序
+    pub fn into_leaves(mut self) -> Vec<Expr> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![std::mem::replace(&mut self, Expr::Num(0.0))];
+        while let Some(mut e) = stack.pop() {
+            match e {
+                Expr::Num(_) | Expr::Var(_) => leaves.push(e),
+                _ => {
+                    let (a, b) = e.children_mut();
+                    // 后进先出, 先压右边
+                    for child in b.into_iter().chain(a) {
+                        stack.push(std::mem::replace(child, Expr::Num(0.0)));
+                    }
+                }
+            }
+        }
+        leaves
+    }
+
+    /// 插入: 把所有名为 name 的变量替换成 value 子树
+    pub fn substitute(&mut self, name: &str, value: &Expr) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+        while let Some(e) = stack.pop() {
+            match e {
+                Expr::Var(v) if v == name => {
+                    *e = value.clone();
+                    count += 1;
+                }
+                _ => {
+                    let (a, b) = e.children_mut();
+                    stack.extend(a);
+                    stack.extend(b);
+                }
+            }
+        }
+        count
+    }
+
+    /// 删除: 把不含变量的子树折叠成一个常数节点, 返回删掉的节点数
+    pub fn fold_constants(&mut self) -> usize {
+        let before = self.iter().count();
+        // 后序遍历: 先处理子节点再处理父节点。
+        // 子树用 mem::replace 从父节点中取出来, 处理完再放回去, 不需要裸指针
+        enum Step {
+            Visit(Expr),
+            Rebuild(Expr),
+        }
+        let mut steps = vec![Step::Visit(std::mem::replace(self, Expr::Num(0.0)))];
+        // 已经处理完的子树, 左子树在右子树下面
+        let mut done: Vec<Expr> = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Visit(mut e) => {
+                    let (a, b) = e.children_mut();
+                    let children: Vec<Expr> = a
+                        .into_iter()
+                        .chain(b)
+                        .map(|c| std::mem::replace(c, Expr::Num(0.0)))
+                        .collect();
+                    if children.is_empty() {
+                        done.push(e);
+                    } else {
+                        steps.push(Step::Rebuild(e));
+                        // 后进先出, 先压右边
+                        steps.extend(children.into_iter().rev().map(Step::Visit));
+                    }
+                }
+                Step::Rebuild(mut e) => {
+                    let (a, b) = e.children_mut();
+                    if let Some(b) = b {
+                        *b = done.pop().unwrap();
+                    }
+                    if let Some(a) = a {
+                        *a = done.pop().unwrap();
+                    }
+                    let value = match &e {
+                        Expr::Neg(a) => a.as_num().map(|a| -a),
+                        Expr::Add(a, b) => a.as_num().zip(b.as_num()).map(|(a, b)| a + b),
+                        Expr::Sub(a, b) => a.as_num().zip(b.as_num()).map(|(a, b)| a - b),
+                        Expr::Mul(a, b) => a.as_num().zip(b.as_num()).map(|(a, b)| a * b),
+                        Expr::Div(a, b) => a.as_num().zip(b.as_num()).map(|(a, b)| a / b),
+                        _ => None,
+                    };
+                    done.push(value.map_or(e, Expr::Num));
+                }
+            }
+        }
+        *self = done.pop().unwrap();
+        before - self.iter().count()
+    }
+
+    // 与 self 同一种节点, 子节点换成 a、b; 叶子节点原样复制
+    fn same_op(&self, a: Option<Expr>, b: Option<Expr>) -> Expr {
+        let mut children = a.into_iter().chain(b).map(Box::new);
+        let mut next = || children.next().unwrap();
+        match self {
+            Expr::Num(v) => Expr::Num(*v),
+            Expr::Var(name) => Expr::Var(name.clone()),
+            Expr::Neg(_) => Expr::Neg(next()),
+            Expr::Add(..) => Expr::Add(next(), next()),
+            Expr::Sub(..) => Expr::Sub(next(), next()),
+            Expr::Mul(..) => Expr::Mul(next(), next()),
+            Expr::Div(..) => Expr::Div(next(), next()),
+        }
+    }
+
+    fn as_num(&self) -> Option<f64> {
+        match self {
+            Expr::Num(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// 求值, 变量的值由 env 提供
+    pub fn eval(&self, env: &dyn Fn(&str) -> Option<f64>) -> Option<f64> {
+        // 同样用显式的栈做后序遍历, 值栈里存放子表达式的结果
+        enum Step<'a> {
+            Visit(&'a Expr),
+            Apply(&'a Expr),
+        }
+        let mut steps = vec![Step::Visit(self)];
+        let mut values: Vec<f64> = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Visit(e) => match e {
+                    Expr::Num(v) => values.push(*v),
+                    Expr::Var(name) => values.push(env(name)?),
+                    _ => {
+                        steps.push(Step::Apply(e));
+                        let (a, b) = e.children();
+                        steps.extend(b.map(Step::Visit));
+                        steps.extend(a.map(Step::Visit));
+                    }
+                },
+                Step::Apply(e) => {
+                    let v = if let Expr::Neg(_) = e {
+                        -values.pop()?
+                    } else {
+                        let b = values.pop()?;
+                        let a = values.pop()?;
+                        match e {
+                            Expr::Add(..) => a + b,
+                            Expr::Sub(..) => a - b,
+                            Expr::Mul(..) => a * b,
+                            Expr::Div(..) => a / b,
+                            _ => unreachable!(),
+                        }
+                    };
+                    values.push(v);
+                }
+            }
+        }
+        values.pop()
+    }
+}
+
+// 与链表一样, 把子树移到一个 Vec 里逐个销毁
+// 从栈里取出的节点在销毁之前已经被掏空了, 它自己的 drop 不会再产生递归
+impl Drop for Expr {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(mut e) = stack.pop() {
+            e.take_children(&mut stack);
+        }
+    }
+}
+
+pub struct ExprIter<'a> {
+    stack: Vec<&'a Expr>,
+}
+
+impl<'a> Iterator for ExprIter<'a> {
+    type Item = &'a Expr;
+    fn next(&mut self) -> Option<&'a Expr> {
+        let e = self.stack.pop()?;
+        let (a, b) = e.children();
+        self.stack.extend(b);
+        self.stack.extend(a);
+        Some(e)
+    }
+}
+
+pub struct NumbersMut<'a> {
+    stack: Vec<&'a mut Expr>,
+}
+
+impl<'a> Iterator for NumbersMut<'a> {
+    type Item = &'a mut f64;
+    fn next(&mut self) -> Option<&'a mut f64> {
+        while let Some(e) = self.stack.pop() {
+            match e {
+                Expr::Num(v) => return Some(v),
+                Expr::Var(_) => {}
+                _ => {
+                    let (a, b) = e.children_mut();
+                    self.stack.extend(b);
+                    self.stack.extend(a);
+                }
+            }
+        }
+        None
+    }
+}
+
+// 输出时加上必要的括号
+impl Clone for Expr {
+    fn clone(&self) -> Expr {
+        // 后序遍历: 先复制子树, 再用它们拼出父节点
+        enum Step<'a> {
+            Visit(&'a Expr),
+            Build(&'a Expr),
+        }
+        let mut steps = vec![Step::Visit(self)];
+        // 已经复制好的子树, 左子树在右子树下面
+        let mut done: Vec<Expr> = Vec::new();
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Visit(e) => {
+                    let (a, b) = e.children();
+                    if a.is_none() {
+                        done.push(e.same_op(None, None));
+                    } else {
+                        steps.push(Step::Build(e));
+                        steps.extend(b.map(Step::Visit));
+                        steps.extend(a.map(Step::Visit));
+                    }
+                }
+                Step::Build(e) => {
+                    let b = if e.children().1.is_some() {
+                        done.pop()
+                    } else {
+                        None
+                    };
+                    let a = done.pop();
+                    done.push(e.same_op(a, b));
+                }
+            }
+        }
+        done.pop().unwrap()
+    }
+}
+
+impl PartialEq for Expr {
+    fn eq(&self, other: &Expr) -> bool {
+        // 成对地比较两棵树中位置相同的节点
+        let mut stack = vec![(self, other)];
+        while let Some((x, y)) = stack.pop() {
+            let same = match (x, y) {
+                (Expr::Num(a), Expr::Num(b)) => a == b,
+                (Expr::Var(a), Expr::Var(b)) => a == b,
+                _ => std::mem::discriminant(x) == std::mem::discriminant(y),
+            };
+            if !same {
+                return false;
+            }
+            let ((xa, xb), (ya, yb)) = (x.children(), y.children());
+            stack.extend(xb.zip(yb));
+            stack.extend(xa.zip(ya));
+        }
+        true
+    }
+}
+
+impl fmt::Debug for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // 栈里是还没有输出的节点和符号, 后进先出, 所以要倒着压栈
+        enum Piece<'a> {
+            Node(&'a Expr),
+            Text(&'static str),
+        }
+        let mut stack = vec![Piece::Node(self)];
+        while let Some(piece) = stack.pop() {
+            let e = match piece {
+                Piece::Text(text) => {
+                    f.write_str(text)?;
+                    continue;
+                }
+                Piece::Node(e) => e,
+            };
+            let (open, op, close) = match e {
+                Expr::Num(v) => {
+                    write!(f, "{}", v)?;
+                    continue;
+                }
+                Expr::Var(name) => {
+                    f.write_str(name)?;
+                    continue;
+                }
+                Expr::Neg(_) => ("-", "", ""),
+                Expr::Add(..) => ("(", " + ", ")"),
+                Expr::Sub(..) => ("(", " - ", ")"),
+                Expr::Mul(..) => ("", " * ", ""),
+                Expr::Div(..) => ("", " / ", ""),
+            };
+            f.write_str(open)?;
+            let (a, b) = e.children();
+            stack.push(Piece::Text(close));
+            if let Some(b) = b {
+                stack.push(Piece::Node(b));
+                stack.push(Piece::Text(op));
+            }
+            stack.extend(a.map(Piece::Node));
+        }
+        Ok(())
+    }
+}
+
+// ---------- 对照: 使用编译器生成的递归析构 ----------
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static MAX_DEPTH: Cell<usize> = const { Cell::new(0) };
+    static STACK_TOP: Cell<usize> = const { Cell::new(0) };
+    static STACK_BOTTOM: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// 没有手动实现析构的链表节点
+/// 编译器生成的析构会先调用 Drop::drop, 再销毁 next 字段, next 的析构又会销毁它的 next ...
+/// 为了能测量递归有多深, 这里在 drop 里显式地做了同样的事, 并记录调用栈的深度和地址
+pub struct NaiveList {
+    value: u64,
+    next: Option<Box<NaiveList>>,
+}
+
+impl NaiveList {
+    pub fn with_len(n: usize) -> Option<Box<NaiveList>> {
+        let mut head = None;
+        for i in 0..n {
+            head = Some(Box::new(NaiveList {
+                value: i as u64,
+                next: head,
+            }));
+        }
+        head
+    }
+}
+
+impl Drop for NaiveList {
+    fn drop(&mut self) {
+        let marker = 0u8;
+        let addr = &marker as *const u8 as usize;
+        let depth = DEPTH.with(|d| {
+            d.set(d.get() + 1);
+            d.get()
+        });
+        MAX_DEPTH.with(|m| m.set(m.get().max(depth)));
+        STACK_TOP.with(|t| t.set(t.get().max(addr)));
+        STACK_BOTTOM.with(|b| b.set(b.get().min(addr)));
+        drop(self.next.take());
+        DEPTH.with(|d| d.set(d.get() - 1));
+    }
+}
+
+/// 销毁一个长度为 n 的 NaiveList, 返回递归的最大深度和占用的栈空间字节数
+pub fn measure_naive_drop(n: usize) -> (usize, usize) {
+    MAX_DEPTH.with(|m| m.set(0));
+    STACK_TOP.with(|t| t.set(0));
+    STACK_BOTTOM.with(|b| b.set(usize::MAX));
+    drop(NaiveList::with_len(n));
+    let depth = MAX_DEPTH.with(|m| m.get());
+    let used = STACK_TOP.with(|t| t.get()) - STACK_BOTTOM.with(|b| b.get());
+    (depth, used)
+}
+
+/// 在子进程中销毁 NaiveList 时使用的环境变量, 值是链表的长度
+pub fn learn_recursive_types() {
+    // 链表
+    let mut list: List<i32> = (1..=5).collect();
+    list.push_front(0);
+    list.insert(3, 100);
+    assert_eq!(list.remove(3), Some(100));
+    list.push_back(6);
+    for v in list.iter_mut() {
+        *v *= 10;
+    }
+    list.reverse();
+    println!("{:?}", list);
+    assert_eq!(
+        list.iter().copied().collect::<Vec<_>>(),
+        vec![60, 50, 40, 30, 20, 10, 0]
+    );
+    assert_eq!(list.len(), 7);
+    let owned: Vec<i32> = list.into_iter().filter(|v| v % 20 == 0).collect();
+    assert_eq!(owned, vec![60, 40, 20, 0]);
+
+    // 二叉搜索树
+    let mut map: BstMap<i32, &str> = vec![(5, "five"), (2, "two"), (8, "eight"), (1, "one")]
+        .into_iter()
+        .collect();
+    map.insert(6, "six");
+    map.insert(9, "nine");
+    assert_eq!(map.insert(2, "TWO"), Some("two"));
+    // 删除有两个子节点的根节点
+    assert_eq!(map.remove(&5), Some("five"));
+    assert_eq!(map.remove(&5), None);
+    for (_, v) in map.iter_mut() {
+        *v = if v.len() > 3 { "long" } else { "short" };
+    }
+    println!("{:?} height {}", map, map.height());
+    assert_eq!(
+        map.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+        vec![1, 2, 6, 8, 9]
+    );
+    assert_eq!(map.first(), Some((&1, &"short")));
+    let keys: Vec<i32> = map.into_iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![1, 2, 6, 8, 9]);
+
+    // 按顺序插入会让树退化成一条链, 高度等于元素个数, 但依然不会栈溢出
+    let mut line: BstMap<u32, ()> = (0..5000).map(|i| (i, ())).collect();
+    assert_eq!(line.height(), 5000);
+    assert_eq!(line.remove(&0), Some(()));
+    assert_eq!(line.iter().count(), 4999);
+    drop(line);
+
+    // 表达式树: (x + 2 * 3) * -(y - 1)
+    let mut e = Expr::Mul(
+        Box::new(Expr::Add(
+            Box::new(Expr::var("x")),
+            Box::new(Expr::Mul(
+                Box::new(Expr::num(2.0)),
+                Box::new(Expr::num(3.0)),
+            )),
+        )),
+        Box::new(Expr::Neg(Box::new(Expr::Sub(
+            Box::new(Expr::var("y")),
+            Box::new(Expr::num(1.0)),
+        )))),
+    );
+    println!("{:?}", e);
+    let env = |name: &str| match name {
+        "x" => Some(4.0),
+        "y" => Some(3.0),
+        _ => None,
+    };
+    assert_eq!(e.eval(&env), Some(-20.0));
+    assert_eq!(e.fold_constants(), 2);
+    println!("{:?}", e);
+    assert_eq!(e.substitute("y", &Expr::num(5.0)), 1);
+    for v in e.numbers_mut() {
+        *v += 1.0;
+    }
+    println!("{:?} = {:?}", e, e.eval(&env));
+    assert_eq!(e.iter().filter(|n| matches!(n, Expr::Num(_))).count(), 3);
+    assert_eq!(e.clone().into_leaves().len(), 4);
+
+    // 一百万个节点的链表和一棵一百万层深的表达式树, 销毁时都不会栈溢出
+    let long: List<u64> = (0..1_000_000).collect();
+    drop(long);
+    let mut deep = Expr::num(0.0);
+    for i in 0..1_000_000 {
+        deep = Expr::Add(Box::new(deep), Box::new(Expr::num(i as f64)));
+    }
+    assert_eq!(deep.eval(&env), Some(499_999_500_000.0));
+    // 复制、比较和打印也都不递归
+    let copy = deep.clone();
+    assert!(copy == deep);
+    let text = format!("{:?}", copy);
+    assert_eq!(text.matches('(').count(), 1_000_000);
+    assert!(text.contains("(0 + 0) + 1) + 2)"));
+    assert!(text.ends_with(" + 999999)"));
+    if let Expr::Add(_, last) = &mut deep {
+        **last = Expr::num(-1.0);
+    }
+    assert!(copy != deep);
+    drop(copy);
+    drop(deep);
+
+    // 而编译器生成的递归析构, 链表有多长, 调用栈就有多深
+    for &n in [10, 100, 1000].iter() {
+        let (depth, used) = measure_naive_drop(n);
+        println!(
+            "naive drop of {} nodes: depth {}, stack {} bytes (~{} bytes per node)",
+            n,
+            depth,
+            used,
+            used / n.max(2).saturating_sub(1)
+        );
+        assert_eq!(depth, n);
+        if n == 1000 {
+            println!(
+                "an 8MB stack overflows at about {} nodes",
+                (8 << 20) / (used / n).max(1)
+            );
+        }
+    }
+    // 按上面测出的每层栈空间可以估算出多长的链表会让析构栈溢出。
+    // 栈溢出会终止整个进程, 无法用 catch_unwind 捕获, 所以真正的溢出放在 tests/naive_drop.rs 里,
+    // 用 `cargo test --test naive_drop` 运行: 在 256KB 的线程栈上销毁一百万个节点的 NaiveList
+    // 而同样长的 List 在同样小的栈上销毁没有问题
+    let small_stack = thread::Builder::new().stack_size(256 << 10);
+    let long: List<u64> = (0..1_000_000).collect();
+    let handle = small_stack.spawn(move || drop(long)).unwrap();
+    assert!(handle.join().is_ok());
+}
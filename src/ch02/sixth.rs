@@ -0,0 +1,744 @@
+#![allow(dead_code)]
+
+// 用 enum 定义协议
+// third::learn_enum 里定义了 Message、BoardGameTurn、Animal 三个 enum,
+// 分别包含了 unit、tuple、struct 三种形式的 variant, 以及 C 风格的带值 enum。
+// enum 是 tagged union, 天然适合描述"若干种消息之一"的协议:
+// 编码时先写一个 tag 表示是哪一个 variant, 再依次写出它的成员; 解码时反过来。
+// 这里为它们实现了两种编码:
+// 1. 紧凑的二进制格式: tag 占一个字节, 整数用 zigzag + LEB128 变长编码, 字符串带长度前缀
+// 2. 便于阅读的文本格式: 与 Rust 的字面量语法一致, 如 Move { x: 3, y: 4 }
+// 解码时会检查未知的 tag、被截断的输入以及多余的尾部数据。
+// 新的协议只需要照着 Message 的写法为自己的 enum 实现 Wire 即可。
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Quit,
+    ChangeColor(i32, i32, i32),
+    Move { x: i32, y: i32 },
+    Write(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoardGameTurn {
+    Move { squares: i32 },
+    Pass,
+}
+
+// third::learn_enum 中用的是小写的 dog cat tiger, 这里按照命名规范改成了大写
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Animal {
+    Dog = 1,
+    Cat = 200,
+    Tiger,
+}
+
+/// 解码错误, offset 是出错位置: 二进制格式中是字节下标, 文本格式中是字符串的字节偏移
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// 不认识的 tag 或 variant 名字
+    UnknownTag {
+        ty: &'static str,
+        tag: String,
+        offset: usize,
+    },
+    /// 输入提前结束了
+    Truncated { offset: usize },
+    /// 一个完整的值解码完之后还有多余的数据
+    TrailingData { offset: usize, remaining: usize },
+    /// 其他格式错误, 如非法的 UTF-8、整数越界、文本语法错误
+    Invalid { offset: usize, msg: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnknownTag { ty, tag, offset } => {
+                write!(f, "unknown tag {} for {} at offset {}", tag, ty, offset)
+            }
+            DecodeError::Truncated { offset } => write!(f, "input truncated at offset {}", offset),
+            DecodeError::TrailingData { offset, remaining } => write!(
+                f,
+                "{} trailing byte(s) after value at offset {}",
+                remaining, offset
+            ),
+            DecodeError::Invalid { offset, msg } => write!(f, "{} at offset {}", msg, offset),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// ---------- 二进制格式 ----------
+
+/// 二进制编码器
+#[derive(Default)]
+pub struct BinWriter {
+    buf: Vec<u8>,
+}
+
+impl BinWriter {
+    pub fn tag(&mut self, tag: u8) {
+        self.buf.push(tag);
+    }
+
+    /// LEB128: 每个字节存 7 位, 最高位为 1 表示后面还有
+    pub fn varint(&mut self, mut v: u64) {
+        loop {
+            let byte = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                self.buf.push(byte);
+                return;
+            }
+            self.buf.push(byte | 0x80);
+        }
+    }
+
+    pub fn bytes(&mut self, b: &[u8]) {
+        self.varint(b.len() as u64);
+        self.buf.extend_from_slice(b);
+    }
+}
+
+/// 二进制解码器
+pub struct BinReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinReader<'a> {
+    pub fn new(buf: &'a [u8]) -> BinReader<'a> {
+        BinReader { buf, pos: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    pub fn tag(&mut self) -> Result<u8, DecodeError> {
+        let b = *self
+            .buf
+            .get(self.pos)
+            .ok_or(DecodeError::Truncated { offset: self.pos })?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    pub fn varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.pos;
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.tag()?;
+            // 第 10 个字节只剩最低的 1 位可用, 再多就超出 u64 了
+            if shift == 63 && b > 1 {
+                return Err(DecodeError::Invalid {
+                    offset: start,
+                    msg: "varint overflows u64".to_string(),
+                });
+            }
+            v |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(DecodeError::Invalid {
+            offset: start,
+            msg: "varint is too long".to_string(),
+        })
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.varint()? as usize;
+        let end = self.pos.checked_add(len).filter(|&e| e <= self.buf.len());
+        let end = end.ok_or(DecodeError::Truncated {
+            offset: self.buf.len(),
+        })?;
+        let b = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(b)
+    }
+
+    /// 一个值解码完之后, 输入必须恰好用完
+    pub fn finish(&self) -> Result<(), DecodeError> {
+        if self.pos == self.buf.len() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingData {
+                offset: self.pos,
+                remaining: self.buf.len() - self.pos,
+            })
+        }
+    }
+
+    pub fn unknown_tag(&self, ty: &'static str, tag: u8) -> DecodeError {
+        DecodeError::UnknownTag {
+            ty,
+            tag: tag.to_string(),
+            offset: self.pos - 1,
+        }
+    }
+}
+
+// ---------- 文本格式 ----------
+
+/// 文本解码器, 一个简单的手写词法分析器
+pub struct TextReader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> TextReader<'a> {
+    pub fn new(src: &'a str) -> TextReader<'a> {
+        TextReader { src, pos: 0 }
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn invalid(&self, msg: String) -> DecodeError {
+        if self.pos >= self.src.len() {
+            DecodeError::Truncated { offset: self.pos }
+        } else {
+            DecodeError::Invalid {
+                offset: self.pos,
+                msg,
+            }
+        }
+    }
+
+    /// 下一个非空白字符是否是 c, 是的话消费掉
+    pub fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, c: char) -> Result<(), DecodeError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.invalid(format!("expected `{}`", c)))
+        }
+    }
+
+    /// 读取一个标识符, 返回它和它的起始位置
+    pub fn ident(&mut self) -> Result<(&'a str, usize), DecodeError> {
+        self.skip_ws();
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| self.rest().len());
+        if len == 0 {
+            return Err(self.invalid("expected identifier".to_string()));
+        }
+        self.pos += len;
+        Ok((&self.src[start..self.pos], start))
+    }
+
+    /// 结构体形式的字段名和冒号
+    pub fn field(&mut self, name: &str) -> Result<(), DecodeError> {
+        let (got, at) = self.ident()?;
+        if got != name {
+            return Err(DecodeError::Invalid {
+                offset: at,
+                msg: format!("expected field `{}`, found `{}`", name, got),
+            });
+        }
+        self.expect(':')
+    }
+
+    pub fn int(&mut self) -> Result<i64, DecodeError> {
+        self.skip_ws();
+        let start = self.pos;
+        let rest = self.rest();
+        let sign = usize::from(rest.starts_with('-'));
+        let digits = rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign);
+        if digits == 0 {
+            return Err(self.invalid("expected integer".to_string()));
+        }
+        self.pos += sign + digits;
+        self.src[start..self.pos]
+            .parse()
+            .map_err(|_| DecodeError::Invalid {
+                offset: start,
+                msg: "integer out of range".to_string(),
+            })
+    }
+
+    /// 双引号字符串, 支持 \" \\ \n \t 转义
+    pub fn string(&mut self) -> Result<String, DecodeError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(s);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, c @ '"')) | Some((_, c @ '\\')) => s.push(c),
+                    Some((j, c)) => {
+                        return Err(DecodeError::Invalid {
+                            offset: self.pos + j,
+                            msg: format!("unknown escape `\\{}`", c),
+                        })
+                    }
+                    None => break,
+                },
+                c => s.push(c),
+            }
+        }
+        Err(DecodeError::Truncated {
+            offset: self.src.len(),
+        })
+    }
+
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        self.skip_ws();
+        if self.pos == self.src.len() {
+            Ok(())
+        } else {
+            Err(DecodeError::TrailingData {
+                offset: self.pos,
+                remaining: self.src.len() - self.pos,
+            })
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// ---------- Wire trait ----------
+
+/// 可以在两种格式之间编解码的类型
+pub trait Wire: Sized {
+    fn encode(&self, w: &mut BinWriter);
+    fn decode(r: &mut BinReader) -> Result<Self, DecodeError>;
+    fn write_text(&self, out: &mut String);
+    fn read_text(r: &mut TextReader) -> Result<Self, DecodeError>;
+}
+
+pub fn to_bytes<T: Wire>(value: &T) -> Vec<u8> {
+    let mut w = BinWriter::default();
+    value.encode(&mut w);
+    w.buf
+}
+
+pub fn from_bytes<T: Wire>(bytes: &[u8]) -> Result<T, DecodeError> {
+    let mut r = BinReader::new(bytes);
+    let v = T::decode(&mut r)?;
+    r.finish()?;
+    Ok(v)
+}
+
+pub fn to_text<T: Wire>(value: &T) -> String {
+    let mut s = String::new();
+    value.write_text(&mut s);
+    s
+}
+
+pub fn from_text<T: Wire>(text: &str) -> Result<T, DecodeError> {
+    let mut r = TextReader::new(text);
+    let v = T::read_text(&mut r)?;
+    r.finish()?;
+    Ok(v)
+}
+
+// zigzag 编码把有符号数映射到无符号数: 0 -> 0, -1 -> 1, 1 -> 2, -2 -> 3 ...
+// 这样绝对值小的负数也只需要很少的字节
+impl Wire for i32 {
+    fn encode(&self, w: &mut BinWriter) {
+        w.varint(((self << 1) ^ (self >> 31)) as u32 as u64);
+    }
+
+    fn decode(r: &mut BinReader) -> Result<i32, DecodeError> {
+        let start = r.offset();
+        let v = u32::try_from(r.varint()?).map_err(|_| DecodeError::Invalid {
+            offset: start,
+            msg: "integer out of range".to_string(),
+        })?;
+        Ok((v >> 1) as i32 ^ -((v & 1) as i32))
+    }
+
+    fn write_text(&self, out: &mut String) {
+        out.push_str(&self.to_string());
+    }
+
+    fn read_text(r: &mut TextReader) -> Result<i32, DecodeError> {
+        let start = r.pos;
+        i32::try_from(r.int()?).map_err(|_| DecodeError::Invalid {
+            offset: start,
+            msg: "integer out of range".to_string(),
+        })
+    }
+}
+
+impl Wire for String {
+    fn encode(&self, w: &mut BinWriter) {
+        w.bytes(self.as_bytes());
+    }
+
+    fn decode(r: &mut BinReader) -> Result<String, DecodeError> {
+        let start = r.offset();
+        let b = r.bytes()?;
+        String::from_utf8(b.to_vec()).map_err(|_| DecodeError::Invalid {
+            offset: start,
+            msg: "invalid UTF-8".to_string(),
+        })
+    }
+
+    fn write_text(&self, out: &mut String) {
+        write_string(self, out);
+    }
+
+    fn read_text(r: &mut TextReader) -> Result<String, DecodeError> {
+        r.string()
+    }
+}
+
+// 消息序列: 二进制格式先写个数, 文本格式用方括号
+impl<T: Wire> Wire for Vec<T> {
+    fn encode(&self, w: &mut BinWriter) {
+        w.varint(self.len() as u64);
+        for v in self {
+            v.encode(w);
+        }
+    }
+
+    fn decode(r: &mut BinReader) -> Result<Vec<T>, DecodeError> {
+        let n = r.varint()?;
+        // 不能直接 with_capacity(n), 恶意的输入会让我们分配巨大的内存
+        let mut v = Vec::new();
+        for _ in 0..n {
+            v.push(T::decode(r)?);
+        }
+        Ok(v)
+    }
+
+    fn write_text(&self, out: &mut String) {
+        out.push('[');
+        for (i, v) in self.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            v.write_text(out);
+        }
+        out.push(']');
+    }
+
+    fn read_text(r: &mut TextReader) -> Result<Vec<T>, DecodeError> {
+        r.expect('[')?;
+        let mut v = Vec::new();
+        if r.eat(']') {
+            return Ok(v);
+        }
+        loop {
+            v.push(T::read_text(r)?);
+            if r.eat(']') {
+                return Ok(v);
+            }
+            r.expect(',')?;
+        }
+    }
+}
+
+// 下面是一个完整的协议模板
+// tag 一旦发布就不能再修改含义, 新增的 variant 只能使用新的 tag
+impl Wire for Message {
+    fn encode(&self, w: &mut BinWriter) {
+        match self {
+            Message::Quit => w.tag(0),
+            Message::ChangeColor(r, g, b) => {
+                w.tag(1);
+                r.encode(w);
+                g.encode(w);
+                b.encode(w);
+            }
+            Message::Move { x, y } => {
+                w.tag(2);
+                x.encode(w);
+                y.encode(w);
+            }
+            Message::Write(s) => {
+                w.tag(3);
+                s.encode(w);
+            }
+        }
+    }
+
+    fn decode(r: &mut BinReader) -> Result<Message, DecodeError> {
+        match r.tag()? {
+            0 => Ok(Message::Quit),
+            1 => Ok(Message::ChangeColor(
+                i32::decode(r)?,
+                i32::decode(r)?,
+                i32::decode(r)?,
+            )),
+            2 => Ok(Message::Move {
+                x: i32::decode(r)?,
+                y: i32::decode(r)?,
+            }),
+            3 => Ok(Message::Write(String::decode(r)?)),
+            tag => Err(r.unknown_tag("Message", tag)),
+        }
+    }
+
+    fn write_text(&self, out: &mut String) {
+        match self {
+            Message::Quit => out.push_str("Quit"),
+            Message::ChangeColor(r, g, b) => {
+                out.push_str("ChangeColor(");
+                r.write_text(out);
+                out.push_str(", ");
+                g.write_text(out);
+                out.push_str(", ");
+                b.write_text(out);
+                out.push(')');
+            }
+            Message::Move { x, y } => {
+                out.push_str("Move { x: ");
+                x.write_text(out);
+                out.push_str(", y: ");
+                y.write_text(out);
+                out.push_str(" }");
+            }
+            Message::Write(s) => {
+                out.push_str("Write(");
+                s.write_text(out);
+                out.push(')');
+            }
+        }
+    }
+
+    fn read_text(r: &mut TextReader) -> Result<Message, DecodeError> {
+        let (name, at) = r.ident()?;
+        match name {
+            "Quit" => Ok(Message::Quit),
+            "ChangeColor" => {
+                r.expect('(')?;
+                let red = i32::read_text(r)?;
+                r.expect(',')?;
+                let green = i32::read_text(r)?;
+                r.expect(',')?;
+                let blue = i32::read_text(r)?;
+                r.expect(')')?;
+                Ok(Message::ChangeColor(red, green, blue))
+            }
+            "Move" => {
+                r.expect('{')?;
+                r.field("x")?;
+                let x = i32::read_text(r)?;
+                r.expect(',')?;
+                r.field("y")?;
+                let y = i32::read_text(r)?;
+                r.expect('}')?;
+                Ok(Message::Move { x, y })
+            }
+            "Write" => {
+                r.expect('(')?;
+                let s = String::read_text(r)?;
+                r.expect(')')?;
+                Ok(Message::Write(s))
+            }
+            _ => Err(DecodeError::UnknownTag {
+                ty: "Message",
+                tag: name.to_string(),
+                offset: at,
+            }),
+        }
+    }
+}
+
+impl Wire for BoardGameTurn {
+    fn encode(&self, w: &mut BinWriter) {
+        match self {
+            BoardGameTurn::Move { squares } => {
+                w.tag(0);
+                squares.encode(w);
+            }
+            BoardGameTurn::Pass => w.tag(1),
+        }
+    }
+
+    fn decode(r: &mut BinReader) -> Result<BoardGameTurn, DecodeError> {
+        match r.tag()? {
+            0 => Ok(BoardGameTurn::Move {
+                squares: i32::decode(r)?,
+            }),
+            1 => Ok(BoardGameTurn::Pass),
+            tag => Err(r.unknown_tag("BoardGameTurn", tag)),
+        }
+    }
+
+    fn write_text(&self, out: &mut String) {
+        match self {
+            BoardGameTurn::Move { squares } => {
+                out.push_str("Move { squares: ");
+                squares.write_text(out);
+                out.push_str(" }");
+            }
+            BoardGameTurn::Pass => out.push_str("Pass"),
+        }
+    }
+
+    fn read_text(r: &mut TextReader) -> Result<BoardGameTurn, DecodeError> {
+        let (name, at) = r.ident()?;
+        match name {
+            "Move" => {
+                r.expect('{')?;
+                r.field("squares")?;
+                let squares = i32::read_text(r)?;
+                r.expect('}')?;
+                Ok(BoardGameTurn::Move { squares })
+            }
+            "Pass" => Ok(BoardGameTurn::Pass),
+            _ => Err(DecodeError::UnknownTag {
+                ty: "BoardGameTurn",
+                tag: name.to_string(),
+                offset: at,
+            }),
+        }
+    }
+}
+
+// C 风格的 enum 直接用它的值作为 tag
+impl Wire for Animal {
+    fn encode(&self, w: &mut BinWriter) {
+        w.tag(*self as u8);
+    }
+
+    fn decode(r: &mut BinReader) -> Result<Animal, DecodeError> {
+        match r.tag()? {
+            1 => Ok(Animal::Dog),
+            200 => Ok(Animal::Cat),
+            201 => Ok(Animal::Tiger),
+            tag => Err(r.unknown_tag("Animal", tag)),
+        }
+    }
+
+    fn write_text(&self, out: &mut String) {
+        out.push_str(&format!("{:?}", self));
+    }
+
+    fn read_text(r: &mut TextReader) -> Result<Animal, DecodeError> {
+        let (name, at) = r.ident()?;
+        match name {
+            "Dog" => Ok(Animal::Dog),
+            "Cat" => Ok(Animal::Cat),
+            "Tiger" => Ok(Animal::Tiger),
+            _ => Err(DecodeError::UnknownTag {
+                ty: "Animal",
+                tag: name.to_string(),
+                offset: at,
+            }),
+        }
+    }
+}
+
+pub fn learn_wire() {
+    let messages = vec![
+        Message::Quit,
+        Message::ChangeColor(0, 160, 255),
+        Message::Move { x: 3, y: -4 },
+        Message::Write("hello \"世界\"".to_string()),
+    ];
+
+    for m in &messages {
+        let bytes = to_bytes(m);
+        let text = to_text(m);
+        println!("{:<35} {:?}", text, bytes);
+        assert_eq!(&from_bytes::<Message>(&bytes).unwrap(), m);
+        assert_eq!(&from_text::<Message>(&text).unwrap(), m);
+    }
+
+    // 整个序列也可以编码
+    let bytes = to_bytes(&messages);
+    assert_eq!(from_bytes::<Vec<Message>>(&bytes).unwrap(), messages);
+    let text = to_text(&messages);
+    assert_eq!(from_text::<Vec<Message>>(&text).unwrap(), messages);
+    // 文本格式允许任意空白
+    assert_eq!(
+        from_text::<Message>("  Move{x:3,\n y : -4 }  ").unwrap(),
+        Message::Move { x: 3, y: -4 }
+    );
+
+    let turn = BoardGameTurn::Move { squares: 1 };
+    assert_eq!(from_bytes::<BoardGameTurn>(&to_bytes(&turn)).unwrap(), turn);
+    assert_eq!(to_bytes(&Animal::Tiger), vec![201]);
+    assert_eq!(from_text::<Animal>("Cat").unwrap(), Animal::Cat);
+
+    // 错误处理
+    let errors = vec![
+        from_bytes::<Message>(&[9]).unwrap_err(),
+        from_bytes::<Message>(&[2, 6]).unwrap_err(),
+        from_bytes::<Message>(&[0, 0]).unwrap_err(),
+        from_bytes::<Message>(&[3, 5, b'a']).unwrap_err(),
+        from_bytes::<Message>(&[3, 1, 0xff]).unwrap_err(),
+        from_bytes::<Animal>(&[2]).unwrap_err(),
+        from_text::<Message>("Jump").unwrap_err(),
+        from_text::<Message>("Move { x: 3,").unwrap_err(),
+        from_text::<Message>("Quit Quit").unwrap_err(),
+        from_text::<Message>("ChangeColor(1, 2, 99999999999)").unwrap_err(),
+    ];
+    for e in &errors {
+        println!("{}", e);
+    }
+    assert!(matches!(
+        errors[0],
+        DecodeError::UnknownTag { offset: 0, .. }
+    ));
+    assert_eq!(errors[1], DecodeError::Truncated { offset: 2 });
+    assert_eq!(
+        errors[2],
+        DecodeError::TrailingData {
+            offset: 1,
+            remaining: 1
+        }
+    );
+    assert!(matches!(errors[7], DecodeError::Truncated { .. }));
+    assert!(matches!(
+        errors[8],
+        DecodeError::TrailingData { offset: 5, .. }
+    ));
+    // varint 最多 10 个字节, 第 10 个字节只能是 0 或 1
+    let mut w = BinWriter::default();
+    w.varint(u64::MAX);
+    assert_eq!(w.buf.len(), 10);
+    assert_eq!(BinReader::new(&w.buf).varint(), Ok(u64::MAX));
+    let mut overflow = [0xff; 10];
+    overflow[9] = 0x02;
+    let err = BinReader::new(&overflow).varint().unwrap_err();
+    println!("{}", err);
+    assert!(matches!(err, DecodeError::Invalid { offset: 0, .. }));
+}
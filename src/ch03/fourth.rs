@@ -0,0 +1,647 @@
+#![allow(dead_code)]
+
+// 位集合与位域
+// first::learn_bit_op 用 {:08b} 打印了 u8 的各种位运算结果。
+// 位运算最常见的用途就是把一组 bool 或者几个很小的整数"压缩"进一个整数里:
+// 1. BitSet<W>: 定长位集合, 用 W 个 u64 存储, 可以完全放在栈上
+//    (stable Rust 还不能在类型里写 [u64; (N + 63) / 64] 这样的表达式, 所以参数是字数而不是位数)
+// 2. BitVec: 可增长的位集合, 用 Vec<u64> 存储
+// 3. bitfield! 宏: 给一个整数里的若干位段起名字, 生成读写方法
+// 它们都按照 {:b} 的格式输出, 每 8 位用下划线分隔, 和 learn_bit_op 的输出保持一致。
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub};
+
+const WORD_BITS: usize = 64;
+
+// 下面这些函数只依赖 &[u64], 两种位集合共用
+
+fn count(words: &[u64]) -> usize {
+    words.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+// [0, i) 范围内被置位的个数
+fn rank(words: &[u64], i: usize) -> usize {
+    let (w, b) = (i / WORD_BITS, i % WORD_BITS);
+    let full = count(&words[..w.min(words.len())]);
+    match words.get(w) {
+        Some(word) if b > 0 => full + (word & ((1u64 << b) - 1)).count_ones() as usize,
+        _ => full,
+    }
+}
+
+// 第 k 个(从 0 开始)被置位的下标
+fn select(words: &[u64], mut k: usize) -> Option<usize> {
+    for (w, &word) in words.iter().enumerate() {
+        let n = word.count_ones() as usize;
+        if k < n {
+            // 在这个字里, 每次清掉最低的一位 1
+            let mut word = word;
+            for _ in 0..k {
+                word &= word - 1;
+            }
+            return Some(w * WORD_BITS + word.trailing_zeros() as usize);
+        }
+        k -= n;
+    }
+    None
+}
+
+// 从高位到低位输出, 每 8 位一组
+fn fmt_binary(words: &[u64], bits: usize, f: &mut fmt::Formatter) -> fmt::Result {
+    let bits = bits.div_ceil(8).max(1) * 8;
+    let mut s = String::with_capacity(bits + bits / 8);
+    for i in (0..bits).rev() {
+        let set = words
+            .get(i / WORD_BITS)
+            .is_some_and(|w| w & (1 << (i % WORD_BITS)) != 0);
+        s.push(if set { '1' } else { '0' });
+        if i % 8 == 0 && i > 0 {
+            s.push('_');
+        }
+    }
+    if f.alternate() {
+        f.write_str("0b")?;
+    }
+    f.write_str(&s)
+}
+
+/// 按位从低到高遍历被置位的下标
+pub struct Ones<'a> {
+    words: &'a [u64],
+    index: usize,
+    current: u64,
+}
+
+impl<'a> Ones<'a> {
+    fn new(words: &'a [u64]) -> Ones<'a> {
+        Ones {
+            words,
+            index: 0,
+            current: words.first().copied().unwrap_or(0),
+        }
+    }
+}
+
+impl<'a> Iterator for Ones<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.index += 1;
+            self.current = *self.words.get(self.index)?;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // x & (x - 1) 清掉最低的一位 1
+        self.current &= self.current - 1;
+        Some(self.index * WORD_BITS + bit)
+    }
+}
+
+/// 定长位集合, 容量为 W * 64 位
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitSet<const W: usize> {
+    words: [u64; W],
+}
+
+pub type BitSet64 = BitSet<1>;
+pub type BitSet256 = BitSet<4>;
+
+impl<const W: usize> Default for BitSet<W> {
+    fn default() -> Self {
+        BitSet::new()
+    }
+}
+
+impl<const W: usize> BitSet<W> {
+    pub const CAPACITY: usize = W * WORD_BITS;
+
+    pub const fn new() -> Self {
+        BitSet { words: [0; W] }
+    }
+
+    fn locate(i: usize) -> (usize, u64) {
+        assert!(
+            i < Self::CAPACITY,
+            "bit index {} out of range for BitSet of {} bits",
+            i,
+            Self::CAPACITY
+        );
+        (i / WORD_BITS, 1 << (i % WORD_BITS))
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        i < Self::CAPACITY && self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    /// 置位, 返回之前的值
+    pub fn set(&mut self, i: usize) -> bool {
+        let (w, mask) = Self::locate(i);
+        let old = self.words[w] & mask != 0;
+        self.words[w] |= mask;
+        old
+    }
+
+    /// 清零, 返回之前的值
+    pub fn clear(&mut self, i: usize) -> bool {
+        let (w, mask) = Self::locate(i);
+        let old = self.words[w] & mask != 0;
+        self.words[w] &= !mask;
+        old
+    }
+
+    /// 取反, 返回之后的值
+    pub fn toggle(&mut self, i: usize) -> bool {
+        let (w, mask) = Self::locate(i);
+        self.words[w] ^= mask;
+        self.words[w] & mask != 0
+    }
+
+    pub fn len(&self) -> usize {
+        count(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    pub fn iter(&self) -> Ones<'_> {
+        Ones::new(&self.words)
+    }
+
+    /// [0, i) 范围内被置位的个数
+    pub fn rank(&self, i: usize) -> usize {
+        rank(&self.words, i)
+    }
+
+    /// 第 k 个(从 0 开始)被置位的下标
+    pub fn select(&self, k: usize) -> Option<usize> {
+        select(&self.words, k)
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .all(|(a, b)| a & !b == 0)
+    }
+}
+
+// 定长位集合是 Copy 的, 运算符直接按值实现
+macro_rules! bitset_op {
+    ($tr:ident, $method:ident, $tra:ident, $method_a:ident, |$a:ident, $b:ident| $e:expr) => {
+        impl<const W: usize> $tr for BitSet<W> {
+            type Output = BitSet<W>;
+            fn $method(mut self, rhs: BitSet<W>) -> BitSet<W> {
+                self.$method_a(rhs);
+                self
+            }
+        }
+
+        impl<const W: usize> $tra for BitSet<W> {
+            fn $method_a(&mut self, rhs: BitSet<W>) {
+                for ($a, &$b) in self.words.iter_mut().zip(rhs.words.iter()) {
+                    *$a = $e;
+                }
+            }
+        }
+    };
+}
+
+bitset_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| *a | b);
+bitset_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| *a & b);
+bitset_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| *a ^ b);
+
+/// 差集
+impl<const W: usize> Sub for BitSet<W> {
+    type Output = BitSet<W>;
+    fn sub(mut self, rhs: BitSet<W>) -> BitSet<W> {
+        for (a, b) in self.words.iter_mut().zip(rhs.words.iter()) {
+            *a &= !b;
+        }
+        self
+    }
+}
+
+/// 补集
+impl<const W: usize> Not for BitSet<W> {
+    type Output = BitSet<W>;
+    fn not(mut self) -> BitSet<W> {
+        for a in self.words.iter_mut() {
+            *a = !*a;
+        }
+        self
+    }
+}
+
+impl<const W: usize> FromIterator<usize> for BitSet<W> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut s = BitSet::new();
+        for i in iter {
+            s.set(i);
+        }
+        s
+    }
+}
+
+impl<'a, const W: usize> IntoIterator for &'a BitSet<W> {
+    type Item = usize;
+    type IntoIter = Ones<'a>;
+    fn into_iter(self) -> Ones<'a> {
+        self.iter()
+    }
+}
+
+impl<const W: usize> fmt::Binary for BitSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_binary(&self.words, Self::CAPACITY, f)
+    }
+}
+
+impl<const W: usize> fmt::Debug for BitSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// 可增长的位集合
+/// 置位超出当前容量时自动扩容, 读取或清零超出容量的位什么也不做
+#[derive(Clone, Default)]
+pub struct BitVec {
+    words: Vec<u64>,
+}
+
+impl BitVec {
+    pub fn new() -> BitVec {
+        BitVec { words: Vec::new() }
+    }
+
+    pub fn with_capacity(bits: usize) -> BitVec {
+        BitVec {
+            words: Vec::with_capacity(bits.div_ceil(WORD_BITS)),
+        }
+    }
+
+    /// 当前已经分配的位数
+    pub fn capacity(&self) -> usize {
+        self.words.len() * WORD_BITS
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.words
+            .get(i / WORD_BITS)
+            .is_some_and(|w| w & (1 << (i % WORD_BITS)) != 0)
+    }
+
+    pub fn set(&mut self, i: usize) -> bool {
+        let w = i / WORD_BITS;
+        if w >= self.words.len() {
+            self.words.resize(w + 1, 0);
+        }
+        let mask = 1 << (i % WORD_BITS);
+        let old = self.words[w] & mask != 0;
+        self.words[w] |= mask;
+        old
+    }
+
+    pub fn clear(&mut self, i: usize) -> bool {
+        match self.words.get_mut(i / WORD_BITS) {
+            Some(w) => {
+                let mask = 1 << (i % WORD_BITS);
+                let old = *w & mask != 0;
+                *w &= !mask;
+                old
+            }
+            None => false,
+        }
+    }
+
+    pub fn toggle(&mut self, i: usize) -> bool {
+        if self.get(i) {
+            self.clear(i);
+            false
+        } else {
+            self.set(i);
+            true
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        count(&self.words)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    pub fn iter(&self) -> Ones<'_> {
+        Ones::new(&self.words)
+    }
+
+    pub fn rank(&self, i: usize) -> usize {
+        rank(&self.words, i)
+    }
+
+    pub fn select(&self, k: usize) -> Option<usize> {
+        select(&self.words, k)
+    }
+
+    /// 去掉末尾全 0 的字, 释放多余的空间
+    pub fn shrink_to_fit(&mut self) {
+        let n = self.significant_words();
+        self.words.truncate(n);
+        self.words.shrink_to_fit();
+    }
+
+    fn significant_words(&self) -> usize {
+        self.words
+            .iter()
+            .rposition(|&w| w != 0)
+            .map_or(0, |i| i + 1)
+    }
+
+    fn zip_with(&self, rhs: &BitVec, f: impl Fn(u64, u64) -> u64) -> BitVec {
+        let n = self.words.len().max(rhs.words.len());
+        let word = |v: &BitVec, i: usize| v.words.get(i).copied().unwrap_or(0);
+        let mut out = BitVec {
+            words: (0..n).map(|i| f(word(self, i), word(rhs, i))).collect(),
+        };
+        out.shrink_to_fit();
+        out
+    }
+}
+
+// 末尾多出来的 0 不影响相等性
+impl PartialEq for BitVec {
+    fn eq(&self, other: &BitVec) -> bool {
+        self.words[..self.significant_words()] == other.words[..other.significant_words()]
+    }
+}
+
+impl Eq for BitVec {}
+
+// BitVec 不是 Copy 的, 运算符实现在引用上, 不消耗操作数
+macro_rules! bitvec_op {
+    ($tr:ident, $method:ident, $tra:ident, $method_a:ident, |$a:ident, $b:ident| $e:expr) => {
+        impl $tr<&BitVec> for &BitVec {
+            type Output = BitVec;
+            fn $method(self, rhs: &BitVec) -> BitVec {
+                self.zip_with(rhs, |$a, $b| $e)
+            }
+        }
+
+        impl $tra<&BitVec> for BitVec {
+            fn $method_a(&mut self, rhs: &BitVec) {
+                *self = self.zip_with(rhs, |$a, $b| $e);
+            }
+        }
+    };
+}
+
+bitvec_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+bitvec_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+bitvec_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a ^ b);
+
+impl Sub<&BitVec> for &BitVec {
+    type Output = BitVec;
+    fn sub(self, rhs: &BitVec) -> BitVec {
+        self.zip_with(rhs, |a, b| a & !b)
+    }
+}
+
+impl FromIterator<usize> for BitVec {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut s = BitVec::new();
+        for i in iter {
+            s.set(i);
+        }
+        s
+    }
+}
+
+impl<'a> IntoIterator for &'a BitVec {
+    type Item = usize;
+    type IntoIter = Ones<'a>;
+    fn into_iter(self) -> Ones<'a> {
+        self.iter()
+    }
+}
+
+impl fmt::Binary for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highest = self.iter().last().map_or(0, |i| i + 1);
+        fmt_binary(&self.words, highest, f)
+    }
+}
+
+impl fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// 把整数按 8 位一组格式化, 如 0b1010_1010 -> "10101010", 0x1234 -> "00010010_00110100"
+pub fn grouped_binary(value: u128, bits: u32) -> String {
+    let mut s = String::new();
+    for i in (0..bits).rev() {
+        s.push(if value >> i & 1 == 1 { '1' } else { '0' });
+        if i % 8 == 0 && i > 0 {
+            s.push('_');
+        }
+    }
+    s
+}
+
+/// 位域: 给一个整数里的若干个位段起名字
+///
+/// ```ignore
+/// bitfield! {
+///     pub struct Rgb565(u16) {
+///         blue, set_blue: 0..5;
+///         green, set_green: 5..11;
+///         red, set_red: 11..16;
+///     }
+/// }
+/// ```
+///
+/// 每个字段的区间都是左闭右开的位下标, 越界和重叠在编译期就会报错。
+/// setter 的名字需要显式给出, 因为 macro_rules! 没办法拼接标识符。
+/// 底层的整数必须是无符号的: 有符号整数的 >> 是算术右移, 掩码和读出的字段都会带上符号位,
+/// 所以同样在编译期拒绝:
+///
+/// ```compile_fail,E0080
+/// dive_into_rust_of_fcc::bitfield! {
+///     pub struct Signed(i16) {
+///         low, set_low: 0..4;
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! bitfield {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($int:ty) {
+            $($field:ident, $setter:ident: $lo:literal..$hi:literal;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        $vis struct $name($int);
+
+        // 编译期检查: 整数是无符号的, 每个字段都在整数宽度之内, 且互不重叠
+        const _: () = {
+            assert!(<$int>::MIN == 0, "bitfield: the backing integer must be unsigned");
+            let bits = (std::mem::size_of::<$int>() * 8) as u32;
+            let fields: &[(u32, u32)] = &[$(($lo, $hi)),*];
+            let mut used: u128 = 0;
+            let mut i = 0;
+            while i < fields.len() {
+                let (lo, hi) = fields[i];
+                assert!(lo < hi, "bitfield: empty field range");
+                assert!(hi <= bits, "bitfield: field exceeds the integer width");
+                let mask = (u128::MAX >> (128 - (hi - lo))) << lo;
+                assert!(used & mask == 0, "bitfield: overlapping fields");
+                used |= mask;
+                i += 1;
+            }
+        };
+
+        #[allow(dead_code)]
+        impl $name {
+            pub const fn new() -> $name {
+                $name(0)
+            }
+
+            pub const fn from_raw(raw: $int) -> $name {
+                $name(raw)
+            }
+
+            pub const fn raw(self) -> $int {
+                self.0
+            }
+
+            $(
+                pub const fn $field(self) -> $int {
+                    let width = $hi - $lo;
+                    let mask = (!(0 as $int)) >> ((std::mem::size_of::<$int>() * 8) as u32 - width);
+                    (self.0 >> $lo) & mask
+                }
+
+                /// 写入字段, 数值超出字段宽度时 panic
+                pub fn $setter(&mut self, value: $int) -> &mut $name {
+                    let width = $hi - $lo;
+                    let mask = (!(0 as $int)) >> ((std::mem::size_of::<$int>() * 8) as u32 - width);
+                    assert!(
+                        value & !mask == 0,
+                        "value {} does not fit in the {}-bit field `{}`",
+                        value,
+                        width,
+                        stringify!($field)
+                    );
+                    self.0 = (self.0 & !(mask << $lo)) | (value << $lo);
+                    self
+                }
+            )*
+        }
+
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    $(.field(stringify!($field), &self.$field()))*
+                    .finish()
+            }
+        }
+
+        impl std::fmt::Binary for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                if f.alternate() {
+                    f.write_str("0b")?;
+                }
+                f.write_str(&$crate::ch03::fourth::grouped_binary(
+                    self.0 as u128,
+                    (std::mem::size_of::<$int>() * 8) as u32,
+                ))
+            }
+        }
+    };
+}
+
+bitfield! {
+    /// RGB565 格式的颜色, 常见于嵌入式设备的屏幕
+    pub struct Rgb565(u16) {
+        blue, set_blue: 0..5;
+        green, set_green: 5..11;
+        red, set_red: 11..16;
+    }
+}
+
+bitfield! {
+    /// 类似 Unix 文件权限的标志位
+    pub struct Mode(u16) {
+        other, set_other: 0..3;
+        group, set_group: 3..6;
+        user, set_user: 6..9;
+        sticky, set_sticky: 9..10;
+    }
+}
+
+// 编译错误: 字段重叠
+// bitfield! { struct Bad(u8) { a, set_a: 0..4; b, set_b: 3..8; } }
+
+pub fn learn_bitset() {
+    // 和 learn_bit_op 一样的两个数
+    let num1: BitSet64 = (0..8).filter(|i| 0b_1010_1010u8 >> i & 1 == 1).collect();
+    let num2: BitSet64 = (4..8).collect();
+    println!("{:?} {:?}", num1, num2);
+    println!("{:b}", num1 & num2);
+    println!("{:b}", num1 | num2);
+    println!("{:b}", num1 ^ num2);
+    println!("{:b}", num1 - num2);
+    assert_eq!((num1 & num2).iter().collect::<Vec<_>>(), vec![5, 7]);
+    assert_eq!((num1 | num2).len(), 6);
+    assert_eq!((!num1).len(), 60);
+
+    let mut s = BitSet256::new();
+    for i in (0..256).step_by(3) {
+        s.set(i);
+    }
+    assert!(s.get(255));
+    assert!(!s.toggle(255));
+    assert!(!s.clear(254));
+    // rank 和 select 互为逆运算
+    assert_eq!(s.rank(100), 34);
+    assert_eq!(s.select(34), Some(102));
+    for k in 0..s.len() {
+        assert_eq!(s.rank(s.select(k).unwrap()), k);
+    }
+    assert_eq!(s.select(s.len()), None);
+    assert!(num2.is_subset(&(num1 | num2)));
+
+    // 可增长的位集合
+    let mut v = BitVec::new();
+    v.set(3);
+    v.set(200);
+    println!("{:?} capacity {}", v, v.capacity());
+    let w: BitVec = vec![3, 4, 5].into_iter().collect();
+    assert_eq!((&v & &w).iter().collect::<Vec<_>>(), vec![3]);
+    assert_eq!((&v | &w).len(), 4);
+    assert_eq!((&v - &w).iter().collect::<Vec<_>>(), vec![200]);
+    v.clear(200);
+    // 虽然容量不同, 但内容一样
+    assert_eq!(v, [3].iter().copied().collect());
+    println!("{:#b}", &v ^ &w);
+
+    // 位域
+    let mut c = Rgb565::new();
+    c.set_red(31).set_green(0).set_blue(31);
+    println!("{:?} {:b} {:#06x}", c, c, c.raw());
+    // 红 11111, 绿 000000, 蓝 11111
+    assert_eq!(c.raw(), 0xf81f);
+    assert_eq!(Rgb565::from_raw(0x07e0).green(), 63);
+
+    let mut m = Mode::new();
+    m.set_user(0o7).set_group(0o5).set_other(0o5);
+    println!("{:?} {:o}", m, m.raw());
+    assert_eq!(m.raw(), 0o755);
+}
@@ -1,2 +1,4 @@
 pub mod first;
+pub mod fourth;
 pub mod second;
+pub mod third;
@@ -0,0 +1,1327 @@
+#![allow(dead_code)]
+
+// 表达式解释器
+// first 和 second 两节讲的都是"Rust 里几乎一切都是表达式":
+// 语句块、if、loop 都有值, 赋值表达式的值是 (), && 和 || 会短路而 & 和 | 不会。
+// 这里实现一个很小的解释器, 支持 Rust 表达式语法的一个子集:
+// 1. 整数(i64)、bool、() 三种值
+// 2. let / let mut, 赋值与组合赋值 += -= *= /= %= &= |= ^= <<= >>=
+// 3. 算术、位运算、比较、&& || (短路) 以及 bool 上的 & | ^ (不短路)
+// 4. 语句块、if-else、loop、while、for x in a..b, 带标签的 break/continue, break 带值
+// 5. 内置函数 f1() f2() (和 first::learn_bool_op 里的一样, 会打印一行字) 以及 print(x)
+// 每个表达式求值之后都会记录到 trace 里, 没有被求值的操作数也会被标记出来,
+// 这样就能直观地看到"哪些表达式被求值了、值和类型分别是什么"。
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// 解释器里的值
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    Unit,
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "i64",
+            Value::Bool(_) => "bool",
+            Value::Unit => "()",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
+
+/// 解析错误或者运行时错误, pos 是出错位置在源码中的字节偏移
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Parse { pos: usize, msg: String },
+    Eval { pos: usize, msg: String },
+}
+
+impl Error {
+    /// 把字节偏移换算成 行:列, 方便阅读
+    pub fn locate(&self, src: &str) -> (usize, usize) {
+        let pos = match self {
+            Error::Parse { pos, .. } | Error::Eval { pos, .. } => *pos,
+        };
+        let before = &src[..pos.min(src.len())];
+        let line = before.matches('\n').count() + 1;
+        let col = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+        (line, col)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse { pos, msg } => write!(f, "parse error at {}: {}", pos, msg),
+            Error::Eval { pos, msg } => write!(f, "runtime error at {}: {}", pos, msg),
+        }
+    }
+}
+
+// ---------- 词法分析 ----------
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Int(i64),
+    Ident(String),
+    Label(String),
+    Punct(&'static str),
+    Eof,
+}
+
+// 多字符的符号要排在前面, 保证最长匹配
+const PUNCTS: [&str; 39] = [
+    "<<=", ">>=", "..", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "&=",
+    "|=", "^=", "<<", ">>", "+", "-", "*", "/", "%", "&", "|", "^", "!", "<", ">", "=", "(", ")",
+    "{", "}", ";", ":", ",", "'",
+];
+
+fn lex(src: &str) -> Result<Vec<(Tok, usize, usize)>, Error> {
+    let bytes = src.as_bytes();
+    let mut toks = Vec::new();
+    let mut i = 0;
+    'outer: while i < bytes.len() {
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if src[i..].starts_with("//") {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+        let start = i;
+        if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let text: String = src[start..i].chars().filter(|&c| c != '_').collect();
+            let v = text.parse::<i64>().map_err(|_| Error::Parse {
+                pos: start,
+                msg: format!("integer literal `{}` is too large", &src[start..i]),
+            })?;
+            toks.push((Tok::Int(v), start, i));
+            continue;
+        }
+        let ident_len = |from: usize| {
+            bytes[from..]
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                .count()
+        };
+        if c.is_ascii_alphabetic() || c == b'_' {
+            i += ident_len(i);
+            toks.push((Tok::Ident(src[start..i].to_string()), start, i));
+            continue;
+        }
+        if c == b'\'' {
+            let n = ident_len(i + 1);
+            if n == 0 {
+                return Err(Error::Parse {
+                    pos: start,
+                    msg: "expected a label after `'`".to_string(),
+                });
+            }
+            i += 1 + n;
+            toks.push((Tok::Label(src[start + 1..i].to_string()), start, i));
+            continue;
+        }
+        for p in PUNCTS.iter() {
+            if src[i..].starts_with(p) {
+                i += p.len();
+                toks.push((Tok::Punct(p), start, i));
+                continue 'outer;
+            }
+        }
+        let ch = src[i..].chars().next().unwrap();
+        return Err(Error::Parse {
+            pos: start,
+            msg: format!("unexpected character `{}`", ch),
+        });
+    }
+    toks.push((Tok::Eof, src.len(), src.len()));
+    Ok(toks)
+}
+
+// ---------- 语法树 ----------
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Ty {
+    Int,
+    Bool,
+    Unit,
+}
+
+#[derive(Debug)]
+struct Expr {
+    kind: ExprKind,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug)]
+enum ExprKind {
+    Lit(Value),
+    Var(String),
+    Call(String, Vec<Expr>),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Assign(Option<BinOp>, String, Box<Expr>),
+    Block(Vec<Stmt>, Option<Box<Expr>>),
+    If(Box<Expr>, Box<Expr>, Option<Box<Expr>>),
+    Loop(Option<String>, Box<Expr>),
+    While(Option<String>, Box<Expr>, Box<Expr>),
+    For(Option<String>, String, Box<Expr>, Box<Expr>, Box<Expr>),
+    Break(Option<String>, Option<Box<Expr>>),
+    Continue(Option<String>),
+}
+
+impl ExprKind {
+    // 以语句块结尾的表达式, 作为语句时可以省略分号
+    fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            ExprKind::Block(..)
+                | ExprKind::If(..)
+                | ExprKind::Loop(..)
+                | ExprKind::While(..)
+                | ExprKind::For(..)
+        )
+    }
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Let {
+        mutable: bool,
+        name: String,
+        ty: Option<Ty>,
+        init: Expr,
+    },
+    Expr(Expr),
+}
+
+// ---------- 语法分析 ----------
+
+struct Parser {
+    toks: Vec<(Tok, usize, usize)>,
+    pos: usize,
+}
+
+// 二元运算符的优先级, 数字越大结合得越紧
+fn binary_op(tok: &Tok) -> Option<(BinOp, u8)> {
+    let p = match tok {
+        Tok::Punct(p) => *p,
+        _ => return None,
+    };
+    let op = match p {
+        "||" => (BinOp::Or, 1),
+        "&&" => (BinOp::And, 2),
+        "==" => (BinOp::Eq, 3),
+        "!=" => (BinOp::Ne, 3),
+        "<" => (BinOp::Lt, 3),
+        "<=" => (BinOp::Le, 3),
+        ">" => (BinOp::Gt, 3),
+        ">=" => (BinOp::Ge, 3),
+        "|" => (BinOp::BitOr, 4),
+        "^" => (BinOp::BitXor, 5),
+        "&" => (BinOp::BitAnd, 6),
+        "<<" => (BinOp::Shl, 7),
+        ">>" => (BinOp::Shr, 7),
+        "+" => (BinOp::Add, 8),
+        "-" => (BinOp::Sub, 8),
+        "*" => (BinOp::Mul, 9),
+        "/" => (BinOp::Div, 9),
+        "%" => (BinOp::Rem, 9),
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn compound_op(tok: &Tok) -> Option<Option<BinOp>> {
+    let op = match tok {
+        Tok::Punct("=") => None,
+        Tok::Punct("+=") => Some(BinOp::Add),
+        Tok::Punct("-=") => Some(BinOp::Sub),
+        Tok::Punct("*=") => Some(BinOp::Mul),
+        Tok::Punct("/=") => Some(BinOp::Div),
+        Tok::Punct("%=") => Some(BinOp::Rem),
+        Tok::Punct("&=") => Some(BinOp::BitAnd),
+        Tok::Punct("|=") => Some(BinOp::BitOr),
+        Tok::Punct("^=") => Some(BinOp::BitXor),
+        Tok::Punct("<<=") => Some(BinOp::Shl),
+        Tok::Punct(">>=") => Some(BinOp::Shr),
+        _ => return None,
+    };
+    Some(op)
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.toks[self.pos].0
+    }
+
+    fn peek_at(&self, n: usize) -> &Tok {
+        &self.toks[(self.pos + n).min(self.toks.len() - 1)].0
+    }
+
+    fn start(&self) -> usize {
+        self.toks[self.pos].1
+    }
+
+    // 上一个已经消费掉的 token 的结束位置
+    fn end(&self) -> usize {
+        self.toks[self.pos.saturating_sub(1)].2
+    }
+
+    fn bump(&mut self) -> Tok {
+        let tok = self.toks[self.pos].0.clone();
+        if self.pos < self.toks.len() - 1 {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn error<T>(&self, msg: String) -> Result<T, Error> {
+        Err(Error::Parse {
+            pos: self.start(),
+            msg,
+        })
+    }
+
+    fn is_punct(&self, p: &str) -> bool {
+        matches!(self.peek(), Tok::Punct(q) if *q == p)
+    }
+
+    fn is_keyword(&self, kw: &str) -> bool {
+        matches!(self.peek(), Tok::Ident(s) if s == kw)
+    }
+
+    fn expect(&mut self, p: &str) -> Result<(), Error> {
+        if self.is_punct(p) {
+            self.bump();
+            Ok(())
+        } else {
+            self.error(format!("expected `{}`, found {:?}", p, self.peek()))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, Error> {
+        match self.peek().clone() {
+            Tok::Ident(s) if !is_keyword(&s) => {
+                self.bump();
+                Ok(s)
+            }
+            t => self.error(format!("expected identifier, found {:?}", t)),
+        }
+    }
+
+    fn node(&self, kind: ExprKind, start: usize) -> Expr {
+        Expr {
+            kind,
+            start,
+            end: self.end(),
+        }
+    }
+
+    // 程序本身就是一个没有大括号的语句块
+    fn program(&mut self) -> Result<Expr, Error> {
+        let start = self.start();
+        let (stmts, tail) = self.stmts()?;
+        if *self.peek() != Tok::Eof {
+            return self.error(format!("unexpected {:?}", self.peek()));
+        }
+        Ok(self.node(ExprKind::Block(stmts, tail), start))
+    }
+
+    fn block(&mut self) -> Result<Expr, Error> {
+        let start = self.start();
+        self.expect("{")?;
+        let (stmts, tail) = self.stmts()?;
+        self.expect("}")?;
+        Ok(self.node(ExprKind::Block(stmts, tail), start))
+    }
+
+    fn stmts(&mut self) -> Result<(Vec<Stmt>, Option<Box<Expr>>), Error> {
+        let mut stmts = Vec::new();
+        loop {
+            if self.is_punct("}") || *self.peek() == Tok::Eof {
+                return Ok((stmts, None));
+            }
+            if self.is_punct(";") {
+                self.bump();
+                continue;
+            }
+            if self.is_keyword("let") {
+                stmts.push(self.let_stmt()?);
+                continue;
+            }
+            let block_like = self.starts_block_like();
+            let e = if block_like {
+                self.block_like()?
+            } else {
+                self.expr()?
+            };
+            if self.is_punct(";") {
+                self.bump();
+                stmts.push(Stmt::Expr(e));
+            } else if self.is_punct("}") || *self.peek() == Tok::Eof {
+                return Ok((stmts, Some(Box::new(e))));
+            } else if block_like {
+                // 语句位置上的 if/loop/{} 可以不写分号
+                stmts.push(Stmt::Expr(e));
+            } else {
+                return self.error(format!("expected `;`, found {:?}", self.peek()));
+            }
+        }
+    }
+
+    fn let_stmt(&mut self) -> Result<Stmt, Error> {
+        self.bump();
+        let mutable = self.is_keyword("mut");
+        if mutable {
+            self.bump();
+        }
+        let name = self.ident()?;
+        let ty = if self.is_punct(":") {
+            self.bump();
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        self.expect("=")?;
+        let init = self.expr()?;
+        self.expect(";")?;
+        Ok(Stmt::Let {
+            mutable,
+            name,
+            ty,
+            init,
+        })
+    }
+
+    fn ty(&mut self) -> Result<Ty, Error> {
+        if self.is_punct("(") {
+            self.bump();
+            self.expect(")")?;
+            return Ok(Ty::Unit);
+        }
+        match self.peek().clone() {
+            Tok::Ident(ref s) if s == "i64" => {
+                self.bump();
+                Ok(Ty::Int)
+            }
+            Tok::Ident(ref s) if s == "bool" => {
+                self.bump();
+                Ok(Ty::Bool)
+            }
+            t => self.error(format!("unsupported type {:?}", t)),
+        }
+    }
+
+    fn starts_block_like(&self) -> bool {
+        match self.peek() {
+            Tok::Punct("{") => true,
+            Tok::Label(_) => true,
+            Tok::Ident(s) => matches!(s.as_str(), "if" | "loop" | "while" | "for"),
+            _ => false,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        let start = self.start();
+        // 赋值表达式的左边只能是一个变量
+        if let Tok::Ident(name) = self.peek().clone() {
+            if !is_keyword(&name) {
+                if let Some(op) = compound_op(self.peek_at(1)) {
+                    self.bump();
+                    self.bump();
+                    let rhs = self.expr()?;
+                    return Ok(self.node(ExprKind::Assign(op, name, Box::new(rhs)), start));
+                }
+            }
+        }
+        self.binary(0)
+    }
+
+    fn binary(&mut self, min_prec: u8) -> Result<Expr, Error> {
+        let start = self.start();
+        let mut lhs = self.unary()?;
+        while let Some((op, prec)) = binary_op(self.peek()) {
+            if prec <= min_prec {
+                break;
+            }
+            self.bump();
+            let rhs = self.binary(prec)?;
+            // 比较运算符不能连写, a < b < c 在 Rust 里是编译错误
+            if prec == 3 {
+                if let Some((_, 3)) = binary_op(self.peek()) {
+                    return self.error("comparison operators cannot be chained".to_string());
+                }
+            }
+            lhs = self.node(ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), start);
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        let start = self.start();
+        let op = match self.peek() {
+            Tok::Punct("-") => UnOp::Neg,
+            Tok::Punct("!") => UnOp::Not,
+            _ => return self.primary(),
+        };
+        self.bump();
+        let e = self.unary()?;
+        Ok(self.node(ExprKind::Unary(op, Box::new(e)), start))
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        let start = self.start();
+        if self.starts_block_like() {
+            return self.block_like();
+        }
+        match self.peek().clone() {
+            Tok::Int(v) => {
+                self.bump();
+                Ok(self.node(ExprKind::Lit(Value::Int(v)), start))
+            }
+            Tok::Punct("(") => {
+                self.bump();
+                if self.is_punct(")") {
+                    self.bump();
+                    return Ok(self.node(ExprKind::Lit(Value::Unit), start));
+                }
+                let e = self.expr()?;
+                self.expect(")")?;
+                Ok(e)
+            }
+            Tok::Ident(s) => match s.as_str() {
+                "true" | "false" => {
+                    self.bump();
+                    Ok(self.node(ExprKind::Lit(Value::Bool(s == "true")), start))
+                }
+                "break" => {
+                    self.bump();
+                    let label = self.label();
+                    let value = if self.can_start_expr() {
+                        Some(Box::new(self.expr()?))
+                    } else {
+                        None
+                    };
+                    Ok(self.node(ExprKind::Break(label, value), start))
+                }
+                "continue" => {
+                    self.bump();
+                    let label = self.label();
+                    Ok(self.node(ExprKind::Continue(label), start))
+                }
+                _ => {
+                    let name = self.ident()?;
+                    if !self.is_punct("(") {
+                        return Ok(self.node(ExprKind::Var(name), start));
+                    }
+                    self.bump();
+                    let mut args = Vec::new();
+                    while !self.is_punct(")") {
+                        args.push(self.expr()?);
+                        if !self.is_punct(")") {
+                            self.expect(",")?;
+                        }
+                    }
+                    self.bump();
+                    Ok(self.node(ExprKind::Call(name, args), start))
+                }
+            },
+            t => self.error(format!("expected expression, found {:?}", t)),
+        }
+    }
+
+    fn label(&mut self) -> Option<String> {
+        if let Tok::Label(l) = self.peek().clone() {
+            self.bump();
+            Some(l)
+        } else {
+            None
+        }
+    }
+
+    fn can_start_expr(&self) -> bool {
+        !matches!(
+            self.peek(),
+            Tok::Eof | Tok::Punct(";") | Tok::Punct("}") | Tok::Punct(")") | Tok::Punct(",")
+        )
+    }
+
+    fn block_like(&mut self) -> Result<Expr, Error> {
+        let start = self.start();
+        let label = self.label();
+        if label.is_some() {
+            self.expect(":")?;
+            if !(self.is_keyword("loop") || self.is_keyword("while") || self.is_keyword("for")) {
+                return self.error("labels are only allowed on loops".to_string());
+            }
+        }
+        if self.is_punct("{") {
+            return self.block();
+        }
+        let kw = match self.bump() {
+            Tok::Ident(kw) => kw,
+            _ => unreachable!(),
+        };
+        let kind = match kw.as_str() {
+            "if" => {
+                let cond = self.expr()?;
+                let then = self.block()?;
+                let els = if self.is_keyword("else") {
+                    self.bump();
+                    // else if 就是 else 后面跟了另一个 if 表达式
+                    if self.is_keyword("if") {
+                        Some(Box::new(self.block_like()?))
+                    } else {
+                        Some(Box::new(self.block()?))
+                    }
+                } else {
+                    None
+                };
+                ExprKind::If(Box::new(cond), Box::new(then), els)
+            }
+            "loop" => ExprKind::Loop(label, Box::new(self.block()?)),
+            "while" => {
+                let cond = self.expr()?;
+                ExprKind::While(label, Box::new(cond), Box::new(self.block()?))
+            }
+            "for" => {
+                let var = self.ident()?;
+                if !self.is_keyword("in") {
+                    return self.error("expected `in`".to_string());
+                }
+                self.bump();
+                // 区间的两端不能再包含 .. , 所以从比较运算符以上的优先级开始解析
+                let lo = self.binary(3)?;
+                self.expect("..")?;
+                let hi = self.binary(3)?;
+                let body = self.block()?;
+                ExprKind::For(label, var, Box::new(lo), Box::new(hi), Box::new(body))
+            }
+            _ => unreachable!(),
+        };
+        Ok(self.node(kind, start))
+    }
+}
+
+fn is_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "let"
+            | "mut"
+            | "if"
+            | "else"
+            | "loop"
+            | "while"
+            | "for"
+            | "in"
+            | "break"
+            | "continue"
+            | "true"
+            | "false"
+    )
+}
+
+// ---------- 求值 ----------
+
+/// 求值过程中的一个事件
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// 表达式求值完成, depth 是嵌套深度
+    Value {
+        depth: usize,
+        text: String,
+        value: Value,
+    },
+    /// 表达式发散了(break/continue), 没有产生值, 类型是 !
+    Diverge {
+        depth: usize,
+        text: String,
+        what: String,
+    },
+    /// 因为短路或条件不满足而没有被求值的表达式
+    Skipped {
+        depth: usize,
+        text: String,
+        why: &'static str,
+    },
+    /// 内置函数打印的内容
+    Output(String),
+}
+
+/// 一次运行的结果: 最终的值和完整的求值过程
+#[derive(Debug)]
+pub struct Run {
+    pub value: Value,
+    pub trace: Vec<Event>,
+}
+
+impl Run {
+    pub fn output(&self) -> Vec<&str> {
+        self.trace
+            .iter()
+            .filter_map(|e| match e {
+                Event::Output(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Run {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for e in &self.trace {
+            match e {
+                Event::Value { depth, text, value } => writeln!(
+                    f,
+                    "{:w$}{} : {} = {}",
+                    "",
+                    text,
+                    value.type_name(),
+                    value,
+                    w = depth * 2
+                )?,
+                Event::Diverge { depth, text, what } => {
+                    writeln!(f, "{:w$}{} : ! ({})", "", text, what, w = depth * 2)?
+                }
+                Event::Skipped { depth, text, why } => {
+                    writeln!(f, "{:w$}{} 未求值 ({})", "", text, why, w = depth * 2)?
+                }
+                Event::Output(s) => writeln!(f, "> {}", s)?,
+            }
+        }
+        write!(f, "=> {} : {}", self.value, self.value.type_name())
+    }
+}
+
+// 控制流: break/continue 会一直向外传播, 直到被对应的循环接住
+enum Flow {
+    Break {
+        pos: usize,
+        label: Option<String>,
+        value: Option<Value>,
+    },
+    Continue {
+        pos: usize,
+        label: Option<String>,
+    },
+    Error(Error),
+}
+
+impl From<Error> for Flow {
+    fn from(e: Error) -> Flow {
+        Flow::Error(e)
+    }
+}
+
+struct Binding {
+    value: Value,
+    mutable: bool,
+}
+
+struct Interp<'s> {
+    src: &'s str,
+    scopes: Vec<HashMap<String, Binding>>,
+    trace: Vec<Event>,
+    depth: usize,
+    // 防止死循环把 trace 撑爆
+    steps: usize,
+}
+
+const MAX_STEPS: usize = 100_000;
+
+fn eval_err(e: &Expr, msg: String) -> Flow {
+    Flow::Error(Error::Eval { pos: e.start, msg })
+}
+
+impl<'s> Interp<'s> {
+    // 把源码片段压成一行, 太长的截断
+    fn text(&self, e: &Expr) -> String {
+        let s = self.src[e.start..e.end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if s.chars().count() > 48 {
+            let head: String = s.chars().take(45).collect();
+            format!("{}...", head)
+        } else {
+            s
+        }
+    }
+
+    fn skipped(&mut self, e: &Expr, why: &'static str) {
+        let text = self.text(e);
+        self.trace.push(Event::Skipped {
+            depth: self.depth + 1,
+            text,
+            why,
+        });
+    }
+
+    fn eval(&mut self, e: &Expr) -> Result<Value, Flow> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(eval_err(e, "evaluation step limit exceeded".to_string()));
+        }
+        self.depth += 1;
+        let r = self.eval_inner(e);
+        self.depth -= 1;
+        let text = self.text(e);
+        match &r {
+            Ok(v) => self.trace.push(Event::Value {
+                depth: self.depth,
+                text,
+                value: *v,
+            }),
+            Err(Flow::Break { label, .. }) => self.trace.push(Event::Diverge {
+                depth: self.depth,
+                text,
+                what: match label {
+                    Some(l) => format!("break '{}", l),
+                    None => "break".to_string(),
+                },
+            }),
+            Err(Flow::Continue { label, .. }) => self.trace.push(Event::Diverge {
+                depth: self.depth,
+                text,
+                what: match label {
+                    Some(l) => format!("continue '{}", l),
+                    None => "continue".to_string(),
+                },
+            }),
+            Err(Flow::Error(_)) => {}
+        }
+        r
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().find_map(|s| s.get_mut(name))
+    }
+
+    fn eval_inner(&mut self, e: &Expr) -> Result<Value, Flow> {
+        match &e.kind {
+            ExprKind::Lit(v) => Ok(*v),
+            ExprKind::Var(name) => match self.lookup(name) {
+                Some(b) => Ok(b.value),
+                None => Err(eval_err(e, format!("cannot find value `{}`", name))),
+            },
+            ExprKind::Call(name, args) => {
+                let mut values = Vec::new();
+                for a in args {
+                    values.push(self.eval(a)?);
+                }
+                self.call(e, name, &values)
+            }
+            ExprKind::Unary(op, inner) => {
+                let v = self.eval(inner)?;
+                match (op, v) {
+                    (UnOp::Neg, Value::Int(i)) => i
+                        .checked_neg()
+                        .map(Value::Int)
+                        .ok_or_else(|| eval_err(e, "attempt to negate with overflow".to_string())),
+                    (UnOp::Not, Value::Int(i)) => Ok(Value::Int(!i)),
+                    (UnOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                    _ => Err(eval_err(
+                        e,
+                        format!("cannot apply unary operator to type `{}`", v.type_name()),
+                    )),
+                }
+            }
+            ExprKind::Binary(op @ BinOp::And, l, r) | ExprKind::Binary(op @ BinOp::Or, l, r) => {
+                let lv = self.eval(l)?;
+                let lb = self.expect_bool(l, lv)?;
+                // 短路: && 左边为 false 或者 || 左边为 true 时, 右边不再求值
+                if (*op == BinOp::And) != lb {
+                    self.skipped(r, "短路");
+                    return Ok(Value::Bool(lb));
+                }
+                let rv = self.eval(r)?;
+                Ok(Value::Bool(self.expect_bool(r, rv)?))
+            }
+            ExprKind::Binary(op, l, r) => {
+                // 其他二元运算符两边都会求值, 包括 bool 上的 & | ^
+                let lv = self.eval(l)?;
+                let rv = self.eval(r)?;
+                binary(*op, lv, rv).map_err(|msg| eval_err(e, msg))
+            }
+            ExprKind::Assign(op, name, rhs) => {
+                let rv = self.eval(rhs)?;
+                let b = match self.lookup(name) {
+                    Some(b) => b,
+                    None => return Err(eval_err(e, format!("cannot find value `{}`", name))),
+                };
+                if !b.mutable {
+                    return Err(eval_err(
+                        e,
+                        format!("cannot assign twice to immutable variable `{}`", name),
+                    ));
+                }
+                let nv = match op {
+                    Some(op) => binary(*op, b.value, rv).map_err(|msg| eval_err(e, msg))?,
+                    None => rv,
+                };
+                if nv.type_name() != b.value.type_name() {
+                    return Err(eval_err(
+                        e,
+                        format!(
+                            "mismatched types: expected `{}`, found `{}`",
+                            b.value.type_name(),
+                            nv.type_name()
+                        ),
+                    ));
+                }
+                b.value = nv;
+                // 赋值表达式的类型是 ()
+                Ok(Value::Unit)
+            }
+            ExprKind::Block(stmts, tail) => {
+                self.scopes.push(HashMap::new());
+                let r = self.block(stmts, tail);
+                self.scopes.pop();
+                r
+            }
+            ExprKind::If(cond, then, els) => {
+                let cv = self.eval(cond)?;
+                if self.expect_bool(cond, cv)? {
+                    let v = self.eval(then)?;
+                    match els {
+                        Some(els) => self.skipped(els, "条件为 true"),
+                        // 没有 else 分支时, if 分支的类型必须是 ()
+                        None if v != Value::Unit => {
+                            return Err(eval_err(
+                                then,
+                                format!(
+                                    "`if` without `else` must have type `()`, found `{}`",
+                                    v.type_name()
+                                ),
+                            ))
+                        }
+                        None => {}
+                    }
+                    Ok(v)
+                } else {
+                    self.skipped(then, "条件为 false");
+                    match els {
+                        Some(els) => self.eval(els),
+                        None => Ok(Value::Unit),
+                    }
+                }
+            }
+            ExprKind::Loop(label, body) => loop {
+                match self.eval(body) {
+                    Ok(_) => {}
+                    Err(flow) => match self.catch(label, flow, true)? {
+                        Some(v) => return Ok(v),
+                        None => continue,
+                    },
+                }
+            },
+            ExprKind::While(label, cond, body) => loop {
+                let cv = self.eval(cond)?;
+                if !self.expect_bool(cond, cv)? {
+                    return Ok(Value::Unit);
+                }
+                if let Err(flow) = self.eval(body) {
+                    if self.catch(label, flow, false)?.is_some() {
+                        return Ok(Value::Unit);
+                    }
+                }
+            },
+            ExprKind::For(label, var, lo, hi, body) => {
+                let lv = self.eval(lo)?;
+                let lo_v = self.expect_int(lo, lv)?;
+                let hv = self.eval(hi)?;
+                let hi_v = self.expect_int(hi, hv)?;
+                for i in lo_v..hi_v {
+                    let mut scope = HashMap::new();
+                    scope.insert(
+                        var.clone(),
+                        Binding {
+                            value: Value::Int(i),
+                            mutable: false,
+                        },
+                    );
+                    self.scopes.push(scope);
+                    let r = self.eval(body);
+                    self.scopes.pop();
+                    if let Err(flow) = r {
+                        if self.catch(label, flow, false)?.is_some() {
+                            break;
+                        }
+                    }
+                }
+                Ok(Value::Unit)
+            }
+            ExprKind::Break(label, value) => {
+                let value = match value {
+                    Some(v) => Some(self.eval(v)?),
+                    None => None,
+                };
+                Err(Flow::Break {
+                    pos: e.start,
+                    label: label.clone(),
+                    value,
+                })
+            }
+            ExprKind::Continue(label) => Err(Flow::Continue {
+                pos: e.start,
+                label: label.clone(),
+            }),
+        }
+    }
+
+    // 循环接住属于自己的 break/continue
+    // 返回 Some(value) 表示循环结束, None 表示继续下一轮, Err 表示继续向外传播
+    fn catch(
+        &mut self,
+        own: &Option<String>,
+        flow: Flow,
+        allow_value: bool,
+    ) -> Result<Option<Value>, Flow> {
+        let mine = |label: &Option<String>| label.is_none() || label == own;
+        match flow {
+            Flow::Break { pos, label, value } if mine(&label) => match value {
+                Some(_) if !allow_value => Err(Flow::Error(Error::Eval {
+                    pos,
+                    msg: "`break` with value is only allowed in `loop`".to_string(),
+                })),
+                v => Ok(Some(v.unwrap_or(Value::Unit))),
+            },
+            Flow::Continue { label, .. } if mine(&label) => Ok(None),
+            other => Err(other),
+        }
+    }
+
+    fn block(&mut self, stmts: &[Stmt], tail: &Option<Box<Expr>>) -> Result<Value, Flow> {
+        for s in stmts {
+            match s {
+                Stmt::Let {
+                    mutable,
+                    name,
+                    ty,
+                    init,
+                } => {
+                    let v = self.eval(init)?;
+                    if let Some(ty) = ty {
+                        let ok = matches!(
+                            (ty, v),
+                            (Ty::Int, Value::Int(_))
+                                | (Ty::Bool, Value::Bool(_))
+                                | (Ty::Unit, Value::Unit)
+                        );
+                        if !ok {
+                            return Err(eval_err(
+                                init,
+                                format!("mismatched types: found `{}`", v.type_name()),
+                            ));
+                        }
+                    }
+                    self.scopes.last_mut().unwrap().insert(
+                        name.clone(),
+                        Binding {
+                            value: v,
+                            mutable: *mutable,
+                        },
+                    );
+                }
+                Stmt::Expr(e) => {
+                    let v = self.eval(e)?;
+                    if e.kind.is_block_like() && v != Value::Unit && !self.ends_with_semi(e) {
+                        return Err(eval_err(
+                            e,
+                            format!("expected `()`, found `{}`; add a `;`", v.type_name()),
+                        ));
+                    }
+                }
+            }
+        }
+        match tail {
+            Some(t) => self.eval(t),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn ends_with_semi(&self, e: &Expr) -> bool {
+        self.src[e.end..].trim_start().starts_with(';')
+    }
+
+    fn expect_bool(&self, e: &Expr, v: Value) -> Result<bool, Flow> {
+        match v {
+            Value::Bool(b) => Ok(b),
+            _ => Err(eval_err(
+                e,
+                format!(
+                    "mismatched types: expected `bool`, found `{}`",
+                    v.type_name()
+                ),
+            )),
+        }
+    }
+
+    fn expect_int(&self, e: &Expr, v: Value) -> Result<i64, Flow> {
+        match v {
+            Value::Int(i) => Ok(i),
+            _ => Err(eval_err(
+                e,
+                format!(
+                    "mismatched types: expected `i64`, found `{}`",
+                    v.type_name()
+                ),
+            )),
+        }
+    }
+
+    fn call(&mut self, e: &Expr, name: &str, args: &[Value]) -> Result<Value, Flow> {
+        match (name, args) {
+            ("f1", []) => {
+                self.trace.push(Event::Output("Call f1".to_string()));
+                Ok(Value::Bool(true))
+            }
+            ("f2", []) => {
+                self.trace.push(Event::Output("Call f2".to_string()));
+                Ok(Value::Bool(false))
+            }
+            ("print", args) => {
+                let s: Vec<String> = args.iter().map(|v| v.to_string()).collect();
+                self.trace.push(Event::Output(s.join(" ")));
+                Ok(Value::Unit)
+            }
+            _ => Err(eval_err(
+                e,
+                format!(
+                    "cannot find function `{}` taking {} argument(s)",
+                    name,
+                    args.len()
+                ),
+            )),
+        }
+    }
+}
+
+fn binary(op: BinOp, l: Value, r: Value) -> Result<Value, String> {
+    use BinOp::*;
+    let overflow = |what: &str| format!("attempt to {} with overflow", what);
+    match (l, r) {
+        (Value::Int(a), Value::Int(b)) => {
+            let v = match op {
+                Add => a.checked_add(b).ok_or_else(|| overflow("add"))?,
+                Sub => a.checked_sub(b).ok_or_else(|| overflow("subtract"))?,
+                Mul => a.checked_mul(b).ok_or_else(|| overflow("multiply"))?,
+                Div if b == 0 => return Err("attempt to divide by zero".to_string()),
+                Div => a.checked_div(b).ok_or_else(|| overflow("divide"))?,
+                Rem if b == 0 => {
+                    return Err(
+                        "attempt to calculate the remainder with a divisor of zero".to_string()
+                    )
+                }
+                Rem => a
+                    .checked_rem(b)
+                    .ok_or_else(|| overflow("calculate the remainder"))?,
+                BitAnd => a & b,
+                BitOr => a | b,
+                BitXor => a ^ b,
+                Shl | Shr => {
+                    let n = u32::try_from(b).ok().filter(|&n| n < 64);
+                    let n = n.ok_or_else(|| {
+                        overflow(if op == Shl {
+                            "shift left"
+                        } else {
+                            "shift right"
+                        })
+                    })?;
+                    if op == Shl {
+                        a << n
+                    } else {
+                        a >> n
+                    }
+                }
+                Eq => return Ok(Value::Bool(a == b)),
+                Ne => return Ok(Value::Bool(a != b)),
+                Lt => return Ok(Value::Bool(a < b)),
+                Le => return Ok(Value::Bool(a <= b)),
+                Gt => return Ok(Value::Bool(a > b)),
+                Ge => return Ok(Value::Bool(a >= b)),
+                And | Or => unreachable!(),
+            };
+            Ok(Value::Int(v))
+        }
+        (Value::Bool(a), Value::Bool(b)) => {
+            let v = match op {
+                BitAnd => a & b,
+                BitOr => a | b,
+                BitXor => a ^ b,
+                Eq => a == b,
+                Ne => a != b,
+                // false < true
+                Lt => !a & b,
+                Le => a <= b,
+                Gt => a & !b,
+                Ge => a >= b,
+                _ => return Err(format!("cannot apply `{:?}` to type `bool`", op)),
+            };
+            Ok(Value::Bool(v))
+        }
+        (Value::Unit, Value::Unit) if op == Eq || op == Ne => Ok(Value::Bool(op == Eq)),
+        _ => Err(format!(
+            "cannot apply `{:?}` to `{}` and `{}`",
+            op,
+            l.type_name(),
+            r.type_name()
+        )),
+    }
+}
+
+/// 解析并执行一段程序, 返回最终的值和求值过程
+pub fn run(src: &str) -> Result<Run, Error> {
+    let toks = lex(src)?;
+    let program = Parser { toks, pos: 0 }.program()?;
+    let mut interp = Interp {
+        src,
+        scopes: Vec::new(),
+        trace: Vec::new(),
+        depth: 0,
+        steps: 0,
+    };
+    match interp.eval(&program) {
+        Ok(value) => Ok(Run {
+            value,
+            trace: interp.trace,
+        }),
+        Err(Flow::Error(e)) => Err(e),
+        Err(Flow::Break { pos, .. }) | Err(Flow::Continue { pos, .. }) => Err(Error::Eval {
+            pos,
+            msg: "`break`/`continue` outside of a loop or with an unknown label".to_string(),
+        }),
+    }
+}
+
+/// 只关心结果时使用
+pub fn eval(src: &str) -> Result<Value, Error> {
+    run(src).map(|r| r.value)
+}
+
+pub fn learn_interpreter() {
+    // 短路与不短路: 对照 first::learn_bool_op
+    let r = run("f2() & f1()").unwrap();
+    println!("{}\n", r);
+    assert_eq!(r.output(), vec!["Call f2", "Call f1"]);
+    let r = run("f2() && f1()").unwrap();
+    println!("{}\n", r);
+    assert_eq!(r.output(), vec!["Call f2"]);
+    assert_eq!(r.value, Value::Bool(false));
+
+    // 赋值表达式的值是 ()
+    let r = run("let mut x = 1; let y = x = 3; y").unwrap();
+    println!("{}\n", r);
+    assert_eq!(r.value, Value::Unit);
+
+    // 语句块是表达式, 最后一个不带分号的表达式就是它的值
+    assert_eq!(
+        eval("let y = { let a = 2; a * 3 }; y + 1"),
+        Ok(Value::Int(7))
+    );
+    assert_eq!(eval("{ 5; }"), Ok(Value::Unit));
+
+    // 组合赋值
+    assert_eq!(
+        eval("let x = 2; let mut y = 4; y += x; y *= x; y <<= 1; y"),
+        Ok(Value::Int(24))
+    );
+
+    // if-else 是表达式
+    assert_eq!(
+        eval("let n = -3; if n < 0 { -1 } else if n > 0 { 1 } else { 0 }"),
+        Ok(Value::Int(-1))
+    );
+
+    // loop 可以通过 break 带出一个值, 带标签的 break 可以跳出外层循环
+    let src = "
+        let mut count = 0;
+        let v = 'outer: loop {
+            loop {
+                count += 1;
+                if count % 2 == 0 { continue; }
+                if count > 5 { break 'outer count * 10; }
+                break;
+            }
+        };
+        v";
+    let r = run(src).unwrap();
+    assert_eq!(r.value, Value::Int(70));
+    println!("{} 个表达式被求值\n", r.trace.len());
+
+    // while 和 for 的值永远是 ()
+    let r = run("let mut sum = 0; for i in 0..5 { sum += i; } while sum > 7 { sum -= 1 } sum");
+    assert_eq!(r.map(|r| r.value), Ok(Value::Int(7)));
+    assert_eq!(
+        eval("let mut i = 0; while i < 3 { i += 1 }"),
+        Ok(Value::Unit)
+    );
+
+    // 这些错误在 Rust 里都是编译错误或 panic, 这里表现为运行时错误
+    for src in [
+        "let x = 1; x = 2; x",
+        "while true { break 1; }",
+        "if true { 1 }",
+        "1 + true",
+        "1 < 2 < 3",
+        "let x = 9223372036854775807; x + 1",
+        "break",
+    ]
+    .iter()
+    {
+        let e = eval(src).unwrap_err();
+        let (line, col) = e.locate(src);
+        println!("{:<40} {}:{} {}", src, line, col, e);
+    }
+}
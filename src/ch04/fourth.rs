@@ -0,0 +1,325 @@
+#![allow(dead_code)]
+
+// 用 const fn 在编译期生成查找表
+// first::learn_const_fn 只用 cube(2) 算了一个数组长度。
+// 实际上 stable 的 const fn 已经可以写 while 循环、if/match、局部可变变量、
+// 读写数组元素、调用其他 const fn, 还可以带 const 泛型参数,
+// 足以在编译期生成真正有用的表格。下面几个表都是 const, 运行时没有任何初始化开销:
+// 1. CRC32_TABLE: CRC-32 (IEEE 802.3) 的查表法用的 256 项表
+// 2. IS_PRIME / PRIMES: 埃拉托斯特尼筛法
+// 3. FACTORIALS_U64 / FACTORIALS_U128: 阶乘表, 长度恰好是不溢出的最大长度
+// 4. SIN_TABLE / COS_TABLE: 一个圆周分成 256 份的正弦/余弦定点数表
+// 目前 const fn 中还不能使用 for 循环(它依赖 Iterator trait), 只能用 while 代替;
+// 也不能调用 f64::sin 这样的非 const 函数, 所以正弦表用整数实现的泰勒级数计算。
+// 编译期计算中发生整数溢出或者数组越界, 会直接变成编译错误。
+
+// ---------- CRC-32 ----------
+
+/// CRC-32 使用的多项式(反转后的 0x04C11DB7)
+pub const CRC32_POLY: u32 = 0xEDB8_8320;
+
+/// 生成 CRC-32 查找表: 第 i 项是单个字节 i 的 CRC 余数
+pub const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+pub const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// 查表法计算 CRC-32, 本身也是 const fn, 所以可以对常量数据在编译期求值
+pub const fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    let mut i = 0;
+    while i < data.len() {
+        crc = CRC32_TABLE[((crc ^ data[i] as u32) & 0xff) as usize] ^ (crc >> 8);
+        i += 1;
+    }
+    !crc
+}
+
+/// 不查表, 逐位更新 CRC 寄存器, 用来验证查找表
+/// 表中的第 i 项就是寄存器初始值为 0 时处理一个字节 i 的结果
+pub fn crc32_update_bitwise(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (CRC32_POLY & mask);
+        }
+    }
+    crc
+}
+
+/// 逐位计算的 CRC-32, 初始值和结果都要取反
+pub fn crc32_bitwise(data: &[u8]) -> u32 {
+    !crc32_update_bitwise(!0, data)
+}
+
+// 编译期断言, 表生成得不对时连编译都通不过
+const _: () = assert!(CRC32_TABLE[1] == 0x7707_3096);
+const _: () = assert!(crc32(b"123456789") == 0xCBF4_3926);
+
+// ---------- 素数筛 ----------
+
+/// 埃拉托斯特尼筛法, 返回的数组中 sieve[n] 表示 n 是否是素数
+pub const fn sieve<const N: usize>() -> [bool; N] {
+    let mut is_prime = [true; N];
+    if N > 0 {
+        is_prime[0] = false;
+    }
+    if N > 1 {
+        is_prime[1] = false;
+    }
+    let mut i = 2;
+    while i * i < N {
+        if is_prime[i] {
+            let mut j = i * i;
+            while j < N {
+                is_prime[j] = false;
+                j += i;
+            }
+        }
+        i += 1;
+    }
+    is_prime
+}
+
+/// 统计筛中素数的个数, 用来确定 PRIMES 数组的长度
+pub const fn count_primes<const N: usize>(sieve: &[bool; N]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < N {
+        if sieve[i] {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// 把筛中的素数依次取出来, C 必须等于素数的个数
+pub const fn collect_primes<const N: usize, const C: usize>(sieve: &[bool; N]) -> [u32; C] {
+    let mut primes = [0u32; C];
+    let mut count = 0;
+    let mut i = 0;
+    while i < N {
+        if sieve[i] {
+            primes[count] = i as u32;
+            count += 1;
+        }
+        i += 1;
+    }
+    assert!(count == C, "C must equal the number of primes");
+    primes
+}
+
+pub const PRIME_LIMIT: usize = 1000;
+pub const IS_PRIME: [bool; PRIME_LIMIT] = sieve::<PRIME_LIMIT>();
+pub const PRIME_COUNT: usize = count_primes(&IS_PRIME);
+/// 小于 PRIME_LIMIT 的所有素数
+pub const PRIMES: [u32; PRIME_COUNT] = collect_primes(&IS_PRIME);
+
+/// 试除法判断素数, 用来验证筛法
+pub fn is_prime_trial(n: u32) -> bool {
+    n >= 2
+        && (2..)
+            .take_while(|d| d * d <= n)
+            .all(|d| !n.is_multiple_of(d))
+}
+
+// ---------- 阶乘 ----------
+
+// const fn 不能对返回值类型做泛型运算(乘法 trait 不是 const 的), 所以用宏生成两个版本
+macro_rules! factorial_table {
+    ($name:ident, $t:ty) => {
+        /// 生成 0! .. (N-1)! 的阶乘表, N 太大时编译期就会报溢出错误
+        pub const fn $name<const N: usize>() -> [$t; N] {
+            let mut table = [1 as $t; N];
+            let mut i = 1;
+            while i < N {
+                table[i] = table[i - 1] * i as $t;
+                i += 1;
+            }
+            table
+        }
+    };
+}
+
+factorial_table!(factorials_u64, u64);
+factorial_table!(factorials_u128, u128);
+
+/// 0! ..= 20!, 21! 超出 u64
+pub const FACTORIALS_U64: [u64; 21] = factorials_u64::<21>();
+/// 0! ..= 34!, 35! 超出 u128
+pub const FACTORIALS_U128: [u128; 35] = factorials_u128::<35>();
+// 把长度改成 22 会得到编译错误:
+// error[E0080]: evaluation of constant value failed
+// attempt to multiply with overflow
+
+// ---------- 正弦/余弦表 ----------
+
+/// 一个圆周分成的份数
+pub const ANGLE_STEPS: usize = 256;
+/// 定点数的小数位数, 表中的值是 sin(x) * 2^16
+pub const FIXED_SHIFT: u32 = 16;
+pub const FIXED_ONE: i32 = 1 << FIXED_SHIFT;
+
+// 中间计算使用 62 位小数的定点数, 存放在 i128 中
+const Q: u32 = 62;
+// π * 2^62, 取整
+const PI_Q62: i128 = 14_488_038_916_154_245_684;
+
+// 0 <= x <= π/2 时 sin(x) 的泰勒级数: x - x^3/3! + x^5/5! - ...
+// x 和结果都是 Q62 定点数; x 不超过 2^63, 两个数相乘不会超出 i128
+const fn sin_q62(x: i128) -> i128 {
+    let mut sum = x;
+    let mut term = x;
+    let mut n = 1;
+    while term != 0 {
+        term = -((((term * x) >> Q) * x) >> Q) / ((2 * n) * (2 * n + 1));
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+/// 生成正弦表: table[k] = sin(2πk / ANGLE_STEPS) * 2^16, 四舍五入
+/// 只计算第一象限, 其余三个象限由对称性得到
+pub const fn sin_table() -> [i32; ANGLE_STEPS] {
+    const QUARTER: usize = ANGLE_STEPS / 4;
+    let mut quarter = [0i32; QUARTER + 1];
+    let mut k = 0;
+    while k <= QUARTER {
+        let x = PI_Q62 * k as i128 / (ANGLE_STEPS as i128 / 2);
+        let s = sin_q62(x);
+        // 从 Q62 舍入到 Q16
+        let shift = Q - FIXED_SHIFT;
+        quarter[k] = ((s + (1 << (shift - 1))) >> shift) as i32;
+        k += 1;
+    }
+    let mut table = [0i32; ANGLE_STEPS];
+    let mut k = 0;
+    while k < ANGLE_STEPS {
+        let r = k % QUARTER;
+        table[k] = match k / QUARTER {
+            0 => quarter[r],
+            1 => quarter[QUARTER - r],
+            2 => -quarter[r],
+            _ => -quarter[QUARTER - r],
+        };
+        k += 1;
+    }
+    table
+}
+
+/// cos(x) = sin(x + π/2)
+pub const fn cos_table() -> [i32; ANGLE_STEPS] {
+    let sin = sin_table();
+    let mut table = [0i32; ANGLE_STEPS];
+    let mut k = 0;
+    while k < ANGLE_STEPS {
+        table[k] = sin[(k + ANGLE_STEPS / 4) % ANGLE_STEPS];
+        k += 1;
+    }
+    table
+}
+
+pub const SIN_TABLE: [i32; ANGLE_STEPS] = sin_table();
+pub const COS_TABLE: [i32; ANGLE_STEPS] = cos_table();
+
+const _: () = assert!(SIN_TABLE[ANGLE_STEPS / 4] == FIXED_ONE);
+const _: () = assert!(COS_TABLE[ANGLE_STEPS / 2] == -FIXED_ONE);
+
+/// 把定点数转回浮点数
+pub fn fixed_to_f64(v: i32) -> f64 {
+    v as f64 / FIXED_ONE as f64
+}
+
+/// 用查找表旋转一个整数点, 整个计算只有整数运算
+pub const fn rotate(x: i32, y: i32, step: usize) -> (i32, i32) {
+    let (s, c) = (
+        SIN_TABLE[step % ANGLE_STEPS] as i64,
+        COS_TABLE[step % ANGLE_STEPS] as i64,
+    );
+    let (x, y) = (x as i64, y as i64);
+    (
+        ((x * c - y * s) >> FIXED_SHIFT) as i32,
+        ((x * s + y * c) >> FIXED_SHIFT) as i32,
+    )
+}
+
+pub fn learn_const_tables() {
+    // 每个表都与运行时的计算结果对比
+    for i in 0..=255u8 {
+        assert_eq!(CRC32_TABLE[i as usize], crc32_update_bitwise(0, &[i]));
+    }
+    let text = "深入浅出 Rust".as_bytes();
+    assert_eq!(crc32(text), crc32_bitwise(text));
+    const CHECK: u32 = crc32(b"The quick brown fox jumps over the lazy dog");
+    println!("crc32 = {:#010x}", CHECK);
+    assert_eq!(CHECK, 0x414F_A339);
+
+    assert_eq!(PRIME_COUNT, 168);
+    for n in 0..PRIME_LIMIT as u32 {
+        assert_eq!(IS_PRIME[n as usize], is_prime_trial(n), "{}", n);
+    }
+    let runtime: Vec<u32> = (0..PRIME_LIMIT as u32)
+        .filter(|&n| is_prime_trial(n))
+        .collect();
+    assert_eq!(&PRIMES[..], &runtime[..]);
+    println!(
+        "{} primes below {}, the last is {}",
+        PRIME_COUNT,
+        PRIME_LIMIT,
+        PRIMES[PRIME_COUNT - 1]
+    );
+
+    let mut f = 1u128;
+    for (n, &v) in FACTORIALS_U128.iter().enumerate() {
+        if n > 0 {
+            f *= n as u128;
+        }
+        assert_eq!(v, f);
+        if n < FACTORIALS_U64.len() {
+            assert_eq!(FACTORIALS_U64[n] as u128, f);
+        }
+    }
+    // 两个表的长度都恰好是不溢出的最大长度
+    assert_eq!(FACTORIALS_U64[20].checked_mul(21), None);
+    assert_eq!(FACTORIALS_U128[34].checked_mul(35), None);
+    println!(
+        "20! = {}, 34! = {}",
+        FACTORIALS_U64[20], FACTORIALS_U128[34]
+    );
+
+    for k in 0..ANGLE_STEPS {
+        let x = 2.0 * std::f64::consts::PI * k as f64 / ANGLE_STEPS as f64;
+        let expect_sin = (x.sin() * FIXED_ONE as f64).round() as i32;
+        let expect_cos = (x.cos() * FIXED_ONE as f64).round() as i32;
+        assert_eq!(SIN_TABLE[k], expect_sin, "sin at step {}", k);
+        assert_eq!(COS_TABLE[k], expect_cos, "cos at step {}", k);
+    }
+    println!(
+        "sin(π/4) ≈ {} ({})",
+        fixed_to_f64(SIN_TABLE[ANGLE_STEPS / 8]),
+        SIN_TABLE[ANGLE_STEPS / 8]
+    );
+    // 编译期旋转: (1000, 0) 转 90 度
+    const ROTATED: (i32, i32) = rotate(1000, 0, ANGLE_STEPS / 4);
+    assert_eq!(ROTATED, (0, 1000));
+}
@@ -0,0 +1,340 @@
+#![allow(dead_code)]
+
+// 斐波那契数列的几种算法
+// first::learn_recursion 中的递归版本 fib 有两个问题:
+// 1. 时间复杂度是指数级的, fib(50) 就要算很久
+// 2. 返回值是 u64, 下标超过 93 就溢出了
+// 这里实现了五种算法, 它们对数值类型是泛型的, 所有运算都检查溢出,
+// 溢出时返回 None 而不是 panic 或者得到一个错误的结果:
+// 1. naive: 直接递归, O(φ^n)
+// 2. memoized: 缓存表, 自底向上填, O(n)
+// 3. iterative: 循环, O(n)
+// 4. matrix: 矩阵快速幂, O(log n) 次乘法
+// 5. fast_doubling: 快速倍增, O(log n) 次乘法, 常数比矩阵小
+// 数值类型可以是 u64、u128 或者任意精度的 BigUint。
+// 下标从 0 开始: fib(0) = 0, fib(1) = 1, fib(2) = 1, 与 learn_recursion 一致。
+use super::third::BigUint;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// 斐波那契算法需要的数值运算
+pub trait FibNum: Clone + PartialEq + fmt::Debug {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_fib_num {
+    ($($t:ty)*) => {$(
+        impl FibNum for $t {
+            fn zero() -> Self {
+                0
+            }
+            fn one() -> Self {
+                1
+            }
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+            fn checked_sub(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *other)
+            }
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+        }
+    )*};
+}
+
+impl_fib_num!(u32 u64 u128);
+
+/// u64 能表示的最大下标
+pub const MAX_U64_INDEX: u32 = 93;
+/// u128 能表示的最大下标
+pub const MAX_U128_INDEX: u32 = 186;
+
+/// 直接按定义递归, 与 learn_recursion 中的 fib 相同
+pub fn naive<T: FibNum>(n: u32) -> Option<T> {
+    match n {
+        0 => Some(T::zero()),
+        1 => Some(T::one()),
+        _ => naive::<T>(n - 1)?.checked_add(&naive::<T>(n - 2)?),
+    }
+}
+
+/// 缓存表, 每个下标只计算一次
+/// 自底向上填表: 递归版本要先递归 n 层才会检查溢出, n 大一点就栈溢出;
+/// 表也不按 n 预先分配, 遇到第一次溢出就停下, 所以 n 取 u32::MAX 也只会填 94 项(u64)
+pub fn memoized<T: FibNum>(n: u32) -> Option<T> {
+    let n = n as usize;
+    let mut memo = vec![T::zero(), T::one()];
+    while memo.len() <= n {
+        let len = memo.len();
+        let v = memo[len - 1].checked_add(&memo[len - 2])?;
+        memo.push(v);
+    }
+    memo.into_iter().nth(n)
+}
+
+/// 循环, 只保存最近的两个值
+pub fn iterative<T: FibNum>(n: u32) -> Option<T> {
+    if n == 0 {
+        return Some(T::zero());
+    }
+    // 不能写成循环 n 次再返回 a, 那样会多算一个 fib(n + 1), 导致下标 93 也报告溢出
+    let (mut a, mut b) = (T::zero(), T::one());
+    for _ in 1..n {
+        let c = a.checked_add(&b)?;
+        a = b;
+        b = c;
+    }
+    Some(b)
+}
+
+// 2x2 矩阵 [[a, b], [c, d]]
+type Matrix<T> = [T; 4];
+
+fn mat_mul<T: FibNum>(x: &Matrix<T>, y: &Matrix<T>) -> Option<Matrix<T>> {
+    let dot = |a: &T, b: &T, c: &T, d: &T| a.checked_mul(b)?.checked_add(&c.checked_mul(d)?);
+    Some([
+        dot(&x[0], &y[0], &x[1], &y[2])?,
+        dot(&x[0], &y[1], &x[1], &y[3])?,
+        dot(&x[2], &y[0], &x[3], &y[2])?,
+        dot(&x[2], &y[1], &x[3], &y[3])?,
+    ])
+}
+
+/// 矩阵快速幂
+/// [[1, 1], [1, 0]]^k = [[fib(k + 1), fib(k)], [fib(k), fib(k - 1)]]
+/// 取 k = n - 1, 左上角就是 fib(n), 这样矩阵里不会出现比 fib(n) 更大的数
+pub fn matrix<T: FibNum>(n: u32) -> Option<T> {
+    if n == 0 {
+        return Some(T::zero());
+    }
+    let mut k = n - 1;
+    let mut result = [T::one(), T::zero(), T::zero(), T::one()];
+    let mut base = [T::one(), T::one(), T::one(), T::zero()];
+    while k > 0 {
+        if k & 1 == 1 {
+            result = mat_mul(&result, &base)?;
+        }
+        k >>= 1;
+        // 最后一次平方用不到, 而且可能溢出
+        if k > 0 {
+            base = mat_mul(&base, &base)?;
+        }
+    }
+    let [f, ..] = result;
+    Some(f)
+}
+
+/// 快速倍增
+/// 已知 a = fib(k), b = fib(k + 1), 则
+/// fib(2k) = a * (2b - a)
+/// fib(2k + 1) = a^2 + b^2
+/// 从 n 的最高位开始, 每一位把 k 变成 2k 或者 2k + 1
+pub fn fast_doubling<T: FibNum>(n: u32) -> Option<T> {
+    let even = |a: &T, b: &T| a.checked_mul(&b.checked_add(b)?.checked_sub(a)?);
+    let odd = |a: &T, b: &T| a.checked_mul(a)?.checked_add(&b.checked_mul(b)?);
+    let (mut a, mut b) = (T::zero(), T::one());
+    for i in (0..32 - n.leading_zeros()).rev() {
+        let last = i == 0;
+        // 最后一步只计算需要的那一个, 否则 fib(n + 1) 可能溢出
+        if (n >> i) & 1 == 0 {
+            let c = even(&a, &b)?;
+            if last {
+                return Some(c);
+            }
+            b = odd(&a, &b)?;
+            a = c;
+        } else {
+            let d = odd(&a, &b)?;
+            if last {
+                return Some(d);
+            }
+            b = even(&a, &b)?.checked_add(&d)?;
+            a = d;
+        }
+    }
+    Some(a)
+}
+
+pub fn fib_u64(n: u32) -> Option<u64> {
+    fast_doubling(n)
+}
+
+pub fn fib_u128(n: u32) -> Option<u128> {
+    fast_doubling(n)
+}
+
+pub fn fib_big(n: u32) -> BigUint {
+    fast_doubling(n).unwrap()
+}
+
+// 任意精度的版本使用 third::BigUint, 它永远不会溢出
+impl FibNum for BigUint {
+    fn zero() -> Self {
+        BigUint::zero()
+    }
+    fn one() -> Self {
+        BigUint::one()
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        BigUint::checked_sub(self, other)
+    }
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+// ---------- 性能比较 ----------
+
+/// 一种算法
+pub struct Algorithm<T> {
+    pub name: &'static str,
+    pub run: fn(u32) -> Option<T>,
+    /// 超过这个下标就不参加比较, 指数级的算法只能算很小的下标
+    pub max_index: u32,
+}
+
+pub fn algorithms<T: FibNum>() -> Vec<Algorithm<T>> {
+    vec![
+        Algorithm {
+            name: "naive",
+            run: naive::<T>,
+            max_index: 30,
+        },
+        Algorithm {
+            name: "memoized",
+            run: memoized::<T>,
+            max_index: 10_000,
+        },
+        Algorithm {
+            name: "iterative",
+            run: iterative::<T>,
+            max_index: u32::MAX,
+        },
+        Algorithm {
+            name: "matrix",
+            run: matrix::<T>,
+            max_index: u32::MAX,
+        },
+        Algorithm {
+            name: "fast_doubling",
+            run: fast_doubling::<T>,
+            max_index: u32::MAX,
+        },
+    ]
+}
+
+/// 一次计时的结果: 把同一个操作重复 calls 次, 一共用了 total
+/// ch05::fourteenth 比较几种调用方式的开销时也用它
+pub struct Timing {
+    pub name: &'static str,
+    pub calls: u32,
+    pub total: Duration,
+}
+
+impl Timing {
+    /// 一次调用也没有时是 0
+    pub fn per_call(&self) -> Duration {
+        self.total.checked_div(self.calls).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<14} {:>8} calls {:>12?} per call",
+            self.name,
+            self.calls,
+            self.per_call()
+        )
+    }
+}
+
+/// 用所有适用的算法计算 fib(n), 各重复 rounds 次, 同时检查它们的结果一致
+pub fn benchmark<T: FibNum>(n: u32, rounds: u32) -> Vec<Timing> {
+    let mut expected: Option<Option<T>> = None;
+    let mut timings = Vec::new();
+    for alg in algorithms::<T>().into_iter().filter(|a| n <= a.max_index) {
+        let start = Instant::now();
+        let mut result = None;
+        for _ in 0..rounds {
+            result = (alg.run)(std::hint::black_box(n));
+        }
+        let total = start.elapsed();
+        match &expected {
+            None => expected = Some(result),
+            Some(e) => assert_eq!(*e, result, "{} disagrees at n = {}", alg.name, n),
+        }
+        timings.push(Timing {
+            name: alg.name,
+            calls: rounds,
+            total,
+        });
+    }
+    timings
+}
+
+pub fn learn_fibonacci() {
+    // 所有算法在所有类型上的结果都一致
+    for n in 0..=30 {
+        let expected = naive::<u64>(n);
+        for alg in algorithms::<u64>() {
+            assert_eq!((alg.run)(n), expected, "{} at {}", alg.name, n);
+        }
+        assert_eq!(fib_big(n).to_string(), expected.unwrap().to_string());
+    }
+    assert_eq!(fib_u64(8), Some(21));
+    assert_eq!(fib_u64(90), Some(2_880_067_194_370_816_120));
+
+    // 溢出边界: 每个算法都能算到最大下标, 再多一个就返回 None
+    for alg in algorithms::<u64>().into_iter().skip(1) {
+        assert!((alg.run)(MAX_U64_INDEX).is_some(), "{}", alg.name);
+        assert_eq!((alg.run)(MAX_U64_INDEX + 1), None, "{}", alg.name);
+        // 下标再大也只是返回 None, 不会栈溢出或者一次分配巨大的缓存
+        assert_eq!((alg.run)(300_000), None, "{}", alg.name);
+        assert_eq!((alg.run)(u32::MAX), None, "{}", alg.name);
+    }
+    for alg in algorithms::<u128>().into_iter().skip(1) {
+        assert!((alg.run)(MAX_U128_INDEX).is_some(), "{}", alg.name);
+        assert_eq!((alg.run)(MAX_U128_INDEX + 1), None, "{}", alg.name);
+    }
+    println!("fib({}) = {:?}", MAX_U64_INDEX, fib_u64(MAX_U64_INDEX));
+    println!("fib({}) = {:?}", MAX_U128_INDEX, fib_u128(MAX_U128_INDEX));
+    // 在最大下标上, u128 与任意精度的结果相同
+    assert_eq!(
+        fib_big(MAX_U128_INDEX),
+        BigUint::from(fib_u128(MAX_U128_INDEX).unwrap())
+    );
+
+    // 任意精度版本没有上限
+    let big = fib_big(1000);
+    println!("fib(1000) = {} ({} bits)", big, big.bits());
+    assert_eq!(big.to_string().len(), 209);
+    assert!(big.to_string().starts_with("43466557686937456435"));
+    assert_eq!(iterative::<BigUint>(1000), Some(big));
+
+    // 性能比较
+    let runs = [
+        (30, benchmark::<u64>(30, 3)),
+        (MAX_U128_INDEX, benchmark::<u128>(MAX_U128_INDEX, 1000)),
+        (5000, benchmark::<BigUint>(5000, 5)),
+    ];
+    for (n, timings) in runs.iter() {
+        for t in timings {
+            println!("n = {:<6} {}", n, t);
+        }
+    }
+    // 重复 0 次也是合法的参数
+    for t in benchmark::<u64>(30, 0) {
+        assert_eq!(t.per_call(), Duration::ZERO);
+    }
+}
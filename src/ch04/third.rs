@@ -0,0 +1,1031 @@
+#![allow(dead_code)]
+
+// 任意精度整数
+// 前面的几个例子都碰到了整数的上限:
+// 1. first::learn_recursion 中的 fib 在下标 93 之后溢出 u64
+// 2. ch02::second::integer_overflow 中 123_i8 + 113_i8 溢出
+// 3. Wrapping(u32::MAX) + Wrapping(1) 得到 0
+// BigUint 和 BigInt 没有上限, 用来展示超出 u128 的结果。
+// 不依赖任何第三方库, 实现了:
+// 加减乘除、取余、乘方、比较、与基本整数类型互相转换、按 2/8/10/16 等进制解析和输出。
+// 乘法在两个数都比较大时使用 Karatsuba 算法, 除法使用 Knuth 的 Algorithm D。
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, Shr};
+use std::ops::{Sub, SubAssign};
+use std::str::FromStr;
+
+// 两个数的长度(以 u32 计)都不小于这个值时才使用 Karatsuba 乘法,
+// 太小的数递归和分配内存的开销比省下的乘法还多
+pub const KARATSUBA_THRESHOLD: usize = 32;
+
+/// 任意精度的无符号整数
+/// 以 2^32 为基数, 低位在前; 最高位不为 0, 所以 0 就是一个空的 Vec
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+/// 任意精度的有符号整数, 符号 + 绝对值
+/// 0 的 negative 总是 false, 这样派生的 PartialEq 才是正确的
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: BigUint,
+}
+
+/// 解析字符串失败
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseBigIntError {
+    Empty,
+    InvalidDigit(char),
+}
+
+impl fmt::Display for ParseBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseBigIntError::Empty => write!(f, "cannot parse integer from empty string"),
+            ParseBigIntError::InvalidDigit(c) => write!(f, "invalid digit {:?} found in string", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+/// 转换成基本整数类型时超出了范围, 与 std::num::TryFromIntError 对应
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TryFromBigIntError(());
+
+impl fmt::Display for TryFromBigIntError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "out of range integral type conversion attempted")
+    }
+}
+
+impl std::error::Error for TryFromBigIntError {}
+
+// ---------- 以 &[u32] 表示的数上的基本运算 ----------
+
+fn trim(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+fn cmp_limbs(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = a.to_vec();
+    add_into(&mut out, b, 0);
+    out
+}
+
+// acc += x << (32 * offset), acc 的长度不够时自动扩展
+fn add_into(acc: &mut Vec<u32>, x: &[u32], offset: usize) {
+    if acc.len() < offset + x.len() {
+        acc.resize(offset + x.len(), 0);
+    }
+    let mut carry = 0u64;
+    let mut i = offset;
+    for &d in x {
+        let s = acc[i] as u64 + d as u64 + carry;
+        acc[i] = s as u32;
+        carry = s >> 32;
+        i += 1;
+    }
+    while carry > 0 {
+        if i == acc.len() {
+            acc.push(0);
+        }
+        let s = acc[i] as u64 + carry;
+        acc[i] = s as u32;
+        carry = s >> 32;
+        i += 1;
+    }
+}
+
+// a - b, 要求 a >= b
+fn sub_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = a.to_vec();
+    sub_into(&mut out, b);
+    trim(out)
+}
+
+// acc -= x, 要求 acc >= x
+fn sub_into(acc: &mut [u32], x: &[u32]) {
+    let mut borrow = 0i64;
+    for (i, a) in acc.iter_mut().enumerate() {
+        let d = *a as i64 - *x.get(i).unwrap_or(&0) as i64 - borrow;
+        *a = d as u32;
+        borrow = (d < 0) as i64;
+        if i >= x.len() && borrow == 0 {
+            break;
+        }
+    }
+    debug_assert_eq!(borrow, 0, "subtraction underflow");
+}
+
+// 小学竖式乘法, O(n * m)
+fn schoolbook(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let t = out[i + j] as u64 + x as u64 * y as u64 + carry;
+            out[i + j] = t as u32;
+            carry = t >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+    trim(out)
+}
+
+// Karatsuba 乘法, O(n^1.585)
+// 把两个数各拆成高低两半: a = a1 * B^m + a0, b = b1 * B^m + b0, 则
+// a * b = z2 * B^2m + z1 * B^m + z0
+// 其中 z0 = a0 * b0, z2 = a1 * b1, z1 = (a0 + a1) * (b0 + b1) - z0 - z2
+// 只需要三次乘法, 而不是四次
+fn mul_limbs(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if b.len() < KARATSUBA_THRESHOLD {
+        return schoolbook(a, b);
+    }
+    let m = a.len() / 2;
+    if b.len() <= m {
+        // 长度相差太大, 只拆开长的那个: a * b = a1 * b * B^m + a0 * b
+        let mut out = mul_limbs(&a[..m], b);
+        add_into(&mut out, &mul_limbs(&a[m..], b), m);
+        return trim(out);
+    }
+    let (a0, a1) = (trim(a[..m].to_vec()), &a[m..]);
+    let (b0, b1) = (trim(b[..m].to_vec()), &b[m..]);
+    let z0 = mul_limbs(&a0, &b0);
+    let z2 = mul_limbs(a1, b1);
+    let mut z1 = mul_limbs(&add_limbs(&a0, a1), &add_limbs(&b0, b1));
+    sub_into(&mut z1, &z0);
+    sub_into(&mut z1, &z2);
+    let mut out = z0;
+    add_into(&mut out, &trim(z1), m);
+    add_into(&mut out, &z2, 2 * m);
+    trim(out)
+}
+
+// 除数只有一个 u32 时的除法
+fn div_rem_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+    let mut q = vec![0u32; a.len()];
+    let mut r = 0u64;
+    for i in (0..a.len()).rev() {
+        let cur = (r << 32) | a[i] as u64;
+        q[i] = (cur / d as u64) as u32;
+        r = cur % d as u64;
+    }
+    (trim(q), r as u32)
+}
+
+// Knuth, The Art of Computer Programming, 4.3.1, Algorithm D
+// 要求 v 至少有两个 u32, 且 u >= v
+fn div_rem_knuth(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let n = v.len();
+    let m = u.len() - n;
+    // D1. 规格化: 左移使除数的最高位为 1, 这样试商最多只会大 2
+    let s = v[n - 1].leading_zeros();
+    let vn = shl_limbs(v, s);
+    let mut un = shl_limbs(u, s);
+    un.resize(u.len() + 1, 0);
+    let mut q = vec![0u32; m + 1];
+    let b = 1u64 << 32;
+    for j in (0..=m).rev() {
+        // D3. 用被除数的最高两位除以除数的最高一位, 得到试商 qhat
+        let num = ((un[j + n] as u64) << 32) | un[j + n - 1] as u64;
+        let mut qhat = num / vn[n - 1] as u64;
+        let mut rhat = num % vn[n - 1] as u64;
+        while qhat >= b || qhat * vn[n - 2] as u64 > ((rhat << 32) | un[j + n - 2] as u64) {
+            qhat -= 1;
+            rhat += vn[n - 1] as u64;
+            if rhat >= b {
+                break;
+            }
+        }
+        // D4. un[j..=j + n] -= qhat * vn
+        let mut k = 0i64;
+        for i in 0..n {
+            let p = qhat * vn[i] as u64;
+            let t = un[i + j] as i64 - k - (p & 0xffff_ffff) as i64;
+            un[i + j] = t as u32;
+            k = (p >> 32) as i64 - (t >> 32);
+        }
+        let t = un[j + n] as i64 - k;
+        un[j + n] = t as u32;
+        // D6. 试商大了 1, 加回去
+        if t < 0 {
+            qhat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let t = un[i + j] as u64 + vn[i] as u64 + carry;
+                un[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u32);
+        }
+        q[j] = qhat as u32;
+    }
+    // D8. 余数要右移回去
+    un.truncate(n);
+    (trim(q), trim(shr_limbs(&un, s)))
+}
+
+// 左移不超过 31 位
+fn shl_limbs(a: &[u32], s: u32) -> Vec<u32> {
+    if s == 0 {
+        return a.to_vec();
+    }
+    let mut out = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0;
+    for &d in a {
+        out.push((d << s) | carry);
+        carry = d >> (32 - s);
+    }
+    if carry > 0 {
+        out.push(carry);
+    }
+    out
+}
+
+// 右移不超过 31 位
+fn shr_limbs(a: &[u32], s: u32) -> Vec<u32> {
+    if s == 0 {
+        return a.to_vec();
+    }
+    let mut out = vec![0u32; a.len()];
+    for i in 0..a.len() {
+        let hi = a.get(i + 1).map_or(0, |&h| h << (32 - s));
+        out[i] = (a[i] >> s) | hi;
+    }
+    trim(out)
+}
+
+// ---------- BigUint ----------
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint { limbs: vec![1] }
+    }
+
+    fn from_limbs(limbs: Vec<u32>) -> BigUint {
+        BigUint { limbs: trim(limbs) }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// 二进制表示需要的位数, 0 需要 0 位
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            None => 0,
+            Some(top) => self.limbs.len() as u64 * 32 - top.leading_zeros() as u64,
+        }
+    }
+
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        match cmp_limbs(&self.limbs, &other.limbs) {
+            Ordering::Less => None,
+            _ => Some(BigUint::from_limbs(sub_limbs(&self.limbs, &other.limbs))),
+        }
+    }
+
+    /// 带余数的除法, 除数为 0 时 panic
+    pub fn div_rem(&self, other: &BigUint) -> (BigUint, BigUint) {
+        assert!(!other.is_zero(), "attempt to divide by zero");
+        if cmp_limbs(&self.limbs, &other.limbs) == Ordering::Less {
+            return (BigUint::zero(), self.clone());
+        }
+        if other.limbs.len() == 1 {
+            let (q, r) = div_rem_small(&self.limbs, other.limbs[0]);
+            return (BigUint::from_limbs(q), BigUint::from(r));
+        }
+        let (q, r) = div_rem_knuth(&self.limbs, &other.limbs);
+        (BigUint::from_limbs(q), BigUint::from_limbs(r))
+    }
+
+    /// 乘方, 平方-乘算法
+    pub fn pow(&self, mut exp: u32) -> BigUint {
+        let mut base = self.clone();
+        let mut result = BigUint::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = &result * &base;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// 按指定进制解析, 进制必须在 2..=36 之间, 与 u32::from_str_radix 一样允许一个前导的 +
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<BigUint, ParseBigIntError> {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be in 2..=36, got {}",
+            radix
+        );
+        let s = s.strip_prefix('+').unwrap_or(s);
+        if s.is_empty() {
+            return Err(ParseBigIntError::Empty);
+        }
+        // 每次累积 chunk 位数字再乘进结果, 而不是每一位都做一次大数乘法
+        let (chunk, big_base) = chunk_for(radix);
+        let mut limbs: Vec<u32> = Vec::new();
+        // 与 u64::from_str_radix 一样不接受 _ 分隔符, 它会在 to_digit 时报告为非法数字
+        let digits: Vec<char> = s.chars().collect();
+        for part in digits.chunks(chunk as usize) {
+            let mut value = 0u32;
+            for &c in part {
+                let d = c.to_digit(radix).ok_or(ParseBigIntError::InvalidDigit(c))?;
+                value = value * radix + d;
+            }
+            let scale = if part.len() == chunk as usize {
+                big_base
+            } else {
+                radix.pow(part.len() as u32)
+            };
+            // limbs = limbs * scale + value
+            let mut carry = value as u64;
+            for l in limbs.iter_mut() {
+                let t = *l as u64 * scale as u64 + carry;
+                *l = t as u32;
+                carry = t >> 32;
+            }
+            if carry > 0 {
+                limbs.push(carry as u32);
+            }
+        }
+        Ok(BigUint::from_limbs(limbs))
+    }
+
+    /// 按指定进制输出, 字母为小写
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be in 2..=36, got {}",
+            radix
+        );
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        // 反复除以 radix^chunk, 每个余数是 chunk 位数字
+        let (chunk, big_base) = chunk_for(radix);
+        let mut parts = Vec::new();
+        let mut rest = self.limbs.clone();
+        while !rest.is_empty() {
+            let (q, r) = div_rem_small(&rest, big_base);
+            parts.push(r);
+            rest = q;
+        }
+        let mut s = String::new();
+        for (i, &part) in parts.iter().rev().enumerate() {
+            let digits = format_u32_radix(part, radix);
+            if i > 0 {
+                s.push_str(&"0".repeat(chunk as usize - digits.len()));
+            }
+            s.push_str(&digits);
+        }
+        s
+    }
+}
+
+// radix^chunk 是不超过 u32::MAX 的最大幂
+fn chunk_for(radix: u32) -> (u32, u32) {
+    let mut chunk = 1;
+    let mut base = radix;
+    while let Some(next) = base.checked_mul(radix) {
+        base = next;
+        chunk += 1;
+    }
+    (chunk, base)
+}
+
+fn format_u32_radix(mut v: u32, radix: u32) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(std::char::from_digit(v % radix, radix).unwrap());
+        v /= radix;
+        if v == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        cmp_limbs(&self.limbs, &other.limbs)
+    }
+}
+
+impl<'a> Add<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn add(self, rhs: &BigUint) -> BigUint {
+        BigUint::from_limbs(add_limbs(&self.limbs, &rhs.limbs))
+    }
+}
+
+// 与基本类型一样, 结果为负数时 panic
+impl<'a> Sub<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn sub(self, rhs: &BigUint) -> BigUint {
+        self.checked_sub(rhs)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl<'a> Mul<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        BigUint::from_limbs(mul_limbs(&self.limbs, &rhs.limbs))
+    }
+}
+
+impl<'a> Div<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn div(self, rhs: &BigUint) -> BigUint {
+        self.div_rem(rhs).0
+    }
+}
+
+impl<'a> Rem<&'a BigUint> for &'a BigUint {
+    type Output = BigUint;
+    fn rem(self, rhs: &BigUint) -> BigUint {
+        self.div_rem(rhs).1
+    }
+}
+
+impl Shl<u32> for &BigUint {
+    type Output = BigUint;
+    fn shl(self, n: u32) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs = vec![0u32; (n / 32) as usize];
+        limbs.extend(shl_limbs(&self.limbs, n % 32));
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shr<u32> for &BigUint {
+    type Output = BigUint;
+    fn shr(self, n: u32) -> BigUint {
+        let skip = (n / 32) as usize;
+        if skip >= self.limbs.len() {
+            return BigUint::zero();
+        }
+        BigUint::from_limbs(shr_limbs(&self.limbs[skip..], n % 32))
+    }
+}
+
+impl Shl<u32> for BigUint {
+    type Output = BigUint;
+    fn shl(self, n: u32) -> BigUint {
+        &self << n
+    }
+}
+
+impl Shr<u32> for BigUint {
+    type Output = BigUint;
+    fn shr(self, n: u32) -> BigUint {
+        &self >> n
+    }
+}
+
+// 运算都实现在引用上, 其余三种组合以及复合赋值都转发过去
+macro_rules! forward_ops {
+    ($t:ty; $($op:ident $method:ident $op_assign:ident $method_assign:ident),*) => {$(
+        impl $op<$t> for $t {
+            type Output = $t;
+            fn $method(self, rhs: $t) -> $t {
+                (&self).$method(&rhs)
+            }
+        }
+        impl<'a> $op<&'a $t> for $t {
+            type Output = $t;
+            fn $method(self, rhs: &$t) -> $t {
+                (&self).$method(rhs)
+            }
+        }
+        impl<'a> $op<$t> for &'a $t {
+            type Output = $t;
+            fn $method(self, rhs: $t) -> $t {
+                self.$method(&rhs)
+            }
+        }
+        impl $op_assign<$t> for $t {
+            fn $method_assign(&mut self, rhs: $t) {
+                *self = (&*self).$method(&rhs);
+            }
+        }
+        impl<'a> $op_assign<&'a $t> for $t {
+            fn $method_assign(&mut self, rhs: &$t) {
+                *self = (&*self).$method(rhs);
+            }
+        }
+    )*};
+}
+
+forward_ops!(BigUint;
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign,
+    Rem rem RemAssign rem_assign
+);
+
+impl Sum for BigUint {
+    fn sum<I: Iterator<Item = BigUint>>(iter: I) -> BigUint {
+        iter.fold(BigUint::zero(), |acc, x| acc + x)
+    }
+}
+
+impl Product for BigUint {
+    fn product<I: Iterator<Item = BigUint>>(iter: I) -> BigUint {
+        iter.fold(BigUint::one(), |acc, x| acc * x)
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ParseBigIntError;
+    fn from_str(s: &str) -> Result<BigUint, ParseBigIntError> {
+        BigUint::from_str_radix(s, 10)
+    }
+}
+
+// 各种进制的格式化, 支持 {:#x} 这样的前缀和宽度、填充等参数
+macro_rules! impl_radix_fmt {
+    ($t:ident; $($fmt:ident $radix:expr, $prefix:expr, $upper:expr;)*) => {$(
+        impl fmt::$fmt for $t {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let (non_negative, magnitude) = self.sign_and_magnitude();
+                let mut s = magnitude.to_str_radix($radix);
+                if $upper {
+                    s.make_ascii_uppercase();
+                }
+                f.pad_integral(non_negative, $prefix, &s)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_all_fmt {
+    ($($t:ident)*) => {$(
+        impl_radix_fmt!($t;
+            Display 10, "", false;
+            Binary 2, "0b", false;
+            Octal 8, "0o", false;
+            LowerHex 16, "0x", false;
+            UpperHex 16, "0x", true;
+        );
+
+        impl fmt::Debug for $t {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+    )*};
+}
+
+impl_all_fmt!(BigUint BigInt);
+
+impl BigUint {
+    fn sign_and_magnitude(&self) -> (bool, &BigUint) {
+        (true, self)
+    }
+}
+
+impl BigInt {
+    fn sign_and_magnitude(&self) -> (bool, &BigUint) {
+        (!self.negative, &self.magnitude)
+    }
+}
+
+// ---------- BigInt ----------
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    pub fn one() -> BigInt {
+        BigInt::from(1)
+    }
+
+    /// 由符号和绝对值构造, 保证 0 没有负号
+    pub fn from_parts(negative: bool, magnitude: BigUint) -> BigInt {
+        BigInt {
+            negative: negative && !magnitude.is_zero(),
+            magnitude,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn magnitude(&self) -> &BigUint {
+        &self.magnitude
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.magnitude.clone())
+    }
+
+    /// 带余数的除法, 与基本类型的 / 和 % 一样向零取整, 余数的符号与被除数相同
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (q, r) = self.magnitude.div_rem(&other.magnitude);
+        (
+            BigInt::from_parts(self.negative != other.negative, q),
+            BigInt::from_parts(self.negative, r),
+        )
+    }
+
+    pub fn pow(&self, exp: u32) -> BigInt {
+        BigInt::from_parts(self.negative && exp % 2 == 1, self.magnitude.pow(exp))
+    }
+
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<BigInt, ParseBigIntError> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        if negative && digits.starts_with('+') {
+            return Err(ParseBigIntError::InvalidDigit('+'));
+        }
+        Ok(BigInt::from_parts(
+            negative,
+            BigUint::from_str_radix(digits, radix)?,
+        ))
+    }
+
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        let s = self.magnitude.to_str_radix(radix);
+        if self.negative {
+            format!("-{}", s)
+        } else {
+            s
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+        }
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude)
+    }
+}
+
+impl<'a> Add<&'a BigInt> for &'a BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::from_parts(self.negative, &self.magnitude + &rhs.magnitude);
+        }
+        // 符号不同, 用绝对值大的减去小的, 符号跟随绝对值大的
+        match self.magnitude.cmp(&rhs.magnitude) {
+            Ordering::Less => BigInt::from_parts(rhs.negative, &rhs.magnitude - &self.magnitude),
+            _ => BigInt::from_parts(self.negative, &self.magnitude - &rhs.magnitude),
+        }
+    }
+}
+
+impl<'a> Sub<&'a BigInt> for &'a BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl<'a> Mul<&'a BigInt> for &'a BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != rhs.negative,
+            &self.magnitude * &rhs.magnitude,
+        )
+    }
+}
+
+impl<'a> Div<&'a BigInt> for &'a BigInt {
+    type Output = BigInt;
+    fn div(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).0
+    }
+}
+
+impl<'a> Rem<&'a BigInt> for &'a BigInt {
+    type Output = BigInt;
+    fn rem(self, rhs: &BigInt) -> BigInt {
+        self.div_rem(rhs).1
+    }
+}
+
+forward_ops!(BigInt;
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign,
+    Rem rem RemAssign rem_assign
+);
+
+impl Sum for BigInt {
+    fn sum<I: Iterator<Item = BigInt>>(iter: I) -> BigInt {
+        iter.fold(BigInt::zero(), |acc, x| acc + x)
+    }
+}
+
+impl Product for BigInt {
+    fn product<I: Iterator<Item = BigInt>>(iter: I) -> BigInt {
+        iter.fold(BigInt::one(), |acc, x| acc * x)
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+    fn from_str(s: &str) -> Result<BigInt, ParseBigIntError> {
+        BigInt::from_str_radix(s, 10)
+    }
+}
+
+impl From<BigUint> for BigInt {
+    fn from(magnitude: BigUint) -> BigInt {
+        BigInt::from_parts(false, magnitude)
+    }
+}
+
+impl TryFrom<BigInt> for BigUint {
+    type Error = TryFromBigIntError;
+    fn try_from(v: BigInt) -> Result<BigUint, TryFromBigIntError> {
+        if v.negative {
+            Err(TryFromBigIntError(()))
+        } else {
+            Ok(v.magnitude)
+        }
+    }
+}
+
+// ---------- 与基本整数类型的转换 ----------
+
+impl From<u128> for BigUint {
+    fn from(mut v: u128) -> BigUint {
+        let mut limbs = Vec::new();
+        while v > 0 {
+            limbs.push(v as u32);
+            v >>= 32;
+        }
+        BigUint { limbs }
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(v: i128) -> BigInt {
+        // unsigned_abs 对 i128::MIN 也是正确的
+        BigInt::from_parts(v < 0, BigUint::from(v.unsigned_abs()))
+    }
+}
+
+impl BigUint {
+    fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+        Some(
+            self.limbs
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &d| (acc << 32) | d as u128),
+        )
+    }
+}
+
+impl BigInt {
+    fn to_i128(&self) -> Option<i128> {
+        let m = self.magnitude.to_u128()?;
+        if self.negative {
+            // -2^127 的绝对值放不进 i128, 但它本身可以
+            0i128.checked_sub_unsigned(m)
+        } else {
+            i128::try_from(m).ok()
+        }
+    }
+}
+
+macro_rules! impl_primitive {
+    (unsigned $($t:ty)*) => {$(
+        impl From<$t> for BigUint {
+            fn from(v: $t) -> BigUint {
+                BigUint::from(v as u128)
+            }
+        }
+        impl From<$t> for BigInt {
+            fn from(v: $t) -> BigInt {
+                BigInt::from(BigUint::from(v as u128))
+            }
+        }
+    )*};
+    (signed $($t:ty)*) => {$(
+        impl From<$t> for BigInt {
+            fn from(v: $t) -> BigInt {
+                BigInt::from(v as i128)
+            }
+        }
+    )*};
+    (try_from $($t:ty)*) => {$(
+        impl TryFrom<&BigUint> for $t {
+            type Error = TryFromBigIntError;
+            fn try_from(v: &BigUint) -> Result<$t, TryFromBigIntError> {
+                v.to_u128()
+                    .and_then(|v| <$t>::try_from(v).ok())
+                    .ok_or(TryFromBigIntError(()))
+            }
+        }
+        impl TryFrom<BigUint> for $t {
+            type Error = TryFromBigIntError;
+            fn try_from(v: BigUint) -> Result<$t, TryFromBigIntError> {
+                <$t>::try_from(&v)
+            }
+        }
+        impl TryFrom<&BigInt> for $t {
+            type Error = TryFromBigIntError;
+            fn try_from(v: &BigInt) -> Result<$t, TryFromBigIntError> {
+                // u128 的上半部分超出 i128, 所以非负数要先尝试 u128
+                let r = if v.negative {
+                    v.to_i128().and_then(|v| <$t>::try_from(v).ok())
+                } else {
+                    v.magnitude.to_u128().and_then(|v| <$t>::try_from(v).ok())
+                };
+                r.ok_or(TryFromBigIntError(()))
+            }
+        }
+        impl TryFrom<BigInt> for $t {
+            type Error = TryFromBigIntError;
+            fn try_from(v: BigInt) -> Result<$t, TryFromBigIntError> {
+                <$t>::try_from(&v)
+            }
+        }
+    )*};
+}
+
+impl_primitive!(unsigned u8 u16 u32 u64 usize);
+impl_primitive!(signed i8 i16 i32 i64 isize);
+impl_primitive!(try_from u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl From<u128> for BigInt {
+    fn from(v: u128) -> BigInt {
+        BigInt::from(BigUint::from(v))
+    }
+}
+
+pub fn learn_bigint() {
+    // 超出 u128 的结果
+    let max = BigUint::from(u128::MAX);
+    let beyond = &max + &BigUint::one();
+    println!("u128::MAX + 1 = {}", beyond);
+    assert_eq!(beyond, BigUint::from(2u32).pow(128));
+    assert_eq!(
+        format!("{:#x}", beyond),
+        "0x100000000000000000000000000000000"
+    );
+    assert_eq!(u128::try_from(&beyond), Err(TryFromBigIntError(())));
+    assert_eq!(u128::try_from(&beyond - BigUint::one()), Ok(u128::MAX));
+
+    // 对照 ch02::second::integer_overflow: i8 会溢出, 而 BigInt 不会
+    assert_eq!(123_i8.checked_add(113), None);
+    let sum = BigInt::from(123_i8) + BigInt::from(113_i8);
+    assert_eq!(i16::try_from(&sum), Ok(236));
+    assert!(i8::try_from(&sum).is_err());
+    // Wrapping(u32::MAX) + Wrapping(1) 得到 0, 而 BigUint 得到 2^32
+    use std::num::Wrapping;
+    assert_eq!((Wrapping(u32::MAX) + Wrapping(1)).0, 0);
+    assert_eq!(
+        u64::try_from(BigUint::from(u32::MAX) + BigUint::one()),
+        Ok(1 << 32)
+    );
+
+    // 阶乘
+    let f100: BigUint = (1..=100u32).map(BigUint::from).product();
+    println!("100! = {}", f100);
+    assert_eq!(f100.to_string().len(), 158);
+    assert!(f100.to_string().ends_with(&"0".repeat(24)));
+    // 整除: 100! / 98! = 9900
+    let f98: BigUint = (1..=98u32).map(BigUint::from).product();
+    assert_eq!(
+        f100.div_rem(&f98),
+        (BigUint::from(9900u32), BigUint::zero())
+    );
+
+    // 解析和输出
+    let n: BigUint = "123456789012345678901234567890".parse().unwrap();
+    assert_eq!(n.to_str_radix(16), "18ee90ff6c373e0ee4e3f0ad2");
+    assert_eq!(
+        BigUint::from_str_radix("18EE90FF6C373E0EE4E3F0AD2", 16),
+        Ok(n.clone())
+    );
+    assert_eq!(
+        BigUint::from_str_radix(&format!("{:b}", n), 2),
+        Ok(n.clone())
+    );
+    assert_eq!(
+        BigUint::from_str_radix(&format!("{:o}", n), 8),
+        Ok(n.clone())
+    );
+    assert_eq!(format!("{:>40}", n).len(), 40);
+    assert_eq!(
+        "12a".parse::<BigUint>(),
+        Err(ParseBigIntError::InvalidDigit('a'))
+    );
+    assert_eq!("".parse::<BigUint>(), Err(ParseBigIntError::Empty));
+    for s in ["_", "__", "1_000"].iter() {
+        assert_eq!(
+            s.parse::<BigUint>(),
+            Err(ParseBigIntError::InvalidDigit('_'))
+        );
+        assert!(s.parse::<u64>().is_err());
+    }
+
+    // 有符号: 除法向零取整, 余数符号跟随被除数, 与基本类型一致
+    let a = BigInt::from(-7);
+    let b = BigInt::from(2);
+    assert_eq!(a.div_rem(&b), (BigInt::from(-7 / 2), BigInt::from(-7 % 2)));
+    assert_eq!(format!("{:x}", BigInt::from(-255)), "-ff");
+    assert_eq!("-0".parse::<BigInt>(), Ok(BigInt::zero()));
+    assert_eq!(i128::try_from(BigInt::from(i128::MIN)), Ok(i128::MIN));
+    assert!(i128::try_from(-BigInt::from(i128::MIN)).is_err());
+    assert!(BigInt::from(-1) < BigInt::zero());
+    let neg = BigInt::from(-3).pow(41);
+    println!("(-3)^41 = {}", neg);
+    assert!(neg.is_negative());
+
+    // 大数乘除法: Karatsuba 和 Algorithm D, 与简单的算法对照
+    let x = BigUint::from(3u32).pow(5000) - BigUint::one();
+    let y = BigUint::from(7u32).pow(3000) + BigUint::from(12345u32);
+    assert!(x.limbs.len() > KARATSUBA_THRESHOLD && y.limbs.len() > KARATSUBA_THRESHOLD);
+    let product = &x * &y;
+    assert_eq!(product.limbs, schoolbook(&x.limbs, &y.limbs));
+    let (q, r) = product.div_rem(&x);
+    assert_eq!((q, r), (y.clone(), BigUint::zero()));
+    let (q, r) = (&product + &BigUint::from(999u32)).div_rem(&y);
+    assert_eq!((q, r), (x.clone(), BigUint::from(999u32)));
+    println!(
+        "3^5000 has {} bits, {} decimal digits",
+        x.bits(),
+        x.to_string().len()
+    );
+}
@@ -0,0 +1,449 @@
+#![allow(dead_code)]
+
+// 运行期的插件注册表
+// first::trait3 为 dyn Round 实现了 Shape, 但拿到一个 trait object 之后, 我们无法知道它背后是什么类型,
+// 也无法问它"你还实现了哪些 trait"。std::any::Any 提供了运行期的类型信息:
+// 1. TypeId::of::<T>() 是类型的唯一标识, 可以当作 HashMap 的键
+// 2. Box<dyn Any> 可以装任何 'static 类型, downcast_ref::<T>() 检查类型之后才转换, 类型不对返回 None
+// 但是 Any 只能转换成具体类型, 不能从 &dyn Any 直接得到 &dyn Trait。
+// 所以注册组件时, 同时登记它提供的每个能力(capability), 也就是从 &dyn Any 到 &dyn Trait 的转换函数:
+//
+// let mut registry = Registry::new();
+// registry.register(Cache::new());
+// provides!(registry, Cache => dyn Service, dyn Metrics)?;
+// for (name, metrics) in registry.views::<dyn Metrics>() { ... }
+//
+// 这样就可以在运行期查询某个组件有没有某种能力, 把可选的功能插到服务上。
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+// 从 &dyn Any 转换成 &dyn Trait, 组件类型不对时返回 None
+type RefView<D> = Box<dyn Fn(&dyn Any) -> Option<&D>>;
+type MutView<D> = Box<dyn Fn(&mut dyn Any) -> Option<&mut D>>;
+
+// 一种能力的转换函数, 放在 Box<dyn Any> 中, 取出来时再 downcast 成 View<D>
+struct View<D: ?Sized + 'static> {
+    as_ref: RefView<D>,
+    as_mut: MutView<D>,
+}
+
+struct Capability {
+    name: &'static str,
+    view: Box<dyn Any>,
+}
+
+struct Component {
+    name: &'static str,
+    value: Box<dyn Any>,
+    capabilities: HashMap<TypeId, Capability>,
+}
+
+/// 按 TypeId 查找或者转换失败
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LookupError {
+    NotRegistered(TypeId),
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    MissingCapability {
+        component: &'static str,
+        capability: &'static str,
+    },
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LookupError::NotRegistered(id) => write!(f, "no component registered for {:?}", id),
+            LookupError::WrongType { expected, found } => {
+                write!(f, "component is {}, not {}", found, expected)
+            }
+            LookupError::MissingCapability {
+                component,
+                capability,
+            } => write!(f, "{} does not provide {}", component, capability),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+/// 去掉类型名中的路径, dyn a::b::Service 变成 dyn Service
+pub fn short_name(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        out.push(c);
+        if out.ends_with("::") {
+            out.truncate(out.len() - 2);
+            while out.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                out.pop();
+            }
+        }
+    }
+    out
+}
+
+/// 每种类型最多注册一个组件, 按注册的顺序列出
+#[derive(Default)]
+pub struct Registry {
+    components: Vec<Component>,
+    index: HashMap<TypeId, usize>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// 注册一个组件, 同一类型已经注册过时替换掉原来的值(已经登记的能力保留), 返回原来的值
+    pub fn register<C: Any>(&mut self, component: C) -> Option<C> {
+        let id = TypeId::of::<C>();
+        if let Some(&i) = self.index.get(&id) {
+            let old = std::mem::replace(&mut self.components[i].value, Box::new(component));
+            return old.downcast::<C>().ok().map(|b| *b);
+        }
+        self.index.insert(id, self.components.len());
+        self.components.push(Component {
+            name: type_name::<C>(),
+            value: Box::new(component),
+            capabilities: HashMap::new(),
+        });
+        None
+    }
+
+    /// 登记组件 C 提供能力 D, 两个函数一般就是 |c| c, 由编译器完成 &C 到 &dyn Trait 的转换。
+    /// 通常用 provides! 宏调用。C 还没有注册时返回 NotRegistered
+    pub fn provide<C: Any, D: ?Sized + 'static>(
+        &mut self,
+        as_ref: fn(&C) -> &D,
+        as_mut: fn(&mut C) -> &mut D,
+    ) -> Result<(), LookupError> {
+        let id = TypeId::of::<C>();
+        let i = match self.index.get(&id) {
+            Some(&i) => i,
+            None => return Err(LookupError::NotRegistered(id)),
+        };
+        let view: View<D> = View {
+            as_ref: Box::new(move |any| any.downcast_ref::<C>().map(as_ref)),
+            as_mut: Box::new(move |any| any.downcast_mut::<C>().map(as_mut)),
+        };
+        self.components[i].capabilities.insert(
+            TypeId::of::<D>(),
+            Capability {
+                name: type_name::<D>(),
+                view: Box::new(view),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn contains(&self, id: TypeId) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    fn component(&self, id: TypeId) -> Result<&Component, LookupError> {
+        match self.index.get(&id) {
+            Some(&i) => Ok(&self.components[i]),
+            None => Err(LookupError::NotRegistered(id)),
+        }
+    }
+
+    /// 按 TypeId 取得组件, 类型在运行期才知道
+    pub fn get_by_id(&self, id: TypeId) -> Option<&dyn Any> {
+        self.component(id).ok().map(|c| &*c.value)
+    }
+
+    pub fn get<C: Any>(&self) -> Option<&C> {
+        self.get_by_id(TypeId::of::<C>())?.downcast_ref()
+    }
+
+    pub fn get_mut<C: Any>(&mut self) -> Option<&mut C> {
+        let i = *self.index.get(&TypeId::of::<C>())?;
+        self.components[i].value.downcast_mut()
+    }
+
+    /// 检查过的转换: 按 id 找到组件, 再转换成 C, 两步失败时的错误不同
+    pub fn downcast<C: Any>(&self, id: TypeId) -> Result<&C, LookupError> {
+        let component = self.component(id)?;
+        component
+            .value
+            .downcast_ref()
+            .ok_or(LookupError::WrongType {
+                expected: type_name::<C>(),
+                found: component.name,
+            })
+    }
+
+    /// 取出组件, 它的能力也一起删除
+    pub fn remove<C: Any>(&mut self) -> Option<C> {
+        let i = self.index.remove(&TypeId::of::<C>())?;
+        let component = self.components.remove(i);
+        for v in self.index.values_mut() {
+            if *v > i {
+                *v -= 1;
+            }
+        }
+        component.value.downcast().ok().map(|b| *b)
+    }
+
+    // 组件的能力 D, 登记时的类型保证了 downcast 一定成功
+    fn view_of<D: ?Sized + 'static>(
+        capabilities: &HashMap<TypeId, Capability>,
+    ) -> Option<&View<D>> {
+        capabilities
+            .get(&TypeId::of::<D>())
+            .and_then(|c| c.view.downcast_ref::<View<D>>())
+    }
+
+    /// 以能力 D 的形式访问 id 对应的组件
+    pub fn view<D: ?Sized + 'static>(&self, id: TypeId) -> Result<&D, LookupError> {
+        let component = self.component(id)?;
+        Self::view_of::<D>(&component.capabilities)
+            .and_then(|view| (view.as_ref)(&*component.value))
+            .ok_or(LookupError::MissingCapability {
+                component: component.name,
+                capability: type_name::<D>(),
+            })
+    }
+
+    pub fn view_mut<D: ?Sized + 'static>(&mut self, id: TypeId) -> Result<&mut D, LookupError> {
+        let i = match self.index.get(&id) {
+            Some(&i) => i,
+            None => return Err(LookupError::NotRegistered(id)),
+        };
+        let component = &mut self.components[i];
+        let missing = LookupError::MissingCapability {
+            component: component.name,
+            capability: type_name::<D>(),
+        };
+        match Self::view_of::<D>(&component.capabilities) {
+            Some(view) => (view.as_mut)(&mut *component.value).ok_or(missing),
+            None => Err(missing),
+        }
+    }
+
+    /// 所有提供能力 D 的组件, 按注册顺序
+    pub fn views<D: ?Sized + 'static>(&self) -> impl Iterator<Item = (&'static str, &D)> + '_ {
+        self.components.iter().filter_map(|c| {
+            let view = Self::view_of::<D>(&c.capabilities)?;
+            Some((c.name, (view.as_ref)(&*c.value)?))
+        })
+    }
+
+    /// 对所有提供能力 D 的组件执行 f
+    pub fn for_each_mut<D: ?Sized + 'static, F: FnMut(&mut D)>(&mut self, mut f: F) {
+        for c in self.components.iter_mut() {
+            if let Some(view) = Self::view_of::<D>(&c.capabilities) {
+                if let Some(d) = (view.as_mut)(&mut *c.value) {
+                    f(d);
+                }
+            }
+        }
+    }
+
+    pub fn provides<D: ?Sized + 'static>(&self, id: TypeId) -> bool {
+        self.component(id)
+            .map(|c| c.capabilities.contains_key(&TypeId::of::<D>()))
+            .unwrap_or(false)
+    }
+
+    /// 所有组件和它们的能力, 名字去掉了路径, 能力按名字排序
+    pub fn capabilities(&self) -> Vec<(String, Vec<String>)> {
+        self.components
+            .iter()
+            .map(|c| {
+                let mut caps: Vec<String> = c
+                    .capabilities
+                    .values()
+                    .map(|cap| short_name(cap.name))
+                    .collect();
+                caps.sort();
+                (short_name(c.name), caps)
+            })
+            .collect()
+    }
+}
+
+/// provides!(registry, Type => dyn A, dyn B) 登记 Type 提供能力 A 和 B,
+/// 结果是 Result<(), LookupError>, Type 还没有注册时什么也不登记
+#[macro_export]
+macro_rules! provides {
+    ($registry:expr, $ty:ty => $($cap:ty),+ $(,)?) => {{
+        let mut result = Ok(());
+        $(
+            if result.is_ok() {
+                result = $registry.provide::<$ty, $cap>(|c| c, |c| c);
+            }
+        )+
+        result
+    }};
+}
+
+// ---------- 例子: 可以插拔的服务 ----------
+
+pub trait Service {
+    fn handle(&mut self, request: &str) -> String;
+}
+
+pub trait HealthCheck {
+    fn healthy(&self) -> bool;
+}
+
+pub trait Metrics {
+    fn counters(&self) -> Vec<(&'static str, u64)>;
+}
+
+#[derive(Debug, Default)]
+pub struct Cache {
+    entries: HashMap<String, String>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Service for Cache {
+    fn handle(&mut self, request: &str) -> String {
+        match self.entries.get(request) {
+            Some(v) => {
+                self.hits += 1;
+                v.clone()
+            }
+            None => {
+                self.misses += 1;
+                let v = request.to_uppercase();
+                self.entries.insert(request.to_string(), v.clone());
+                v
+            }
+        }
+    }
+}
+
+impl Metrics for Cache {
+    fn counters(&self) -> Vec<(&'static str, u64)> {
+        vec![("hits", self.hits), ("misses", self.misses)]
+    }
+}
+
+#[derive(Debug)]
+pub struct Auth {
+    pub users: Vec<&'static str>,
+    pub backend_up: bool,
+}
+
+impl Service for Auth {
+    fn handle(&mut self, request: &str) -> String {
+        if self.users.contains(&request) {
+            format!("welcome {}", request)
+        } else {
+            "denied".to_string()
+        }
+    }
+}
+
+impl HealthCheck for Auth {
+    fn healthy(&self) -> bool {
+        self.backend_up
+    }
+}
+
+/// 只有数据, 不提供任何能力
+#[derive(Debug, PartialEq)]
+pub struct Settings {
+    pub port: u16,
+}
+
+pub fn learn_plugin_registry() {
+    let mut registry = Registry::new();
+    registry.register(Settings { port: 8080 });
+    registry.register(Cache::default());
+    registry.register(Auth {
+        users: vec!["alice"],
+        backend_up: true,
+    });
+    provides!(registry, Cache => dyn Service, dyn Metrics).unwrap();
+    provides!(registry, Auth => dyn Service, dyn HealthCheck).unwrap();
+    // 忘了注册组件时, 登记能力会失败, 而不是悄悄地什么也不做
+    let err = provides!(registry, String => dyn fmt::Display).unwrap_err();
+    assert_eq!(err, LookupError::NotRegistered(TypeId::of::<String>()));
+
+    for (component, caps) in registry.capabilities() {
+        println!("{:<10} {:?}", component, caps);
+    }
+    assert_eq!(
+        registry.capabilities(),
+        vec![
+            ("Settings".to_string(), vec![]),
+            (
+                "Cache".to_string(),
+                vec!["dyn Metrics".to_string(), "dyn Service".to_string()]
+            ),
+            (
+                "Auth".to_string(),
+                vec!["dyn HealthCheck".to_string(), "dyn Service".to_string()]
+            ),
+        ]
+    );
+
+    // 按具体类型取得组件
+    assert_eq!(registry.get::<Settings>(), Some(&Settings { port: 8080 }));
+    registry.get_mut::<Settings>().unwrap().port = 9090;
+
+    // 只知道 TypeId 时, 先取得 &dyn Any, 再检查过类型之后转换
+    let id = TypeId::of::<Settings>();
+    let any = registry.get_by_id(id).unwrap();
+    assert!(any.is::<Settings>() && !any.is::<Cache>());
+    assert_eq!(registry.downcast::<Settings>(id).unwrap().port, 9090);
+    let err = registry.downcast::<Cache>(id).unwrap_err();
+    println!("{}", err);
+    assert!(matches!(err, LookupError::WrongType { .. }));
+    let err = registry
+        .downcast::<String>(TypeId::of::<String>())
+        .unwrap_err();
+    assert_eq!(err, LookupError::NotRegistered(TypeId::of::<String>()));
+
+    // 通过能力访问组件, 不需要知道它的具体类型
+    let cache = TypeId::of::<Cache>();
+    let service = registry.view_mut::<dyn Service>(cache).unwrap();
+    assert_eq!(service.handle("a"), "A");
+    assert_eq!(service.handle("a"), "A");
+    let counters = registry.view::<dyn Metrics>(cache).unwrap().counters();
+    assert_eq!(counters, vec![("hits", 1), ("misses", 1)]);
+    assert!(!registry.provides::<dyn Service>(id));
+    let err = registry.view::<dyn HealthCheck>(cache).err().unwrap();
+    println!("{}", short_name(&err.to_string()));
+
+    // 把同一个请求发给所有的服务
+    let mut replies = Vec::new();
+    registry.for_each_mut::<dyn Service, _>(|s| replies.push(s.handle("alice")));
+    assert_eq!(replies, ["ALICE", "welcome alice"]);
+    // 只检查提供了健康检查的组件
+    let unhealthy: Vec<&str> = registry
+        .views::<dyn HealthCheck>()
+        .filter(|(_, h)| !h.healthy())
+        .map(|(name, _)| name)
+        .collect();
+    assert!(unhealthy.is_empty());
+    registry.get_mut::<Auth>().unwrap().backend_up = false;
+    assert_eq!(registry.views::<dyn HealthCheck>().count(), 1);
+    assert!(registry
+        .views::<dyn HealthCheck>()
+        .all(|(_, h)| !h.healthy()));
+
+    // 替换和删除
+    let old = registry.register(Settings { port: 1 }).unwrap();
+    assert_eq!(old.port, 9090);
+    let cache: Cache = registry.remove().unwrap();
+    assert_eq!(cache.counters(), vec![("hits", 1), ("misses", 2)]);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.views::<dyn Service>().count(), 1);
+    assert!(registry.view::<dyn Service>(TypeId::of::<Auth>()).is_ok());
+}
@@ -0,0 +1,499 @@
+#![allow(dead_code)]
+
+// trait object 的向上转换和向下转换
+// fifth 中的 trait Derived: Base {} 只说明了实现 Derived 的类型必须实现 Base,
+// 但拿到 &dyn Derived 之后, 旧版本的编译器不允许把它直接当作 &dyn Base 使用(Rust 1.86 才支持),
+// 反过来从 &dyn Base 得到 &dyn Derived 或者具体类型, 编译器更是无能为力。
+// 这里手动实现这两个方向的转换, 对所有的编译器版本都适用:
+// 1. 向上: Upcast<dyn Base> 作为 Derived 的父 trait, 它的方法就进了 dyn Derived 的虚表,
+//    &dyn Derived、Box、Rc、Arc 都能转换成 dyn Base 的形式
+// 2. 向下: 根 trait Cast 提供 &dyn Any, 可以转换回具体类型;
+//    dyn_cast! 为 dyn Base 添加向下转换的方法, &dyn Base 也能转换成 casts! 中列出的 &dyn Derived
+// casts! 为每个具体类型列出它能变成的 trait object, 同时实现 Cast 和 Upcast。
+// 没有用 impl<T: Base> Upcast<dyn Base> for T 一次实现所有类型, 因为这在其他 crate 中违反孤儿规则,
+// 按具体类型实现, 两个宏都可以导出给其他 crate 使用:
+//
+// trait Base: Cast { ... }
+// trait Derived: Base + Upcast<dyn Base> { ... }
+// dyn_cast!(Base, Derived);
+// casts!(Middle => dyn Derived, dyn Base);
+use std::any::{Any, TypeId};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// 从 Self 转换成 U, U 通常是 dyn Base; 方法的接收者都是 object safe 的, 可以通过 dyn Derived 调用
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch05::fifteenth::{Base, Bottom, Derived, Final, Upcast};
+///
+/// let bottom: Box<dyn Final> = Box::new(Bottom::new("b", 3));
+/// let derived: &dyn Derived = bottom.upcast_ref();
+/// let base: &dyn Base = derived.upcast_ref();
+/// assert_eq!(base.name(), "b");
+/// // 跳过中间的一层
+/// let base: Box<dyn Base> = Upcast::<dyn Base>::upcast_box(bottom);
+/// assert!(base.is::<Bottom>());
+/// ```
+///
+/// 只能向上转换, 向下转换要用 cast_ref:
+///
+/// ```compile_fail,E0599
+/// use dive_into_rust_of_fcc::ch05::fifteenth::{Base, Derived, Middle, Upcast};
+///
+/// let base: &dyn Base = &Middle::new("m");
+/// let derived: &dyn Derived = base.upcast_ref();
+/// ```
+pub trait Upcast<U: ?Sized> {
+    fn upcast_ref(&self) -> &U;
+    fn upcast_mut(&mut self) -> &mut U;
+    fn upcast_box(self: Box<Self>) -> Box<U>;
+    fn upcast_rc(self: Rc<Self>) -> Rc<U>;
+    fn upcast_arc(self: Arc<Self>) -> Arc<U>;
+}
+
+// 转换失败时还回原来的指针
+type CastBox<U> = fn(Box<dyn Any>) -> Result<Box<U>, Box<dyn Any>>;
+type CastRc<U> = fn(Rc<dyn Any>) -> Result<Rc<U>, Rc<dyn Any>>;
+type CastArc<U> = fn(Arc<dyn Any>) -> Result<Arc<U>, Arc<dyn Any>>;
+
+/// 向下转换到 trait object 的函数, 由 casts! 为每个具体类型生成
+pub struct Caster<U: ?Sized + 'static> {
+    pub cast_ref: fn(&dyn Any) -> Option<&U>,
+    pub cast_mut: fn(&mut dyn Any) -> Option<&mut U>,
+    pub cast_box: CastBox<U>,
+    pub cast_rc: CastRc<U>,
+    pub cast_arc: CastArc<U>,
+}
+
+/// 层次结构的根, 通常由 casts! 实现
+pub trait Cast: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn into_any_rc(self: Rc<Self>) -> Rc<dyn Any>;
+    fn into_any_arc(self: Arc<Self>) -> Arc<dyn Any>;
+    fn type_name(&self) -> &'static str;
+    /// target 是 TypeId::of::<dyn Trait>(), 返回 &'static Caster<dyn Trait>;
+    /// 放在 dyn Any 中是因为每个 target 的 Caster 类型都不同
+    fn caster(&self, target: TypeId) -> Option<&'static dyn Any>;
+}
+
+/// 标准库只为 Arc<dyn Any + Send + Sync> 提供了 downcast, 这里对 Arc<dyn Any> 做同样的事
+#[doc(hidden)]
+pub fn downcast_arc<T: Any>(a: Arc<dyn Any>) -> Result<Arc<T>, Arc<dyn Any>> {
+    if a.is::<T>() {
+        // 类型已经检查过, 去掉虚表指针就是指向 T 的指针, 与标准库的做法相同
+        Ok(unsafe { Arc::from_raw(Arc::into_raw(a) as *const T) })
+    } else {
+        Err(a)
+    }
+}
+
+/// 宏里的 &$target 会被当成 &'a (dyn A + 'a), 套一层别名就和 trait 中的 &'a (dyn A + 'static) 一致了
+#[doc(hidden)]
+pub type Static<U> = U;
+
+/// casts!(Type => dyn A, dyn B) 为 Type 实现 Cast, 之后 dyn Base 可以转换成 dyn A、dyn B;
+/// 同时为 Type 实现 Upcast<dyn A> 和 Upcast<dyn B>, 这样 Type 才能满足 trait A: Upcast<dyn B> 这样的约束。
+/// 实现的都是 Type 自己的 trait, 所以在其他 crate 中也能用
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch05::fifteenth::{Cast, Upcast};
+/// use dive_into_rust_of_fcc::{casts, dyn_cast};
+/// use std::sync::Arc;
+///
+/// trait Shape: Cast {
+///     fn area(&self) -> f64;
+/// }
+/// trait Polygon: Shape + Upcast<dyn Shape> {
+///     fn sides(&self) -> u32;
+/// }
+/// dyn_cast!(Shape, Polygon);
+///
+/// struct Square(f64);
+/// impl Shape for Square {
+///     fn area(&self) -> f64 {
+///         self.0 * self.0
+///     }
+/// }
+/// impl Polygon for Square {
+///     fn sides(&self) -> u32 {
+///         4
+///     }
+/// }
+/// casts!(Square => dyn Polygon, dyn Shape);
+///
+/// let polygon: Arc<dyn Polygon> = Arc::new(Square(2.0));
+/// let shape: Arc<dyn Shape> = polygon.upcast_arc();
+/// assert_eq!(shape.area(), 4.0);
+/// let polygon = shape.cast_arc::<dyn Polygon>().ok().unwrap();
+/// assert_eq!(polygon.sides(), 4);
+/// let square: Arc<Square> = polygon.downcast_arc().ok().unwrap();
+/// assert_eq!(square.0, 2.0);
+/// ```
+#[macro_export]
+macro_rules! casts {
+    ($t:ty => $($target:ty),* $(,)?) => {
+        impl $crate::ch05::fifteenth::Cast for $t {
+            fn as_any(&self) -> &dyn ::std::any::Any {
+                self
+            }
+            fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+                self
+            }
+            fn into_any(self: Box<Self>) -> Box<dyn ::std::any::Any> {
+                self
+            }
+            fn into_any_rc(self: ::std::rc::Rc<Self>) -> ::std::rc::Rc<dyn ::std::any::Any> {
+                self
+            }
+            fn into_any_arc(
+                self: ::std::sync::Arc<Self>,
+            ) -> ::std::sync::Arc<dyn ::std::any::Any> {
+                self
+            }
+            fn type_name(&self) -> &'static str {
+                ::std::any::type_name::<$t>()
+            }
+            fn caster(
+                &self,
+                target: ::std::any::TypeId,
+            ) -> Option<&'static dyn ::std::any::Any> {
+                $(
+                    if target == ::std::any::TypeId::of::<$target>() {
+                        // 常量的引用会被提升为 'static
+                        const CASTER: $crate::ch05::fifteenth::Caster<$target> =
+                            $crate::ch05::fifteenth::Caster {
+                                cast_ref: |a| a.downcast_ref::<$t>().map(|t| t as &$target),
+                                cast_mut: |a| a.downcast_mut::<$t>().map(|t| t as &mut $target),
+                                cast_box: |a| a.downcast::<$t>().map(|t| t as Box<$target>),
+                                cast_rc: |a| {
+                                    a.downcast::<$t>().map(|t| t as ::std::rc::Rc<$target>)
+                                },
+                                cast_arc: |a| {
+                                    $crate::ch05::fifteenth::downcast_arc::<$t>(a)
+                                        .map(|t| t as ::std::sync::Arc<$target>)
+                                },
+                            };
+                        return Some(&CASTER);
+                    }
+                )*
+                None
+            }
+        }
+
+        $(
+            impl $crate::ch05::fifteenth::Upcast<$target> for $t {
+                fn upcast_ref(&self) -> &$crate::ch05::fifteenth::Static<$target> {
+                    self
+                }
+                fn upcast_mut(&mut self) -> &mut $crate::ch05::fifteenth::Static<$target> {
+                    self
+                }
+                fn upcast_box(self: Box<Self>) -> Box<$target> {
+                    self
+                }
+                fn upcast_rc(self: ::std::rc::Rc<Self>) -> ::std::rc::Rc<$target> {
+                    self
+                }
+                fn upcast_arc(self: ::std::sync::Arc<Self>) -> ::std::sync::Arc<$target> {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+/// dyn_cast!(Base, Derived) 为 dyn Base 和 dyn Derived 添加向下转换的方法: is、downcast_*、cast_*。
+/// 这些是 dyn Trait 的固有方法, 只能写在定义 Trait 的 crate 中, 对其他 crate 定义的 trait 同样适用
+#[macro_export]
+macro_rules! dyn_cast {
+    ($($tr:ident),+ $(,)?) => {$(
+        impl dyn $tr {
+            pub fn is<T: $tr>(&self) -> bool {
+                $crate::ch05::fifteenth::Cast::as_any(self).is::<T>()
+            }
+
+            pub fn downcast_ref<T: $tr>(&self) -> Option<&T> {
+                $crate::ch05::fifteenth::Cast::as_any(self).downcast_ref()
+            }
+
+            pub fn downcast_mut<T: $tr>(&mut self) -> Option<&mut T> {
+                $crate::ch05::fifteenth::Cast::as_any_mut(self).downcast_mut()
+            }
+
+            /// 类型不对时把原来的 Box 还回去
+            pub fn downcast_box<T: $tr>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
+                if self.is::<T>() {
+                    let any = $crate::ch05::fifteenth::Cast::into_any(self);
+                    Ok(any.downcast().unwrap_or_else(|_| unreachable!()))
+                } else {
+                    Err(self)
+                }
+            }
+
+            pub fn downcast_rc<T: $tr>(
+                self: ::std::rc::Rc<Self>,
+            ) -> Result<::std::rc::Rc<T>, ::std::rc::Rc<Self>> {
+                if self.is::<T>() {
+                    let any = $crate::ch05::fifteenth::Cast::into_any_rc(self);
+                    Ok(any.downcast().unwrap_or_else(|_| unreachable!()))
+                } else {
+                    Err(self)
+                }
+            }
+
+            pub fn downcast_arc<T: $tr>(
+                self: ::std::sync::Arc<Self>,
+            ) -> Result<::std::sync::Arc<T>, ::std::sync::Arc<Self>> {
+                if self.is::<T>() {
+                    let any = $crate::ch05::fifteenth::Cast::into_any_arc(self);
+                    Ok($crate::ch05::fifteenth::downcast_arc(any).unwrap_or_else(|_| unreachable!()))
+                } else {
+                    Err(self)
+                }
+            }
+
+            fn caster_of<U: ?Sized + 'static>(
+                &self,
+            ) -> Option<&'static $crate::ch05::fifteenth::Caster<U>> {
+                let target = ::std::any::TypeId::of::<U>();
+                $crate::ch05::fifteenth::Cast::caster(self, target)?.downcast_ref()
+            }
+
+            /// 转换成 casts! 中列出的另一个 trait object, 可以是子 trait, 也可以是不相关的 trait
+            pub fn cast_ref<U: ?Sized + 'static>(&self) -> Option<&U> {
+                (self.caster_of::<U>()?.cast_ref)($crate::ch05::fifteenth::Cast::as_any(self))
+            }
+
+            pub fn cast_mut<U: ?Sized + 'static>(&mut self) -> Option<&mut U> {
+                let cast_mut = self.caster_of::<U>()?.cast_mut;
+                cast_mut($crate::ch05::fifteenth::Cast::as_any_mut(self))
+            }
+
+            pub fn cast_box<U: ?Sized + 'static>(self: Box<Self>) -> Result<Box<U>, Box<Self>> {
+                match self.caster_of::<U>() {
+                    // Caster 来自同一个具体类型, 转换不会失败
+                    Some(c) => {
+                        let any = $crate::ch05::fifteenth::Cast::into_any(self);
+                        Ok((c.cast_box)(any).unwrap_or_else(|_| unreachable!()))
+                    }
+                    None => Err(self),
+                }
+            }
+
+            pub fn cast_rc<U: ?Sized + 'static>(
+                self: ::std::rc::Rc<Self>,
+            ) -> Result<::std::rc::Rc<U>, ::std::rc::Rc<Self>> {
+                match self.caster_of::<U>() {
+                    Some(c) => {
+                        let any = $crate::ch05::fifteenth::Cast::into_any_rc(self);
+                        Ok((c.cast_rc)(any).unwrap_or_else(|_| unreachable!()))
+                    }
+                    None => Err(self),
+                }
+            }
+
+            pub fn cast_arc<U: ?Sized + 'static>(
+                self: ::std::sync::Arc<Self>,
+            ) -> Result<::std::sync::Arc<U>, ::std::sync::Arc<Self>> {
+                match self.caster_of::<U>() {
+                    Some(c) => {
+                        let any = $crate::ch05::fifteenth::Cast::into_any_arc(self);
+                        Ok((c.cast_arc)(any).unwrap_or_else(|_| unreachable!()))
+                    }
+                    None => Err(self),
+                }
+            }
+        }
+    )+};
+}
+
+// ---------- 三层的例子 ----------
+
+pub trait Base: Cast {
+    fn name(&self) -> String;
+}
+
+pub trait Derived: Base + Upcast<dyn Base> {
+    fn level(&self) -> u32;
+
+    fn rename(&mut self, name: &str);
+}
+
+pub trait Final: Derived + Upcast<dyn Derived> + Upcast<dyn Base> {
+    fn describe(&self) -> String {
+        format!("{} at level {}", self.name(), self.level())
+    }
+}
+
+dyn_cast!(Base, Derived, Final);
+
+/// 只实现了 Base
+pub struct Plain(pub u32);
+
+impl Base for Plain {
+    fn name(&self) -> String {
+        format!("plain#{}", self.0)
+    }
+}
+
+casts!(Plain => dyn Base);
+
+pub struct Middle {
+    name: String,
+}
+
+impl Middle {
+    pub fn new(name: &str) -> Middle {
+        Middle {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Base for Middle {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl Derived for Middle {
+    fn level(&self) -> u32 {
+        1
+    }
+
+    fn rename(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+}
+
+casts!(Middle => dyn Derived, dyn Base);
+
+pub struct Bottom {
+    inner: Middle,
+    level: u32,
+}
+
+impl Bottom {
+    pub fn new(name: &str, level: u32) -> Bottom {
+        Bottom {
+            inner: Middle::new(name),
+            level,
+        }
+    }
+}
+
+impl Base for Bottom {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+}
+
+impl Derived for Bottom {
+    fn level(&self) -> u32 {
+        self.level
+    }
+
+    fn rename(&mut self, name: &str) {
+        self.inner.rename(name);
+    }
+}
+
+impl Final for Bottom {}
+
+// 也能转换成不在这个层次结构中的 trait
+impl std::fmt::Display for Bottom {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(&self.describe())
+    }
+}
+
+casts!(Bottom => dyn Final, dyn Derived, dyn Base, dyn std::fmt::Display);
+
+fn names(items: &[&dyn Base]) -> Vec<String> {
+    items.iter().map(|b| b.name()).collect()
+}
+
+pub fn learn_upcasting() {
+    // 向上: 每一层都能转换成它上面的任意一层
+    let mut bottom: Box<dyn Final> = Box::new(Bottom::new("bottom", 2));
+    let middle = Middle::new("middle");
+    let plain = Plain(7);
+    {
+        let as_derived: &dyn Derived = bottom.upcast_ref();
+        let as_base: &dyn Base = as_derived.upcast_ref();
+        let skip: &dyn Base = bottom.upcast_ref();
+        assert_eq!(as_base.name(), skip.name());
+        // 不同层次的对象放进同一个 &dyn Base 的列表
+        let list = names(&[&plain, middle.upcast_ref(), skip]);
+        println!("{:?}", list);
+        assert_eq!(list, ["plain#7", "middle", "bottom"]);
+    }
+    let derived: &mut dyn Derived = bottom.upcast_mut();
+    derived.rename("renamed");
+    assert_eq!(bottom.describe(), "renamed at level 2");
+
+    // Box、Rc、Arc 也可以, 指针本身被转换, 不需要复制对象
+    let derived: Box<dyn Derived> = bottom.upcast_box();
+    let base: Box<dyn Base> = derived.upcast_box();
+    assert_eq!(base.type_name(), std::any::type_name::<Bottom>());
+    let rc: Rc<dyn Final> = Rc::new(Bottom::new("shared", 3));
+    let rc_base: Rc<dyn Base> = Upcast::<dyn Base>::upcast_rc(rc.clone());
+    assert_eq!(Rc::strong_count(&rc), 2);
+    assert_eq!(rc_base.name(), "shared");
+    let arc: Arc<dyn Derived> = Arc::new(Middle::new("arc"));
+    let arc_base: Arc<dyn Base> = arc.upcast_arc();
+    assert_eq!(arc_base.name(), "arc");
+    // Arc 也能向下转换, 对象不需要是 Send + Sync
+    let arc_derived = arc_base.cast_arc::<dyn Derived>().ok().unwrap();
+    assert_eq!(arc_derived.level(), 1);
+    let arc_middle: Arc<Middle> = arc_derived.downcast_arc().ok().unwrap();
+    assert_eq!(arc_middle.name, "arc");
+
+    // 向下: 转换回具体类型, 每一层的 dyn 都有这些方法
+    assert!(base.is::<Bottom>() && !base.is::<Middle>());
+    assert_eq!(base.downcast_ref::<Bottom>().map(|b| b.level), Some(2));
+    let derived: &dyn Derived = &middle;
+    assert!(derived.downcast_ref::<Middle>().is_some());
+    let base = match base.downcast_box::<Middle>() {
+        Ok(_) => unreachable!("it is a Bottom"),
+        // 类型不对, 原来的 Box 还回来了
+        Err(base) => base,
+    };
+
+    // 向下: 转换成子 trait, 要看具体类型实现了什么
+    assert_eq!(base.cast_ref::<dyn Derived>().map(|d| d.level()), Some(2));
+    assert_eq!(
+        base.cast_ref::<dyn Final>().map(|f| f.describe()),
+        Some("renamed at level 2".to_string())
+    );
+    let as_display = base.cast_ref::<dyn std::fmt::Display>().unwrap();
+    assert_eq!(format!("[{:>20}]", as_display), "[  renamed at level 2]");
+    let plain_ref: &dyn Base = &plain;
+    assert!(plain_ref.cast_ref::<dyn Derived>().is_none());
+    let middle_ref: &dyn Base = &middle;
+    assert!(middle_ref.cast_ref::<dyn Derived>().is_some());
+    assert!(middle_ref.cast_ref::<dyn Final>().is_none());
+
+    // 转换之后仍然是同一个对象
+    let mut base: Box<dyn Base> = base;
+    base.cast_mut::<dyn Derived>().unwrap().rename("again");
+    assert_eq!(base.name(), "again");
+    let fin: Box<dyn Final> = base.cast_box::<dyn Final>().ok().unwrap();
+    let round_trip: Box<dyn Base> = Upcast::<dyn Base>::upcast_box(fin);
+    let concrete: Box<Bottom> = round_trip.downcast_box::<Bottom>().ok().unwrap();
+    println!("{}", concrete);
+    assert_eq!(concrete.describe(), "again at level 2");
+
+    // Rc 转换失败时也还回原来的指针
+    let rc_plain: Rc<dyn Base> = Rc::new(Plain(1));
+    let rc_plain = rc_plain.cast_rc::<dyn Derived>().err().unwrap();
+    assert_eq!(rc_plain.name(), "plain#1");
+    let rc_derived = rc_base.cast_rc::<dyn Derived>().ok().unwrap();
+    assert_eq!(rc_derived.level(), 3);
+    // rc 和 rc_derived 指向同一个对象
+    assert_eq!(Rc::strong_count(&rc), 2);
+    drop(rc_derived);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
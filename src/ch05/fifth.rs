@@ -44,3 +44,4 @@ trait Derived: Base {}
 // trait Derived where Self: Base {}
 // 这两种写法没有本质上的区别，都是给 Derived 这个 trait 加了一个约束条件
 // 即实现 Derived trait 的具体类型，也必须满足 Base trait 的约束
+// 通过 trait object 在 dyn Derived 和 dyn Base 之间转换，见 fifteenth::learn_upcasting
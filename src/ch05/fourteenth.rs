@@ -0,0 +1,441 @@
+#![allow(dead_code)]
+
+// 按名字调用的命令表
+// fourth::learn_call_func1 说明了 T::get1、T::get2 和自由函数 get3 都能转换成 fn(&T) -> usize,
+// fourth::learn_call_func 用 <Chef as Wash>::start 区分了同名的方法。
+// 既然方法只是第一个参数为 self 的普通函数, 它们就可以和自由函数、闭包一起放进同一张表里:
+// 1. 函数指针 fn(&T) -> R: 自由函数, 以及 Doc::lines、<Doc as Storage>::size 这样的方法路径
+// 2. Box<dyn Fn(&T) -> R>: 闭包, 可以捕获环境, 通过虚函数表调用
+// 调用的一方可以自己选择分派方式:
+// 1. 静态分派: call_static(word_count, &doc), 泛型参数是函数项自己的类型, 编译器可以内联
+// 2. 函数指针: Callable::call, 间接调用, 不能内联
+// 3. 动态分派: 闭包经过虚函数表调用
+// 4. 按名字: Registry::call, 先查 HashMap, 再做 2 或 3
+// benchmark 测量每一种调用的开销。
+use crate::ch04::second::Timing;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// 可以放进命令表的东西
+pub enum Callable<T: ?Sized, R> {
+    Pointer(fn(&T) -> R),
+    Boxed(Box<dyn Fn(&T) -> R>),
+}
+
+impl<T: ?Sized, R> Callable<T, R> {
+    pub fn call(&self, arg: &T) -> R {
+        match self {
+            Callable::Pointer(f) => f(arg),
+            Callable::Boxed(f) => f(arg),
+        }
+    }
+
+    /// 函数指针可以取出来, 以后直接调用, 不用再经过命令表
+    pub fn as_pointer(&self) -> Option<fn(&T) -> R> {
+        match self {
+            Callable::Pointer(f) => Some(*f),
+            Callable::Boxed(_) => None,
+        }
+    }
+
+    /// 当作闭包交给需要 impl Fn 的泛型代码
+    pub fn as_fn(&self) -> impl Fn(&T) -> R + '_ {
+        move |arg| self.call(arg)
+    }
+}
+
+/// 命令是怎样登记的
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Function,
+    Method,
+    Closure,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Kind::Function => "fn",
+            Kind::Method => "method",
+            Kind::Closure => "closure",
+        })
+    }
+}
+
+pub struct Command<T: ?Sized, R> {
+    name: String,
+    /// 源代码中的路径, 闭包是 <closure>
+    path: String,
+    kind: Kind,
+    callable: Callable<T, R>,
+}
+
+impl<T: ?Sized, R> Command<T, R> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn callable(&self) -> &Callable<T, R> {
+        &self.callable
+    }
+
+    pub fn call(&self, arg: &T) -> R {
+        self.callable.call(arg)
+    }
+}
+
+impl<T: ?Sized, R> fmt::Debug for Command<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownCommand {
+    pub name: String,
+}
+
+impl fmt::Display for UnknownCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown command {:?}", self.name)
+    }
+}
+
+impl std::error::Error for UnknownCommand {}
+
+/// 命令表, 按登记的顺序保存, 同时按名字建立索引
+pub struct Registry<T: ?Sized, R> {
+    commands: Vec<Command<T, R>>,
+    index: HashMap<String, usize>,
+}
+
+impl<T: ?Sized, R> Default for Registry<T, R> {
+    fn default() -> Self {
+        Registry {
+            commands: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T: ?Sized, R> Registry<T, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记一个命令, 同名的命令被替换, 返回旧的那个; 替换时保留原来的位置
+    fn insert(&mut self, command: Command<T, R>) -> Option<Command<T, R>> {
+        match self.index.get(&command.name) {
+            Some(&i) => Some(std::mem::replace(&mut self.commands[i], command)),
+            None => {
+                self.index.insert(command.name.clone(), self.commands.len());
+                self.commands.push(command);
+                None
+            }
+        }
+    }
+
+    /// 自由函数, 通常用 command! 登记, 它会记下函数的路径
+    pub fn add_fn(&mut self, name: &str, path: &str, f: fn(&T) -> R) -> Option<Command<T, R>> {
+        self.insert(Command {
+            name: name.to_string(),
+            path: path.to_string(),
+            kind: Kind::Function,
+            callable: Callable::Pointer(f),
+        })
+    }
+
+    /// 方法路径, Type::method 或者 <Type as Trait>::method
+    pub fn add_method(&mut self, name: &str, path: &str, f: fn(&T) -> R) -> Option<Command<T, R>> {
+        self.insert(Command {
+            name: name.to_string(),
+            path: path.to_string(),
+            kind: Kind::Method,
+            callable: Callable::Pointer(f),
+        })
+    }
+
+    pub fn add_closure<F>(&mut self, name: &str, f: F) -> Option<Command<T, R>>
+    where
+        F: Fn(&T) -> R + 'static,
+    {
+        self.insert(Command {
+            name: name.to_string(),
+            path: "<closure>".to_string(),
+            kind: Kind::Closure,
+            callable: Callable::Boxed(Box::new(f)),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command<T, R>> {
+        self.index.get(name).map(|&i| &self.commands[i])
+    }
+
+    /// 按名字调用, 动态分派
+    pub fn call(&self, name: &str, arg: &T) -> Result<R, UnknownCommand> {
+        match self.get(name) {
+            Some(command) => Ok(command.call(arg)),
+            None => Err(UnknownCommand {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// 依次调用所有的命令
+    pub fn call_all(&self, arg: &T) -> Vec<(&str, R)> {
+        self.commands
+            .iter()
+            .map(|c| (c.name.as_str(), c.call(arg)))
+            .collect()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Command<T, R>> {
+        let i = self.index.remove(name)?;
+        let command = self.commands.remove(i);
+        // 后面的命令都前移了一位
+        for j in self.index.values_mut() {
+            if *j > i {
+                *j -= 1;
+            }
+        }
+        Some(command)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command<T, R>> {
+        self.commands.iter()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.commands.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// 登记函数或方法路径, 同时记下源代码中的路径:
+/// command!(registry, "words" => fn word_count);
+/// command!(registry, "bytes" => method <Doc as Storage>::size);
+#[macro_export]
+macro_rules! command {
+    ($registry:expr, $name:expr => fn $f:expr) => {
+        $registry.add_fn($name, stringify!($f), $f)
+    };
+    ($registry:expr, $name:expr => method $f:expr) => {
+        $registry.add_method($name, stringify!($f), $f)
+    };
+}
+
+/// 静态分派: F 是函数项或闭包自己的类型, 每个 F 单态化出一份代码
+pub fn call_static<T: ?Sized, R, F: Fn(&T) -> R>(f: F, arg: &T) -> R {
+    f(arg)
+}
+
+// ---------- 例子 ----------
+
+pub struct Doc {
+    pub title: String,
+    pub text: String,
+}
+
+impl Doc {
+    pub fn new(title: &str, text: &str) -> Doc {
+        Doc {
+            title: title.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn lines(&self) -> usize {
+        self.text.lines().count()
+    }
+
+    pub fn chars(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+// 两个 trait 都有 size 方法, 与 fourth 中的 Cook 和 Wash 一样, 只能用完整的路径区分
+pub trait Storage {
+    fn size(&self) -> usize;
+}
+
+pub trait Layout {
+    fn size(&self) -> usize;
+}
+
+impl Storage for Doc {
+    /// 字节数
+    fn size(&self) -> usize {
+        self.title.len() + self.text.len()
+    }
+}
+
+impl Layout for Doc {
+    /// 标题占一行
+    fn size(&self) -> usize {
+        1 + self.lines()
+    }
+}
+
+pub fn word_count(doc: &Doc) -> usize {
+    doc.text.split_whitespace().count()
+}
+
+// ---------- 性能比较 ----------
+// 计时结果用 ch04::second 中的 Timing
+
+// 被测的函数很小, 调用本身的开销才显得出来
+fn title_len(doc: &Doc) -> usize {
+    doc.title.len()
+}
+
+fn measure(name: &'static str, calls: u32, mut f: impl FnMut() -> usize) -> (Timing, usize) {
+    let start = Instant::now();
+    let mut sum = 0usize;
+    for _ in 0..calls {
+        sum = sum.wrapping_add(f());
+    }
+    let total = start.elapsed();
+    (Timing { name, calls, total }, sum)
+}
+
+/// 用四种方式各调用 calls 次同一个函数, 同时检查它们的结果一致
+pub fn benchmark(doc: &Doc, calls: u32) -> Vec<Timing> {
+    let mut registry = Registry::new();
+    command!(registry, "title" => fn title_len);
+    registry.add_closure("title (closure)", |d: &Doc| d.title.len());
+    // black_box 让编译器看不到指针指向哪个函数, 无法把间接调用优化掉
+    let pointer = std::hint::black_box(registry.get("title").unwrap().callable());
+    let boxed = std::hint::black_box(registry.get("title (closure)").unwrap().callable());
+
+    let runs = vec![
+        measure("static", calls, || {
+            call_static(title_len, std::hint::black_box(doc))
+        }),
+        measure("fn pointer", calls, || {
+            pointer.call(std::hint::black_box(doc))
+        }),
+        measure("dyn Fn", calls, || boxed.call(std::hint::black_box(doc))),
+        measure("by name", calls, || {
+            registry
+                .call(std::hint::black_box("title"), std::hint::black_box(doc))
+                .unwrap()
+        }),
+    ];
+    let expected = runs[0].1;
+    runs.into_iter()
+        .map(|(timing, sum)| {
+            assert_eq!(sum, expected, "{} disagrees", timing.name);
+            timing
+        })
+        .collect()
+}
+
+pub fn learn_callable_registry() {
+    let doc = Doc::new(
+        "notes",
+        "fn items, method paths\nand closures\nin one table",
+    );
+
+    let mut registry: Registry<Doc, usize> = Registry::new();
+    command!(registry, "words" => fn word_count);
+    command!(registry, "lines" => method Doc::lines);
+    command!(registry, "chars" => method Doc::chars);
+    command!(registry, "bytes" => method <Doc as Storage>::size);
+    command!(registry, "rows" => method <Doc as Layout>::size);
+    // 闭包可以捕获环境, 函数指针不行
+    let needle = String::from("th");
+    registry.add_closure("count-th", move |d| d.text.matches(needle.as_str()).count());
+    let limit = 40;
+    registry.add_closure("over-limit", move |d| d.chars().saturating_sub(limit));
+
+    for command in registry.iter() {
+        println!(
+            "{:<10} {:<7} {:<24} = {}",
+            command.name(),
+            command.kind(),
+            command.path(),
+            command.call(&doc)
+        );
+    }
+    assert_eq!(registry.len(), 7);
+    assert_eq!(registry.call("words", &doc), Ok(9));
+    assert_eq!(registry.call("lines", &doc), Ok(3));
+    assert_eq!(registry.call("bytes", &doc), Ok(5 + 48));
+    assert_eq!(registry.call("rows", &doc), Ok(4));
+    assert_eq!(registry.call("count-th", &doc), Ok(2));
+    assert_eq!(registry.call("over-limit", &doc), Ok(8));
+    let bytes = registry.get("bytes").unwrap();
+    assert_eq!(bytes.path(), "<Doc as Storage>::size");
+    assert_eq!(bytes.kind(), Kind::Method);
+    assert_eq!(registry.get("count-th").unwrap().path(), "<closure>");
+
+    let err = registry.call("pages", &doc).unwrap_err();
+    println!("{}", err);
+    assert_eq!(err.to_string(), "unknown command \"pages\"");
+
+    // 同名的命令被替换, 位置不变; 不捕获环境的闭包也能转换成函数指针
+    let old = command!(registry, "words" => fn |d: &Doc| d.text.split(',').count());
+    assert_eq!(old.map(|c| c.kind()), Some(Kind::Function));
+    assert_eq!(registry.names()[0], "words");
+    assert_eq!(registry.call("words", &doc), Ok(2));
+    assert!(registry.remove("words").is_some());
+    assert_eq!(registry.names()[0], "lines");
+    assert_eq!(registry.call("rows", &doc), Ok(4));
+    let results = registry.call_all(&doc);
+    assert_eq!(results[0], ("lines", 3));
+
+    // 调用的一方选择分派方式
+    // 1. 静态分派, 函数项和闭包都可以
+    assert_eq!(call_static(word_count, &doc), 9);
+    assert_eq!(call_static(<Doc as Layout>::size, &doc), 4);
+    // 2. 取出函数指针, 之后的调用不再查表
+    let lines: fn(&Doc) -> usize = registry
+        .get("lines")
+        .unwrap()
+        .callable()
+        .as_pointer()
+        .unwrap();
+    assert_eq!(lines(&doc), 3);
+    // 闭包没有函数指针
+    assert!(registry
+        .get("count-th")
+        .unwrap()
+        .callable()
+        .as_pointer()
+        .is_none());
+    // 3. 把命令交给需要 impl Fn 的泛型代码
+    let chars = registry.get("chars").unwrap().callable();
+    assert_eq!(call_static(chars.as_fn(), &doc), 48);
+    let docs = [Doc::new("a", "one two"), Doc::new("b", "three")];
+    let counts: Vec<usize> = docs.iter().map(chars.as_fn()).collect();
+    assert_eq!(counts, [7, 5]);
+
+    // 性能比较, 不同机器上的结果不同, 只打印不检查
+    for t in benchmark(&doc, 1_000_000) {
+        println!("{}", t);
+    }
+    // 调用 0 次也是合法的参数
+    for t in benchmark(&doc, 0) {
+        assert_eq!(t.per_call(), Duration::ZERO);
+    }
+}
@@ -77,3 +77,5 @@ pub fn learn_call_func1() {
     check_type(T::get2);
     check_type(get3);
 }
+
+// 既然它们是同一种类型, 就可以放进同一张表里按名字调用, 见 fourteenth::learn_callable_registry
@@ -1,7 +1,14 @@
 pub mod eighth;
+pub mod eleventh;
+pub mod fifteenth;
 pub mod fifth;
 pub mod first;
+pub mod fourteenth;
 pub mod fourth;
+pub mod ninth;
 pub mod second;
 pub mod sixth;
+pub mod tenth;
 pub mod third;
+pub mod thirteenth;
+pub mod twelfth;
@@ -0,0 +1,779 @@
+#![allow(dead_code)]
+
+// 几何图形
+// first 中的 trait Shape 只有一个 area 方法, 实现它的也只有 Circle。
+// 这里把它扩展成一个完整的例子:
+// 1. 图形: Circle、Ellipse、Rectangle、Triangle、Polygon
+// 2. Shape 增加了周长、包围盒、点是否在图形内、平移、缩放、两个图形是否相交
+// 3. Scene 用 Vec<Box<dyn Shape>> 保存不同类型的图形, 可以统计总面积、查询某个点击中了哪些图形
+// 所有坐标都是 f64, y 轴向下还是向上都可以, 这里的计算与方向无关。
+use std::f64::consts::PI;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+/// 平面上的点, 也用来表示位移
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub const fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn distance(self, other: Point) -> f64 {
+        (self - other).length()
+    }
+
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// 叉积, 正负号表示 other 在 self 的逆时针还是顺时针方向
+    pub fn cross(self, other: Point) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Mul<f64> for Point {
+    type Output = Point;
+    fn mul(self, k: f64) -> Point {
+        Point::new(self.x * k, self.y * k)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// 与坐标轴平行的包围盒
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    /// 包含所有点的最小包围盒, 没有点时返回 None
+    pub fn of_points<I: IntoIterator<Item = Point>>(points: I) -> Option<BoundingBox> {
+        let mut iter = points.into_iter();
+        let first = iter.next()?;
+        Some(iter.fold(
+            BoundingBox {
+                min: first,
+                max: first,
+            },
+            |b, p| b.union(&BoundingBox { min: p, max: p }),
+        ))
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// 四周各扩大 margin
+    pub fn expand(&self, margin: f64) -> BoundingBox {
+        BoundingBox {
+            min: self.min - Point::new(margin, margin),
+            max: self.max + Point::new(margin, margin),
+        }
+    }
+}
+
+/// 曲线图形的轮廓用多少条线段近似
+pub const OUTLINE_SEGMENTS: usize = 64;
+
+/// 图形的基本几何描述, 绘图时需要知道图形"是什么", 而不只是它的轮廓
+#[derive(Clone, Debug, PartialEq)]
+pub enum Primitive {
+    Circle { center: Point, radius: f64 },
+    Ellipse { center: Point, rx: f64, ry: f64 },
+    Rect { origin: Point, width: f64, height: f64 },
+    Polygon(Vec<Point>),
+}
+
+pub trait Shape: fmt::Debug {
+    fn name(&self) -> &'static str;
+    fn area(&self) -> f64;
+    fn perimeter(&self) -> f64;
+    fn bounding_box(&self) -> BoundingBox;
+    /// 点在图形内部或者边界上
+    fn contains(&self, p: Point) -> bool;
+    fn translate(&mut self, offset: Point);
+    /// 以 center 为中心缩放, factor 必须大于 0
+    fn scale(&mut self, center: Point, factor: f64);
+    /// 轮廓上的顶点, 按顺序连起来是一个闭合的多边形; 曲线图形返回近似的多边形
+    fn outline(&self) -> Vec<Point>;
+
+    /// 默认用轮廓表示, 圆、椭圆和矩形有更精确的描述
+    fn primitive(&self) -> Primitive {
+        Primitive::Polygon(self.outline())
+    }
+
+    /// 两个图形是否有公共部分
+    /// 先用包围盒快速排除, 再检查一个图形的顶点是否落在另一个图形内, 最后检查边是否相交。
+    /// 对曲线图形来说, 边是近似的, 所以只有两个图形恰好相切时结果可能不准确
+    fn intersects(&self, other: &dyn Shape) -> bool {
+        if !self.bounding_box().intersects(&other.bounding_box()) {
+            return false;
+        }
+        let a = self.outline();
+        let b = other.outline();
+        if a.iter().any(|&p| other.contains(p)) || b.iter().any(|&p| self.contains(p)) {
+            return true;
+        }
+        // 边两两比较, 任意一对相交即可
+        let crossed =
+            edges(&a).any(|(p1, p2)| edges(&b).any(|(q1, q2)| segments_intersect(p1, p2, q1, q2)));
+        crossed
+    }
+}
+
+// 闭合多边形的所有边
+fn edges(points: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    let n = points.len();
+    (0..n).map(move |i| (points[i], points[(i + 1) % n]))
+}
+
+// q 在线段 p1-p2 上(已知三点共线)
+fn on_segment(p1: Point, p2: Point, q: Point) -> bool {
+    q.x >= p1.x.min(p2.x) && q.x <= p1.x.max(p2.x) && q.y >= p1.y.min(p2.y) && q.y <= p1.y.max(p2.y)
+}
+
+/// 两条线段是否相交, 包括端点接触和共线重叠
+pub fn segments_intersect(p1: Point, p2: Point, q1: Point, q2: Point) -> bool {
+    let d1 = (q2 - q1).cross(p1 - q1);
+    let d2 = (q2 - q1).cross(p2 - q1);
+    let d3 = (p2 - p1).cross(q1 - p1);
+    let d4 = (p2 - p1).cross(q2 - p1);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(q1, q2, p1))
+        || (d2 == 0.0 && on_segment(q1, q2, p2))
+        || (d3 == 0.0 && on_segment(p1, p2, q1))
+        || (d4 == 0.0 && on_segment(p1, p2, q2))
+}
+
+fn scale_point(p: Point, center: Point, factor: f64) -> Point {
+    assert!(
+        factor > 0.0,
+        "scale factor must be positive, got {}",
+        factor
+    );
+    center + (p - center) * factor
+}
+
+// 椭圆(圆是它的特例)轮廓上均匀分布的点
+fn ellipse_outline(center: Point, rx: f64, ry: f64) -> Vec<Point> {
+    (0..OUTLINE_SEGMENTS)
+        .map(|i| {
+            let t = 2.0 * PI * i as f64 / OUTLINE_SEGMENTS as f64;
+            center + Point::new(rx * t.cos(), ry * t.sin())
+        })
+        .collect()
+}
+
+// ---------- 圆 ----------
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+// 半径必须是正的有限数, 0、负数和 NaN 都会让面积、包含判断得到没有意义的结果
+fn valid_radius(r: f64) -> bool {
+    r.is_finite() && r > 0.0
+}
+
+impl Circle {
+    /// 半径必须是正数
+    pub fn new(x: f64, y: f64, radius: f64) -> Option<Circle> {
+        if !valid_radius(radius) {
+            return None;
+        }
+        Some(Circle {
+            center: Point::new(x, y),
+            radius,
+        })
+    }
+}
+
+impl Shape for Circle {
+    fn name(&self) -> &'static str {
+        "circle"
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * PI * self.radius
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let r = Point::new(self.radius, self.radius);
+        BoundingBox {
+            min: self.center - r,
+            max: self.center + r,
+        }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        self.center.distance(p) <= self.radius
+    }
+
+    fn translate(&mut self, offset: Point) {
+        self.center = self.center + offset;
+    }
+
+    fn scale(&mut self, center: Point, factor: f64) {
+        self.center = scale_point(self.center, center, factor);
+        self.radius *= factor;
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        ellipse_outline(self.center, self.radius, self.radius)
+    }
+
+    fn primitive(&self) -> Primitive {
+        Primitive::Circle {
+            center: self.center,
+            radius: self.radius,
+        }
+    }
+}
+
+// ---------- 椭圆 ----------
+
+/// 轴与坐标轴平行的椭圆
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ellipse {
+    pub center: Point,
+    pub rx: f64,
+    pub ry: f64,
+}
+
+impl Ellipse {
+    /// 两个半轴都必须是正数, contains 要除以它们
+    pub fn new(x: f64, y: f64, rx: f64, ry: f64) -> Option<Ellipse> {
+        if !valid_radius(rx) || !valid_radius(ry) {
+            return None;
+        }
+        Some(Ellipse {
+            center: Point::new(x, y),
+            rx,
+            ry,
+        })
+    }
+}
+
+impl Shape for Ellipse {
+    fn name(&self) -> &'static str {
+        "ellipse"
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.rx * self.ry
+    }
+
+    // 椭圆的周长没有初等函数的表达式, 这里用 Ramanujan 的第二个近似公式, 误差极小
+    fn perimeter(&self) -> f64 {
+        let (a, b) = (self.rx, self.ry);
+        let h = ((a - b) / (a + b)).powi(2);
+        PI * (a + b) * (1.0 + 3.0 * h / (10.0 + (4.0 - 3.0 * h).sqrt()))
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let r = Point::new(self.rx, self.ry);
+        BoundingBox {
+            min: self.center - r,
+            max: self.center + r,
+        }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        let d = p - self.center;
+        (d.x / self.rx).powi(2) + (d.y / self.ry).powi(2) <= 1.0
+    }
+
+    fn translate(&mut self, offset: Point) {
+        self.center = self.center + offset;
+    }
+
+    fn scale(&mut self, center: Point, factor: f64) {
+        self.center = scale_point(self.center, center, factor);
+        self.rx *= factor;
+        self.ry *= factor;
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        ellipse_outline(self.center, self.rx, self.ry)
+    }
+
+    fn primitive(&self) -> Primitive {
+        Primitive::Ellipse {
+            center: self.center,
+            rx: self.rx,
+            ry: self.ry,
+        }
+    }
+}
+
+// ---------- 矩形 ----------
+
+/// 边与坐标轴平行的矩形
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rectangle {
+    /// x 和 y 都最小的那个角
+    pub origin: Point,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rectangle {
+    /// 宽和高必须是有限的非负数, 0 表示退化成线段或点的矩形
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Option<Rectangle> {
+        let valid = |v: f64| v.is_finite() && v >= 0.0;
+        if !valid(width) || !valid(height) {
+            return None;
+        }
+        Some(Rectangle {
+            origin: Point::new(x, y),
+            width,
+            height,
+        })
+    }
+}
+
+impl Shape for Rectangle {
+    fn name(&self) -> &'static str {
+        "rectangle"
+    }
+
+    fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * (self.width + self.height)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox {
+            min: self.origin,
+            max: self.origin + Point::new(self.width, self.height),
+        }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        self.bounding_box().contains(p)
+    }
+
+    fn translate(&mut self, offset: Point) {
+        self.origin = self.origin + offset;
+    }
+
+    fn scale(&mut self, center: Point, factor: f64) {
+        self.origin = scale_point(self.origin, center, factor);
+        self.width *= factor;
+        self.height *= factor;
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        let Point { x, y } = self.origin;
+        vec![
+            self.origin,
+            Point::new(x + self.width, y),
+            Point::new(x + self.width, y + self.height),
+            Point::new(x, y + self.height),
+        ]
+    }
+
+    fn primitive(&self) -> Primitive {
+        Primitive::Rect {
+            origin: self.origin,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+// ---------- 三角形 ----------
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triangle {
+    pub vertices: [Point; 3],
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Triangle {
+        Triangle {
+            vertices: [a, b, c],
+        }
+    }
+}
+
+impl Shape for Triangle {
+    fn name(&self) -> &'static str {
+        "triangle"
+    }
+
+    fn area(&self) -> f64 {
+        let [a, b, c] = self.vertices;
+        (b - a).cross(c - a).abs() / 2.0
+    }
+
+    fn perimeter(&self) -> f64 {
+        edges(&self.vertices).map(|(p, q)| p.distance(q)).sum()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::of_points(self.vertices.iter().copied()).unwrap()
+    }
+
+    // 点在三条边的同一侧(或者边上)
+    fn contains(&self, p: Point) -> bool {
+        let [a, b, c] = self.vertices;
+        let d1 = (b - a).cross(p - a);
+        let d2 = (c - b).cross(p - b);
+        let d3 = (a - c).cross(p - c);
+        let has_neg = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+        let has_pos = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+        !(has_neg && has_pos)
+    }
+
+    fn translate(&mut self, offset: Point) {
+        for v in self.vertices.iter_mut() {
+            *v = *v + offset;
+        }
+    }
+
+    fn scale(&mut self, center: Point, factor: f64) {
+        for v in self.vertices.iter_mut() {
+            *v = scale_point(*v, center, factor);
+        }
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        self.vertices.to_vec()
+    }
+}
+
+// ---------- 多边形 ----------
+
+/// 简单多边形(边不自交), 顶点按顺时针或逆时针排列都可以
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub vertices: Vec<Point>,
+}
+
+impl Polygon {
+    /// 至少需要三个顶点
+    pub fn new(vertices: Vec<Point>) -> Option<Polygon> {
+        if vertices.len() < 3 {
+            return None;
+        }
+        Some(Polygon { vertices })
+    }
+
+    /// 正 n 边形
+    pub fn regular(center: Point, radius: f64, n: usize) -> Option<Polygon> {
+        Polygon::new(
+            (0..n)
+                .map(|i| {
+                    let t = 2.0 * PI * i as f64 / n as f64 - PI / 2.0;
+                    center + Point::new(radius * t.cos(), radius * t.sin())
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Shape for Polygon {
+    fn name(&self) -> &'static str {
+        "polygon"
+    }
+
+    // 鞋带公式
+    fn area(&self) -> f64 {
+        edges(&self.vertices)
+            .map(|(p, q)| p.cross(q))
+            .sum::<f64>()
+            .abs()
+            / 2.0
+    }
+
+    fn perimeter(&self) -> f64 {
+        edges(&self.vertices).map(|(p, q)| p.distance(q)).sum()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::of_points(self.vertices.iter().copied()).unwrap()
+    }
+
+    // 射线法: 从 p 向右发出一条射线, 与边相交奇数次则在内部; 边上的点单独判断
+    fn contains(&self, p: Point) -> bool {
+        let mut inside = false;
+        for (a, b) in edges(&self.vertices) {
+            if (b - a).cross(p - a) == 0.0 && on_segment(a, b, p) {
+                return true;
+            }
+            if (a.y > p.y) != (b.y > p.y) {
+                let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+                if p.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    fn translate(&mut self, offset: Point) {
+        for v in self.vertices.iter_mut() {
+            *v = *v + offset;
+        }
+    }
+
+    fn scale(&mut self, center: Point, factor: f64) {
+        for v in self.vertices.iter_mut() {
+            *v = scale_point(*v, center, factor);
+        }
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        self.vertices.clone()
+    }
+}
+
+// ---------- 场景 ----------
+
+/// 一组不同类型的图形, 后加入的图形在上层
+#[derive(Debug, Default)]
+pub struct Scene {
+    shapes: Vec<Box<dyn Shape>>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn push<S: Shape + 'static>(&mut self, shape: S) -> &mut Scene {
+        self.shapes.push(Box::new(shape));
+        self
+    }
+
+    pub fn shapes(&self) -> &[Box<dyn Shape>] {
+        &self.shapes
+    }
+
+    pub fn into_shapes(self) -> Vec<Box<dyn Shape>> {
+        self.shapes
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// 所有图形面积之和, 重叠的部分会重复计算
+    pub fn total_area(&self) -> f64 {
+        self.shapes.iter().map(|s| s.area()).sum()
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.shapes
+            .iter()
+            .map(|s| s.bounding_box())
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// 点击测试: 返回包含 p 的图形的下标, 最上层的在前
+    pub fn hit_test(&self, p: Point) -> Vec<usize> {
+        (0..self.shapes.len())
+            .rev()
+            .filter(|&i| self.shapes[i].contains(p))
+            .collect()
+    }
+
+    /// 所有相交的图形对
+    pub fn intersecting_pairs(&self) -> Vec<(usize, usize)> {
+        let n = self.shapes.len();
+        (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .filter(|&(i, j)| self.shapes[i].intersects(&*self.shapes[j]))
+            .collect()
+    }
+
+    pub fn translate_all(&mut self, offset: Point) {
+        for s in self.shapes.iter_mut() {
+            s.translate(offset);
+        }
+    }
+
+    pub fn scale_all(&mut self, center: Point, factor: f64) {
+        for s in self.shapes.iter_mut() {
+            s.scale(center, factor);
+        }
+    }
+}
+
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9 * a.abs().max(1.0)
+}
+
+pub fn learn_geometry() {
+    let mut scene = Scene::new();
+    scene
+        .push(Rectangle::new(0.0, 0.0, 4.0, 3.0).unwrap())
+        .push(Circle::new(5.0, 1.5, 1.5).unwrap())
+        .push(Triangle::new(
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(0.0, 3.0),
+        ))
+        .push(Ellipse::new(10.0, 10.0, 2.0, 1.0).unwrap())
+        .push(
+            Polygon::new(vec![
+                Point::new(7.0, 0.0),
+                Point::new(9.0, 0.0),
+                Point::new(9.0, 2.0),
+                Point::new(8.0, 1.0),
+                Point::new(7.0, 2.0),
+            ])
+            .unwrap(),
+        );
+
+    for s in scene.shapes() {
+        println!(
+            "{:<9} area {:>8.3} perimeter {:>8.3} {:?}",
+            s.name(),
+            s.area(),
+            s.perimeter(),
+            s.bounding_box()
+        );
+    }
+    let [rect, circle, triangle, ellipse, polygon] = [0, 1, 2, 3, 4].map(|i| &scene.shapes()[i]);
+    assert!(approx(rect.area(), 12.0) && approx(rect.perimeter(), 14.0));
+    assert!(approx(circle.area(), PI * 2.25));
+    assert!(approx(triangle.area(), 6.0) && approx(triangle.perimeter(), 12.0));
+    assert!(approx(ellipse.area(), 2.0 * PI));
+    // 凹多边形: 2x2 的正方形上方挖掉一个三角形
+    assert!(approx(polygon.area(), 3.0));
+    assert!(polygon.contains(Point::new(7.5, 1.2)));
+    assert!(!polygon.contains(Point::new(8.0, 1.5)));
+    // 圆的周长等于 rx = ry 时椭圆的周长
+    assert!(approx(
+        Ellipse::new(0.0, 0.0, 1.5, 1.5).unwrap().perimeter(),
+        circle.perimeter()
+    ));
+
+    // 总面积和点击测试
+    println!("total area {:.3}", scene.total_area());
+    assert!(approx(
+        scene.total_area(),
+        12.0 + 2.25 * PI + 6.0 + 2.0 * PI + 3.0
+    ));
+    assert_eq!(scene.hit_test(Point::new(1.0, 1.0)), vec![2, 0]);
+    assert_eq!(scene.hit_test(Point::new(3.0, 2.5)), vec![0]);
+    assert_eq!(scene.hit_test(Point::new(5.0, 1.0)), vec![1]);
+    assert!(scene.hit_test(Point::new(6.0, 6.0)).is_empty());
+    let bb = scene.bounding_box().unwrap();
+    assert_eq!(
+        (bb.min, bb.max),
+        (Point::new(0.0, 0.0), Point::new(12.0, 11.0))
+    );
+
+    // 相交: 矩形和圆在 x = 4 附近重叠, 三角形在矩形内部
+    let pairs = scene.intersecting_pairs();
+    println!("intersecting pairs {:?}", pairs);
+    assert_eq!(pairs, vec![(0, 1), (0, 2)]);
+    // 两个轮廓交叉成十字, 没有一个顶点落在对方内部, 也能检测出来
+    let wide = Rectangle::new(0.0, 1.0, 10.0, 1.0).unwrap();
+    let tall = Polygon::new(vec![
+        Point::new(4.0, -5.0),
+        Point::new(5.0, -5.0),
+        Point::new(5.0, 5.0),
+        Point::new(4.0, 5.0),
+    ])
+    .unwrap();
+    assert!(wide.intersects(&tall) && tall.intersects(&wide));
+    // 圆与三角形的边相切之前不相交
+    let tri = Triangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(10.0, 0.0),
+        Point::new(5.0, 5.0),
+    );
+    assert!(!Circle::new(5.0, -1.1, 1.0).unwrap().intersects(&tri));
+    assert!(Circle::new(5.0, -0.9, 1.0).unwrap().intersects(&tri));
+    // 半径或边长不合法时构造失败, 与 Polygon::new 一样返回 None
+    assert!(Circle::new(0.0, 0.0, 0.0).is_none());
+    assert!(Circle::new(0.0, 0.0, f64::NAN).is_none());
+    assert!(Ellipse::new(0.0, 0.0, 1.0, -1.0).is_none());
+    assert!(Ellipse::new(0.0, 0.0, f64::INFINITY, 1.0).is_none());
+    assert!(Rectangle::new(0.0, 0.0, -1.0, 1.0).is_none());
+    assert!(Rectangle::new(0.0, 0.0, 1.0, f64::NAN).is_none());
+    assert!(Rectangle::new(0.0, 0.0, 0.0, 1.0).is_some());
+
+    // 平移和缩放
+    scene.translate_all(Point::new(1.0, 1.0));
+    scene.scale_all(Point::new(1.0, 1.0), 2.0);
+    assert!(approx(
+        scene.total_area(),
+        4.0 * (12.0 + 2.25 * PI + 6.0 + 2.0 * PI + 3.0)
+    ));
+    assert_eq!(scene.hit_test(Point::new(3.0, 3.0)), vec![2, 0]);
+    let bb = scene.bounding_box().unwrap();
+    assert_eq!(
+        (bb.min, bb.max),
+        (Point::new(1.0, 1.0), Point::new(25.0, 23.0))
+    );
+}
@@ -0,0 +1,660 @@
+#![allow(dead_code)]
+
+// 把图形画成 SVG
+// ninth 中的图形只能打印数字, 这里把它们输出成一个独立的 .svg 文件, 用浏览器就能打开。
+// 1. Style: 描边颜色、描边宽度、填充颜色、不透明度
+// 2. Drawing: 保存图形、样式和图层, 图层小的先画, 同一图层按加入的顺序画, 后画的在上面
+// 3. viewBox 由所有图形的包围盒计算, 四周留出边距和描边的宽度, 图形不会被裁掉
+// 4. 从一个按行描述的场景文件解析出图形:
+//
+// # 注释和空行会被忽略
+// rect 0 0 4 3 fill=#ddeeff layer=-1
+// circle 5 1.5 1.5 fill=orange stroke=black stroke-width=0.1
+// ellipse 10 10 2 1
+// triangle 0 0 4 0 0 3
+// polygon 7 0 9 0 9 2 8 1 7 2
+// regular 12 4 1.5 6
+//
+// 每行是图形的名字、若干个数字, 后面可以跟 key=value 形式的样式, # 之后是注释。
+// Drawing 只通过 Shape::primitive() 知道图形是什么, 所以任何实现了 Shape 的类型都可以画出来。
+use super::ninth::{
+    BoundingBox, Circle, Ellipse, Point, Polygon, Primitive, Rectangle, Scene, Shape, Triangle,
+};
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// 图形的外观, None 表示不描边或者不填充
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub stroke: Option<String>,
+    pub stroke_width: f64,
+    pub fill: Option<String>,
+    pub opacity: f64,
+}
+
+impl Default for Style {
+    /// 黑色细线, 不填充
+    fn default() -> Style {
+        Style {
+            stroke: Some("black".to_string()),
+            stroke_width: 0.05,
+            fill: None,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Style {
+    pub fn new() -> Style {
+        Style::default()
+    }
+
+    pub fn stroke(mut self, color: &str) -> Style {
+        self.stroke = Some(color.to_string());
+        self
+    }
+
+    pub fn no_stroke(mut self) -> Style {
+        self.stroke = None;
+        self
+    }
+
+    pub fn stroke_width(mut self, width: f64) -> Style {
+        self.stroke_width = width;
+        self
+    }
+
+    pub fn fill(mut self, color: &str) -> Style {
+        self.fill = Some(color.to_string());
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f64) -> Style {
+        self.opacity = opacity;
+        self
+    }
+
+    // 描边在轮廓两侧各占一半宽度
+    fn overhang(&self) -> f64 {
+        match self.stroke {
+            Some(_) => self.stroke_width / 2.0,
+            None => 0.0,
+        }
+    }
+}
+
+// 属性值中的特殊字符必须转义, 否则 fill="a"b" 会破坏整个文件
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// 数字最多保留三位小数, 去掉末尾的 0, 避免输出 0.30000000000000004 这样的数
+fn num(x: f64) -> String {
+    let s = format!("{:.3}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+struct Item {
+    shape: Box<dyn Shape>,
+    style: Style,
+    layer: i32,
+}
+
+/// 一张 SVG 图
+pub struct Drawing {
+    items: Vec<Item>,
+    /// 图形四周留出的空白, 与图形使用相同的单位
+    pub margin: f64,
+    /// 一个单位对应多少像素, 只影响 width 和 height, 不影响 viewBox
+    pub pixels_per_unit: f64,
+    pub background: Option<String>,
+}
+
+impl Default for Drawing {
+    fn default() -> Drawing {
+        Drawing {
+            items: Vec::new(),
+            margin: 0.5,
+            pixels_per_unit: 40.0,
+            background: None,
+        }
+    }
+}
+
+impl Drawing {
+    pub fn new() -> Drawing {
+        Drawing::default()
+    }
+
+    /// 加到第 0 层
+    pub fn add<S: Shape + 'static>(&mut self, shape: S, style: Style) -> &mut Drawing {
+        self.add_boxed(Box::new(shape), style, 0)
+    }
+
+    pub fn add_on_layer<S: Shape + 'static>(
+        &mut self,
+        shape: S,
+        style: Style,
+        layer: i32,
+    ) -> &mut Drawing {
+        self.add_boxed(Box::new(shape), style, layer)
+    }
+
+    pub fn add_boxed(&mut self, shape: Box<dyn Shape>, style: Style, layer: i32) -> &mut Drawing {
+        self.items.push(Item {
+            shape,
+            style,
+            layer,
+        });
+        self
+    }
+
+    /// Scene 中的图形全部使用同一个样式, 放在第 0 层
+    pub fn from_scene(scene: Scene, style: &Style) -> Drawing {
+        let mut drawing = Drawing::new();
+        for shape in scene.into_shapes() {
+            drawing.add_boxed(shape, style.clone(), 0);
+        }
+        drawing
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = &dyn Shape> + '_ {
+        self.items.iter().map(|item| &*item.shape)
+    }
+
+    /// 画出来的区域: 所有图形的包围盒, 加上描边和边距; 没有图形时为 None
+    pub fn view_box(&self) -> Option<BoundingBox> {
+        self.items
+            .iter()
+            .map(|item| item.shape.bounding_box().expand(item.style.overhang()))
+            .reduce(|a, b| a.union(&b))
+            .map(|b| b.expand(self.margin))
+    }
+
+    // 按图层排序, sort_by_key 是稳定的, 同一图层保持加入的顺序
+    fn layers(&self) -> Vec<&Item> {
+        let mut items: Vec<&Item> = self.items.iter().collect();
+        items.sort_by_key(|item| item.layer);
+        items
+    }
+
+    pub fn render(&self) -> String {
+        let view = self.view_box().unwrap_or(BoundingBox {
+            min: Point::new(0.0, 0.0),
+            max: Point::new(1.0, 1.0),
+        });
+        let mut out = String::new();
+        // 向 String 写入不会失败
+        let _ = self.write_svg(&mut out, &view);
+        out
+    }
+
+    fn write_svg(&self, out: &mut String, view: &BoundingBox) -> fmt::Result {
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            num(view.width() * self.pixels_per_unit),
+            num(view.height() * self.pixels_per_unit),
+            num(view.min.x),
+            num(view.min.y),
+            num(view.width()),
+            num(view.height())
+        )?;
+        if let Some(bg) = &self.background {
+            writeln!(
+                out,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                num(view.min.x),
+                num(view.min.y),
+                num(view.width()),
+                num(view.height()),
+                escape(bg)
+            )?;
+        }
+        let mut current = None;
+        for item in self.layers() {
+            // 每个图层一个 <g>, 在编辑器中可以整组选中
+            if current != Some(item.layer) {
+                if current.is_some() {
+                    writeln!(out, "  </g>")?;
+                }
+                writeln!(out, r#"  <g id="layer{}">"#, item.layer)?;
+                current = Some(item.layer);
+            }
+            // <title> 在浏览器中显示为鼠标悬停时的提示
+            let primitive = item.shape.primitive();
+            write!(out, "    ")?;
+            write_element(out, &primitive)?;
+            write_style(out, &item.style)?;
+            writeln!(
+                out,
+                "><title>{}</title></{}>",
+                item.shape.name(),
+                tag(&primitive)
+            )?;
+        }
+        if current.is_some() {
+            writeln!(out, "  </g>")?;
+        }
+        writeln!(out, "</svg>")
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.render())
+    }
+
+    /// 读取场景文件, 格式错误时返回 ErrorKind::InvalidData
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Drawing> {
+        let text = fs::read_to_string(path)?;
+        text.parse()
+            .map_err(|e: ParseSceneError| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn tag(primitive: &Primitive) -> &'static str {
+    match primitive {
+        Primitive::Circle { .. } => "circle",
+        Primitive::Ellipse { .. } => "ellipse",
+        Primitive::Rect { .. } => "rect",
+        Primitive::Polygon(_) => "polygon",
+    }
+}
+
+// 元素的名字和几何属性, 不包括结尾的 >
+fn write_element(out: &mut String, primitive: &Primitive) -> fmt::Result {
+    write!(out, "<{}", tag(primitive))?;
+    match primitive {
+        Primitive::Circle { center, radius } => write!(
+            out,
+            r#" cx="{}" cy="{}" r="{}""#,
+            num(center.x),
+            num(center.y),
+            num(*radius)
+        ),
+        Primitive::Ellipse { center, rx, ry } => write!(
+            out,
+            r#" cx="{}" cy="{}" rx="{}" ry="{}""#,
+            num(center.x),
+            num(center.y),
+            num(*rx),
+            num(*ry)
+        ),
+        Primitive::Rect {
+            origin,
+            width,
+            height,
+        } => write!(
+            out,
+            r#" x="{}" y="{}" width="{}" height="{}""#,
+            num(origin.x),
+            num(origin.y),
+            num(*width),
+            num(*height)
+        ),
+        Primitive::Polygon(points) => {
+            let points: Vec<String> = points
+                .iter()
+                .map(|p| format!("{},{}", num(p.x), num(p.y)))
+                .collect();
+            write!(out, r#" points="{}""#, points.join(" "))
+        }
+    }
+}
+
+fn write_style(out: &mut String, style: &Style) -> fmt::Result {
+    match &style.fill {
+        Some(color) => write!(out, r#" fill="{}""#, escape(color))?,
+        None => write!(out, r#" fill="none""#)?,
+    }
+    match &style.stroke {
+        Some(color) => write!(
+            out,
+            r#" stroke="{}" stroke-width="{}""#,
+            escape(color),
+            num(style.stroke_width)
+        )?,
+        None => write!(out, r#" stroke="none""#)?,
+    }
+    if style.opacity < 1.0 {
+        write!(out, r#" opacity="{}""#, num(style.opacity))?;
+    }
+    Ok(())
+}
+
+// ---------- 场景文件 ----------
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    UnknownShape(String),
+    /// 图形需要的数字个数不对
+    WrongArgCount {
+        shape: &'static str,
+        expected: &'static str,
+        found: usize,
+    },
+    InvalidNumber(String),
+    UnknownOption(String),
+    InvalidOption(String),
+    /// 数字的个数对, 但构成不了图形, 比如半径为负数
+    InvalidShape(&'static str),
+}
+
+/// 场景文件中的错误, line 从 1 开始
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseSceneError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseSceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::UnknownShape(s) => write!(f, "unknown shape `{}`", s),
+            ParseErrorKind::WrongArgCount {
+                shape,
+                expected,
+                found,
+            } => write!(f, "{} expects {} numbers, found {}", shape, expected, found),
+            ParseErrorKind::InvalidNumber(s) => write!(f, "invalid number `{}`", s),
+            ParseErrorKind::UnknownOption(s) => write!(f, "unknown option `{}`", s),
+            ParseErrorKind::InvalidOption(s) => write!(f, "invalid option `{}`", s),
+            ParseErrorKind::InvalidShape(s) => write!(f, "invalid {}", s),
+        }
+    }
+}
+
+impl std::error::Error for ParseSceneError {}
+
+// 相邻的两个数字组成一个点
+fn points(args: &[f64]) -> Vec<Point> {
+    args.chunks(2).map(|c| Point::new(c[0], c[1])).collect()
+}
+
+/// 正多边形最多的边数; 场景文件是不可信的输入, 边数太大会一次分配巨大的顶点数组
+pub const MAX_REGULAR_SIDES: usize = 4096;
+
+// 一行描述的图形、样式和图层
+type Parsed = (Box<dyn Shape>, Style, i32);
+
+// 解析一行, 空行和注释返回 None
+fn parse_line(line: &str) -> Result<Option<Parsed>, ParseErrorKind> {
+    // 注释从行首或者空白之后的 # 开始, fill=#ddeeff 中的 # 是颜色的一部分
+    let end = line
+        .char_indices()
+        .find(|&(i, c)| {
+            c == '#'
+                && line[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace)
+        })
+        .map_or(line.len(), |(i, _)| i);
+    let line = line[..end].trim();
+    let mut words = line.split_whitespace();
+    let name = match words.next() {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    let mut args = Vec::new();
+    let mut style = Style::new();
+    let mut layer = 0;
+    for word in words {
+        match word.split_once('=') {
+            None => {
+                let n: f64 = word
+                    .parse()
+                    .map_err(|_| ParseErrorKind::InvalidNumber(word.to_string()))?;
+                if !n.is_finite() {
+                    return Err(ParseErrorKind::InvalidNumber(word.to_string()));
+                }
+                args.push(n);
+            }
+            Some((key, value)) => {
+                let invalid = || ParseErrorKind::InvalidOption(word.to_string());
+                // NaN、inf 和负数都能被 parse::<f64> 接受, 需要单独拒绝
+                let non_negative = || match value.parse::<f64>() {
+                    Ok(v) if v.is_finite() && v >= 0.0 => Ok(v),
+                    _ => Err(invalid()),
+                };
+                match key {
+                    "fill" if value == "none" => style.fill = None,
+                    "fill" => style.fill = Some(value.to_string()),
+                    "stroke" if value == "none" => style.stroke = None,
+                    "stroke" => style.stroke = Some(value.to_string()),
+                    "stroke-width" => style.stroke_width = non_negative()?,
+                    "opacity" => style.opacity = non_negative()?,
+                    "layer" => layer = value.parse().map_err(|_| invalid())?,
+                    _ => return Err(ParseErrorKind::UnknownOption(key.to_string())),
+                }
+            }
+        }
+    }
+
+    let count = |shape: &'static str, expected: &'static str, ok: bool| {
+        if ok {
+            Ok(())
+        } else {
+            Err(ParseErrorKind::WrongArgCount {
+                shape,
+                expected,
+                found: args.len(),
+            })
+        }
+    };
+    let shape: Box<dyn Shape> = match name {
+        "circle" => {
+            count("circle", "3", args.len() == 3)?;
+            let circle = Circle::new(args[0], args[1], args[2]);
+            Box::new(circle.ok_or(ParseErrorKind::InvalidShape("circle"))?)
+        }
+        "ellipse" => {
+            count("ellipse", "4", args.len() == 4)?;
+            let ellipse = Ellipse::new(args[0], args[1], args[2], args[3]);
+            Box::new(ellipse.ok_or(ParseErrorKind::InvalidShape("ellipse"))?)
+        }
+        "rect" => {
+            count("rect", "4", args.len() == 4)?;
+            let rect = Rectangle::new(args[0], args[1], args[2], args[3]);
+            Box::new(rect.ok_or(ParseErrorKind::InvalidShape("rect"))?)
+        }
+        "triangle" => {
+            count("triangle", "6", args.len() == 6)?;
+            let p = points(&args);
+            Box::new(Triangle::new(p[0], p[1], p[2]))
+        }
+        "polygon" => {
+            count(
+                "polygon",
+                "an even number (at least 6) of",
+                args.len() >= 6 && args.len().is_multiple_of(2),
+            )?;
+            Box::new(Polygon::new(points(&args)).unwrap())
+        }
+        // 正多边形: 中心、外接圆半径、边数
+        "regular" => {
+            count("regular", "4", args.len() == 4)?;
+            let n = args[3];
+            if args[2] <= 0.0 || n < 3.0 || n > MAX_REGULAR_SIDES as f64 || n.fract() != 0.0 {
+                return Err(ParseErrorKind::InvalidShape("regular polygon"));
+            }
+            let center = Point::new(args[0], args[1]);
+            Box::new(Polygon::regular(center, args[2], n as usize).unwrap())
+        }
+        _ => return Err(ParseErrorKind::UnknownShape(name.to_string())),
+    };
+    Ok(Some((shape, style, layer)))
+}
+
+impl FromStr for Drawing {
+    type Err = ParseSceneError;
+
+    fn from_str(text: &str) -> Result<Drawing, ParseSceneError> {
+        let mut drawing = Drawing::new();
+        for (i, line) in text.lines().enumerate() {
+            let parsed = parse_line(line).map_err(|kind| ParseSceneError { line: i + 1, kind })?;
+            if let Some((shape, style, layer)) = parsed {
+                drawing.add_boxed(shape, style, layer);
+            }
+        }
+        Ok(drawing)
+    }
+}
+
+const SCENE: &str = "\
+# ninth::learn_geometry 中的场景
+rect 0 0 4 3 fill=#ddeeff layer=-1
+circle 5 1.5 1.5 fill=orange stroke=black stroke-width=0.1 opacity=0.8
+triangle 0 0 4 0 0 3 fill=seagreen   # 画在矩形上面
+ellipse 10 10 2 1 fill=plum layer=2
+
+polygon 7 0 9 0 9 2 8 1 7 2 stroke=navy
+regular 12 4 1.5 4 fill=gold stroke=none
+";
+
+pub fn learn_svg() {
+    let drawing: Drawing = SCENE.parse().unwrap();
+    assert_eq!(drawing.len(), 6);
+    let names: Vec<&str> = drawing.shapes().map(|s| s.name()).collect();
+    assert_eq!(
+        names,
+        [
+            "rectangle",
+            "circle",
+            "triangle",
+            "ellipse",
+            "polygon",
+            "polygon"
+        ]
+    );
+
+    // 包围盒是 (0, 0) - (13.5, 11); 默认的描边向外伸出 0.025, 圆的描边伸出 0.05, 正方形没有描边, 再加上 0.5 的边距
+    let view = drawing.view_box().unwrap();
+    println!("view box {:?}", view);
+    let near = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    assert!(near(view.min.x, -0.525) && near(view.min.y, -0.55));
+    assert!(near(view.max.x, 14.0) && near(view.max.y, 11.525));
+
+    let svg = drawing.render();
+    println!("{}", svg);
+    assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+    assert!(svg.contains(r#"viewBox="-0.525 -0.55 14.525 12.075""#));
+    assert!(svg.contains(r#"<circle cx="5" cy="1.5" r="1.5" fill="orange" stroke="black" stroke-width="0.1" opacity="0.8">"#));
+    assert!(svg.contains(r##"<rect x="0" y="0" width="4" height="3" fill="#ddeeff""##));
+    assert!(svg.contains(r#"<polygon points="7,0 9,0 9,2 8,1 7,2" fill="none" stroke="navy""#));
+    // 图层: -1 层的矩形最先画, 2 层的椭圆最后画, 同一层保持文件中的顺序
+    let position = |s: &str| svg.find(s).unwrap();
+    assert!(position("<rect") < position("<circle"));
+    assert!(position("<circle") < position("<polygon"));
+    assert!(position("<polygon") < position("<ellipse"));
+    assert!(position(r#"<g id="layer-1">"#) < position(r#"<g id="layer0">"#));
+
+    // 错误带有行号
+    let err = "circle 1 2 3\nsquare 1 2".parse::<Drawing>().err().unwrap();
+    println!("{}", err);
+    assert_eq!(err.to_string(), "line 2: unknown shape `square`");
+    let err = "\n\ntriangle 0 0 1 1".parse::<Drawing>().err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "line 3: triangle expects 6 numbers, found 4"
+    );
+    let err = "circle 0 0 x".parse::<Drawing>().err().unwrap();
+    assert_eq!(err.kind, ParseErrorKind::InvalidNumber("x".to_string()));
+    let err = "circle 0 0 -1".parse::<Drawing>().err().unwrap();
+    assert_eq!(err.kind, ParseErrorKind::InvalidShape("circle"));
+    let err = "rect 0 0 -1 1".parse::<Drawing>().err().unwrap();
+    assert_eq!(err.kind, ParseErrorKind::InvalidShape("rect"));
+    let err = "regular 0 0 1 1e15".parse::<Drawing>().err().unwrap();
+    assert_eq!(err.kind, ParseErrorKind::InvalidShape("regular polygon"));
+    assert!("regular 0 0 1 4096".parse::<Drawing>().is_ok());
+    for option in &["stroke-width=NaN", "opacity=inf", "stroke-width=-1"] {
+        let err = format!("circle 0 0 1 {}", option)
+            .parse::<Drawing>()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind, ParseErrorKind::InvalidOption(option.to_string()));
+    }
+    let err = "rect 0 0 1 1 color=red".parse::<Drawing>().err().unwrap();
+    assert_eq!(err.kind, ParseErrorKind::UnknownOption("color".to_string()));
+    // 属性值会被转义
+    let svg = "circle 0 0 1 fill=\"><script>"
+        .parse::<Drawing>()
+        .unwrap()
+        .render();
+    assert!(svg.contains(r#"fill="&quot;&gt;&lt;script&gt;""#));
+
+    // 用代码构造, 或者直接画 ninth 中的 Scene
+    let mut scene = Scene::new();
+    scene
+        .push(Circle::new(0.0, 0.0, 1.0).unwrap())
+        .push(Rectangle::new(-1.0, -1.0, 2.0, 2.0).unwrap());
+    let mut drawing = Drawing::from_scene(scene, &Style::new().stroke("red"));
+    drawing
+        .add(
+            Ellipse::new(0.0, 0.0, 2.0, 0.5).unwrap(),
+            Style::new().no_stroke().fill("blue").opacity(0.3),
+        )
+        .add_on_layer(
+            Polygon::regular(Point::new(0.0, 0.0), 3.0, 5).unwrap(),
+            Style::new().fill("#eee"),
+            -1,
+        );
+    drawing.background = Some("white".to_string());
+    let svg = drawing.render();
+    assert_eq!(svg.matches("<g ").count(), 2);
+    assert!(svg.contains(r#"<rect x="-1" y="-1" width="2" height="2" fill="none" stroke="red""#));
+
+    // 场景文件也能从文件读取, 结果写成 SVG 文件。
+    // 临时目录是大家共用的, 文件名带上进程号, 同时运行的几个程序不会互相覆盖, 用完就删掉
+    let dir = std::env::temp_dir();
+    let stem = format!("dive-into-rust-shapes-{}", std::process::id());
+    let svg_path = dir.join(format!("{}.svg", stem));
+    let scene_path = dir.join(format!("{}.scene", stem));
+    let written = fs::write(&scene_path, SCENE)
+        .and_then(|_| Drawing::load(&scene_path))
+        .and_then(|d| d.save(&svg_path))
+        .and_then(|_| fs::read_to_string(&svg_path));
+    match written {
+        Ok(text) => {
+            println!("wrote {} ({} bytes)", svg_path.display(), text.len());
+            assert_eq!(text, SCENE.parse::<Drawing>().unwrap().render());
+        }
+        Err(e) => println!("cannot write {}: {}", svg_path.display(), e),
+    }
+    // 文件内容有错时, 错误类型是 InvalidData
+    if fs::write(&scene_path, "hexagon 1 2 3").is_ok() {
+        let e = Drawing::load(&scene_path).err().unwrap();
+        assert!(matches!(e.kind(), io::ErrorKind::InvalidData));
+        println!("{}", e);
+    }
+    let _ = fs::remove_file(&scene_path);
+    let _ = fs::remove_file(&svg_path);
+}
@@ -0,0 +1,807 @@
+#![allow(dead_code)]
+
+// 树形和表格输出
+// eighth::learn_display_debug 为 T 手写的 Display 不理会格式说明符, {:>20} 和 {} 的输出完全一样,
+// 而标准库的类型都遵守 Formatter 中的标志: format!("{:>8.2}", 1.23456) 是 "    1.23"。
+// 这里先把嵌套的结构体、枚举和集合转换成 Tree, 再画成缩进的树或者对齐的表格, 并且遵守全部的标志:
+// 1. 宽度、填充、对齐: 树作为一整块对齐, 块内的缩进结构不变; 表格中是每一列的最小宽度和单元格的对齐
+// 2. 精度: 浮点数的小数位数, 字符串最多保留的字符数; 整数忽略精度, 与标准库相同
+// 3. +: 数字总是带符号
+// 4. #: 用 ASCII 字符画线, 代替默认的制表符
+// 5. 0: 表格中的数字用 0 补齐到列宽, 符号在最前面; 树和字符串一样忽略它
+// 自定义类型的 Display 把 Formatter 原样交给 fmt::Display::fmt(&tree(self), f), 标志就都传递下去了;
+// 如果先 format! 出一个字符串再 write!, 这些标志就全部丢掉了。
+use super::twelfth::StrExt;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Alignment, Write};
+
+/// 树的叶子
+#[derive(Clone, Debug, PartialEq)]
+pub enum Scalar {
+    Unit,
+    Bool(bool),
+    Int(i128),
+    UInt(u128),
+    Float(f64),
+    Char(char),
+    Str(String),
+}
+
+macro_rules! impl_from_scalar {
+    ($variant:ident: $($t:ty)*) => {
+        $(
+            impl From<$t> for Scalar {
+                fn from(v: $t) -> Scalar {
+                    Scalar::$variant(v.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_scalar!(Int: i8 i16 i32 i64 i128);
+impl_from_scalar!(UInt: u8 u16 u32 u64 u128);
+impl_from_scalar!(Float: f32 f64);
+impl_from_scalar!(Bool: bool);
+impl_from_scalar!(Char: char);
+impl_from_scalar!(Str: &str String);
+
+impl From<isize> for Scalar {
+    fn from(v: isize) -> Scalar {
+        Scalar::Int(v as i128)
+    }
+}
+
+impl From<usize> for Scalar {
+    fn from(v: usize) -> Scalar {
+        Scalar::UInt(v as u128)
+    }
+}
+
+impl From<()> for Scalar {
+    fn from(_: ()) -> Scalar {
+        Scalar::Unit
+    }
+}
+
+/// 对叶子起作用的标志
+#[derive(Clone, Copy, Debug, Default)]
+struct Spec {
+    precision: Option<usize>,
+    plus: bool,
+    /// 树中和嵌套的值里, 字符串和字符加引号, 以便区分 "1" 和 1
+    quote: bool,
+}
+
+impl Spec {
+    fn of(f: &fmt::Formatter, quote: bool) -> Spec {
+        Spec {
+            precision: f.precision(),
+            plus: f.sign_plus(),
+            quote,
+        }
+    }
+}
+
+impl Scalar {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Scalar::Int(_) | Scalar::UInt(_) | Scalar::Float(_))
+    }
+
+    fn render(&self, spec: Spec) -> String {
+        match *self {
+            Scalar::Unit => "()".to_string(),
+            Scalar::Bool(b) => b.to_string(),
+            Scalar::Int(n) if spec.plus => format!("{:+}", n),
+            Scalar::Int(n) => n.to_string(),
+            Scalar::UInt(n) if spec.plus => format!("{:+}", n),
+            Scalar::UInt(n) => n.to_string(),
+            Scalar::Float(x) => match (spec.precision, spec.plus) {
+                (Some(p), true) => format!("{:+.*}", p, x),
+                (Some(p), false) => format!("{:.*}", p, x),
+                (None, true) => format!("{:+}", x),
+                (None, false) => x.to_string(),
+            },
+            Scalar::Char(c) if spec.quote => format!("{:?}", c),
+            Scalar::Char(c) => c.to_string(),
+            Scalar::Str(ref s) => {
+                let s = match spec.precision {
+                    Some(p) => s.truncate_chars(p),
+                    None => s,
+                };
+                if spec.quote {
+                    format!("{:?}", s)
+                } else {
+                    s.to_string()
+                }
+            }
+        }
+    }
+}
+
+/// 单独输出一个叶子时, 把 Formatter 交给内部的值, 行为与标准库完全相同
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scalar::Unit => f.pad("()"),
+            Scalar::Bool(b) => fmt::Display::fmt(b, f),
+            Scalar::Int(n) => fmt::Display::fmt(n, f),
+            Scalar::UInt(n) => fmt::Display::fmt(n, f),
+            Scalar::Float(x) => fmt::Display::fmt(x, f),
+            Scalar::Char(c) => fmt::Display::fmt(c, f),
+            Scalar::Str(s) => fmt::Display::fmt(s.as_str(), f),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tree {
+    Leaf(Scalar),
+    /// 结构体和结构体形式的枚举成员
+    Struct {
+        name: String,
+        fields: Vec<(String, Tree)>,
+    },
+    /// 元组结构体、元组形式的枚举成员和元组; 没有成员时就是单元结构体或者单元枚举成员
+    Tuple {
+        name: String,
+        items: Vec<Tree>,
+    },
+    List(Vec<Tree>),
+    Map(Vec<(String, Tree)>),
+}
+
+/// 画树用的线
+struct Guides {
+    branch: &'static str,
+    last: &'static str,
+    pipe: &'static str,
+    blank: &'static str,
+}
+
+const UNICODE: Guides = Guides {
+    branch: "├── ",
+    last: "└── ",
+    pipe: "│   ",
+    blank: "    ",
+};
+
+const ASCII: Guides = Guides {
+    branch: "|-- ",
+    last: "`-- ",
+    pipe: "|   ",
+    blank: "    ",
+};
+
+impl Tree {
+    pub fn leaf<T: Into<Scalar>>(value: T) -> Tree {
+        Tree::Leaf(value.into())
+    }
+
+    /// 结构体, 用 field 添加成员
+    pub fn record(name: &str) -> Tree {
+        Tree::Struct {
+            name: name.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// 元组结构体或者枚举成员, 用 item 添加成员
+    pub fn tuple(name: &str) -> Tree {
+        Tree::Tuple {
+            name: name.to_string(),
+            items: Vec::new(),
+        }
+    }
+
+    /// 为 Struct 或 Map 添加一个成员, 其它的种类 panic
+    pub fn field<T: ToTree + ?Sized>(mut self, key: &str, value: &T) -> Tree {
+        match &mut self {
+            Tree::Struct { fields, .. } | Tree::Map(fields) => {
+                fields.push((key.to_string(), value.to_tree()))
+            }
+            other => panic!("field {:?} added to {}", key, other.head(Spec::default())),
+        }
+        self
+    }
+
+    /// 为 Tuple 或 List 添加一个成员, 其它的种类 panic
+    pub fn item<T: ToTree + ?Sized>(mut self, value: &T) -> Tree {
+        match &mut self {
+            Tree::Tuple { items, .. } | Tree::List(items) => items.push(value.to_tree()),
+            other => panic!("item added to {}", other.head(Spec::default())),
+        }
+        self
+    }
+
+    /// 子节点, 带着名字或键
+    fn children(&self) -> Vec<(Option<&str>, &Tree)> {
+        match self {
+            Tree::Leaf(_) => Vec::new(),
+            Tree::Struct { fields, .. } | Tree::Map(fields) => {
+                fields.iter().map(|(k, v)| (Some(k.as_str()), v)).collect()
+            }
+            Tree::Tuple { items, .. } | Tree::List(items) => {
+                items.iter().map(|v| (None, v)).collect()
+            }
+        }
+    }
+
+    /// 节点自己那一行: 叶子是值, 结构体是名字, 集合是元素个数
+    fn head(&self, spec: Spec) -> String {
+        match self {
+            Tree::Leaf(s) => s.render(spec),
+            Tree::Struct { name, .. } => name.clone(),
+            Tree::Tuple { name, items } if name.is_empty() => format!("({})", items.len()),
+            Tree::Tuple { name, .. } => name.clone(),
+            Tree::List(items) => format!("[{}]", items.len()),
+            Tree::Map(entries) => format!("{{{}}}", entries.len()),
+        }
+    }
+
+    /// 单行的形式, 与 {:?} 相似, 用在表格的单元格中
+    fn compact(&self, spec: Spec) -> String {
+        let join = |parts: Vec<String>| parts.join(", ");
+        let pairs = |entries: &[(String, Tree)]| {
+            join(
+                entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v.compact(spec)))
+                    .collect(),
+            )
+        };
+        let items = |items: &[Tree]| join(items.iter().map(|v| v.compact(spec)).collect());
+        match self {
+            Tree::Leaf(s) => s.render(spec),
+            Tree::Struct { name, fields } if fields.is_empty() => name.clone(),
+            Tree::Struct { name, fields } => format!("{} {{ {} }}", name, pairs(fields)),
+            Tree::Tuple { name, items: v } if v.is_empty() && !name.is_empty() => name.clone(),
+            Tree::Tuple { name, items: v } => format!("{}({})", name, items(v)),
+            Tree::List(v) => format!("[{}]", items(v)),
+            Tree::Map(entries) => format!("{{{}}}", pairs(entries)),
+        }
+    }
+
+    fn lines(&self, spec: Spec, guides: &Guides) -> Vec<String> {
+        let mut out = vec![self.head(spec)];
+        self.draw_children(spec, guides, "", &mut out);
+        out
+    }
+
+    fn draw_children(&self, spec: Spec, guides: &Guides, prefix: &str, out: &mut Vec<String>) {
+        let children = self.children();
+        let n = children.len();
+        for (i, (key, child)) in children.into_iter().enumerate() {
+            let last = i + 1 == n;
+            let guide = if last { guides.last } else { guides.branch };
+            let head = child.head(spec);
+            out.push(match key {
+                Some(k) => format!("{}{}{}: {}", prefix, guide, k, head),
+                None => format!("{}{}{}", prefix, guide, head),
+            });
+            let next = format!(
+                "{}{}",
+                prefix,
+                if last { guides.blank } else { guides.pipe }
+            );
+            child.draw_children(spec, guides, &next, out);
+        }
+    }
+}
+
+/// 能转换成 Tree 的类型
+pub trait ToTree {
+    fn to_tree(&self) -> Tree;
+}
+
+macro_rules! impl_to_tree_leaf {
+    ($($t:ty)*) => {
+        $(
+            impl ToTree for $t {
+                fn to_tree(&self) -> Tree {
+                    Tree::leaf(*self)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_tree_leaf!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize f32 f64 bool char);
+impl_to_tree_leaf!(());
+
+impl ToTree for str {
+    fn to_tree(&self) -> Tree {
+        Tree::leaf(self)
+    }
+}
+
+impl ToTree for String {
+    fn to_tree(&self) -> Tree {
+        Tree::leaf(self.as_str())
+    }
+}
+
+impl ToTree for Tree {
+    fn to_tree(&self) -> Tree {
+        self.clone()
+    }
+}
+
+impl<T: ToTree + ?Sized> ToTree for &T {
+    fn to_tree(&self) -> Tree {
+        (**self).to_tree()
+    }
+}
+
+impl<T: ToTree + ?Sized> ToTree for Box<T> {
+    fn to_tree(&self) -> Tree {
+        (**self).to_tree()
+    }
+}
+
+impl<T: ToTree> ToTree for Option<T> {
+    fn to_tree(&self) -> Tree {
+        match self {
+            Some(v) => Tree::tuple("Some").item(v),
+            None => Tree::tuple("None"),
+        }
+    }
+}
+
+impl<T: ToTree> ToTree for [T] {
+    fn to_tree(&self) -> Tree {
+        Tree::List(self.iter().map(ToTree::to_tree).collect())
+    }
+}
+
+impl<T: ToTree, const N: usize> ToTree for [T; N] {
+    fn to_tree(&self) -> Tree {
+        self[..].to_tree()
+    }
+}
+
+impl<T: ToTree> ToTree for Vec<T> {
+    fn to_tree(&self) -> Tree {
+        self[..].to_tree()
+    }
+}
+
+impl<K: fmt::Display, V: ToTree> ToTree for BTreeMap<K, V> {
+    fn to_tree(&self) -> Tree {
+        Tree::Map(
+            self.iter()
+                .map(|(k, v)| (k.to_string(), v.to_tree()))
+                .collect(),
+        )
+    }
+}
+
+/// HashMap 的遍历顺序不固定, 按键排序之后输出才稳定
+impl<K: fmt::Display, V: ToTree, S> ToTree for HashMap<K, V, S> {
+    fn to_tree(&self) -> Tree {
+        let mut entries: Vec<(String, Tree)> = self
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_tree()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Tree::Map(entries)
+    }
+}
+
+impl<A: ToTree, B: ToTree> ToTree for (A, B) {
+    fn to_tree(&self) -> Tree {
+        Tree::tuple("").item(&self.0).item(&self.1)
+    }
+}
+
+impl<A: ToTree, B: ToTree, C: ToTree> ToTree for (A, B, C) {
+    fn to_tree(&self) -> Tree {
+        Tree::tuple("").item(&self.0).item(&self.1).item(&self.2)
+    }
+}
+
+fn write_fill(f: &mut fmt::Formatter, fill: char, n: usize) -> fmt::Result {
+    for _ in 0..n {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+/// 把每一行补齐到最宽的那一行, 再整体按宽度对齐, 块内的缩进结构不变
+fn write_block(f: &mut fmt::Formatter, lines: &[String]) -> fmt::Result {
+    let block = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let pad = f.width().map_or(0, |w| w.saturating_sub(block));
+    let (before, after) = match f.align() {
+        Some(Alignment::Right) => (pad, 0),
+        Some(Alignment::Center) => (pad / 2, pad - pad / 2),
+        // 与字符串相同, 默认左对齐
+        _ => (0, pad),
+    };
+    let fill = f.fill();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            f.write_char('\n')?;
+        }
+        // 没有指定宽度时不输出行尾的填充
+        let tail = if f.width().is_some() {
+            after + block - line.chars().count()
+        } else {
+            0
+        };
+        write_fill(f, fill, before)?;
+        f.write_str(line)?;
+        write_fill(f, fill, tail)?;
+    }
+    Ok(())
+}
+
+/// tree(&value) 的结果, 用 {} 输出时画成缩进的树
+pub struct TreeView(Tree);
+
+pub fn tree<T: ToTree + ?Sized>(value: &T) -> TreeView {
+    TreeView(value.to_tree())
+}
+
+impl fmt::Display for TreeView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let spec = Spec::of(f, true);
+        let guides = if f.alternate() { &ASCII } else { &UNICODE };
+        write_block(f, &self.0.lines(spec, guides))
+    }
+}
+
+/// 对齐的表格, 用 {} 输出
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<Tree>>>,
+}
+
+impl Table {
+    pub fn new(columns: &[&str]) -> Table {
+        Table {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// 每条记录一行: 结构体的成员和映射的键是列, 按第一次出现的顺序排列, 缺少的单元格留空;
+    /// 其它的值放在 value 列中
+    pub fn from_records<I>(records: I) -> Table
+    where
+        I: IntoIterator,
+        I::Item: ToTree,
+    {
+        let mut table = Table::default();
+        for record in records {
+            let entries = match record.to_tree() {
+                Tree::Struct { fields, .. } | Tree::Map(fields) => fields,
+                other => vec![("value".to_string(), other)],
+            };
+            let mut row = vec![None; table.columns.len()];
+            for (key, value) in entries {
+                let i = match table.columns.iter().position(|c| *c == key) {
+                    Some(i) => i,
+                    None => {
+                        table.columns.push(key);
+                        row.push(None);
+                        table.columns.len() - 1
+                    }
+                };
+                row[i] = Some(value);
+            }
+            table.rows.push(row);
+        }
+        // 后来才出现的列, 前面的行补上空的单元格
+        let width = table.columns.len();
+        for row in &mut table.rows {
+            row.resize(width, None);
+        }
+        table
+    }
+
+    /// 添加一行, 单元格多于列数时 panic
+    pub fn push_row(&mut self, cells: Vec<Tree>) {
+        assert!(
+            cells.len() <= self.columns.len(),
+            "{} cells for {} columns",
+            cells.len(),
+            self.columns.len()
+        );
+        let mut row: Vec<Option<Tree>> = cells.into_iter().map(Some).collect();
+        row.resize(self.columns.len(), None);
+        self.rows.push(row);
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// 按对齐方式用 fill 补齐到 width; zero 为 true 时在符号之后补 0, 与 {:08} 相同
+fn align_cell(text: &str, width: usize, align: Alignment, fill: char, zero: bool) -> String {
+    let pad = width.saturating_sub(text.chars().count());
+    if zero {
+        let (sign, digits) = match text.chars().next() {
+            Some(c @ '+') | Some(c @ '-') => (c.to_string(), &text[1..]),
+            _ => (String::new(), text),
+        };
+        return format!("{}{}{}", sign, "0".repeat(pad), digits);
+    }
+    let (before, after) = match align {
+        Alignment::Left => (0, pad),
+        Alignment::Right => (pad, 0),
+        Alignment::Center => (pad / 2, pad - pad / 2),
+    };
+    let fill = |n| std::iter::repeat_n(fill, n).collect::<String>();
+    format!("{}{}{}", fill(before), text, fill(after))
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.columns.is_empty() {
+            return Ok(());
+        }
+        let spec = Spec::of(f, false);
+        let nested = Spec {
+            quote: true,
+            ..spec
+        };
+        // 单元格的文字, 以及它是不是数字
+        let cells: Vec<Vec<(String, bool)>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        None => (String::new(), false),
+                        Some(Tree::Leaf(s)) => (s.render(spec), s.is_numeric()),
+                        Some(t) => (t.compact(nested), false),
+                    })
+                    .collect()
+            })
+            .collect();
+        let min = f.width().unwrap_or(0);
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|i| {
+                cells
+                    .iter()
+                    .map(|row| row[i].0.chars().count())
+                    .chain(Some(self.columns[i].chars().count()))
+                    .chain(Some(min))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        // 没有指定对齐方式时, 全是数字的列靠右, 其它的列靠左
+        let aligns: Vec<Alignment> = (0..self.columns.len())
+            .map(|i| {
+                let numeric = !cells.is_empty()
+                    && cells.iter().all(|row| row[i].0.is_empty() || row[i].1)
+                    && cells.iter().any(|row| row[i].1);
+                match f.align() {
+                    Some(a) => a,
+                    None if numeric => Alignment::Right,
+                    None => Alignment::Left,
+                }
+            })
+            .collect();
+        let (bar, rule, cross) = if f.alternate() {
+            ("|", "-", "+")
+        } else {
+            ("│", "─", "┼")
+        };
+        let fill = f.fill();
+        let zero = f.sign_aware_zero_pad();
+
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!(" {} ", align_cell(c, widths[i], aligns[i], fill, false)))
+            .collect();
+        f.write_str(header.join(bar).trim_end())?;
+        let rules: Vec<String> = widths.iter().map(|w| rule.repeat(w + 2)).collect();
+        write!(f, "\n{}", rules.join(cross))?;
+        for row in &cells {
+            let line: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, (text, numeric))| {
+                    let cell = align_cell(text, widths[i], aligns[i], fill, zero && *numeric);
+                    format!(" {} ", cell)
+                })
+                .collect();
+            write!(f, "\n{}", line.join(bar).trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+// ---------- 例子 ----------
+
+#[derive(Clone, Debug)]
+pub enum Protocol {
+    Http,
+    Https { cert: String },
+    Tcp(u16),
+}
+
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    pub name: String,
+    pub port: u16,
+    pub load: f64,
+    pub protocol: Protocol,
+    pub tags: Vec<&'static str>,
+    pub limits: BTreeMap<String, i32>,
+}
+
+impl ToTree for Protocol {
+    fn to_tree(&self) -> Tree {
+        match self {
+            Protocol::Http => Tree::tuple("Http"),
+            Protocol::Https { cert } => Tree::record("Https").field("cert", cert),
+            Protocol::Tcp(port) => Tree::tuple("Tcp").item(port),
+        }
+    }
+}
+
+impl ToTree for Endpoint {
+    fn to_tree(&self) -> Tree {
+        Tree::record("Endpoint")
+            .field("name", &self.name)
+            .field("port", &self.port)
+            .field("load", &self.load)
+            .field("protocol", &self.protocol)
+            .field("tags", &self.tags)
+            .field("limits", &self.limits)
+    }
+}
+
+/// 把 Formatter 原样交出去, {:>40.1}、{:#} 这些格式对 Endpoint 都有效
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&tree(self), f)
+    }
+}
+
+pub fn learn_pretty_print() {
+    let api = Endpoint {
+        name: "api".to_string(),
+        port: 8443,
+        load: 0.8125,
+        protocol: Protocol::Https {
+            cert: "api.pem".to_string(),
+        },
+        tags: vec!["public", "v2"],
+        limits: vec![("rps".to_string(), 500), ("burst".to_string(), -1)]
+            .into_iter()
+            .collect(),
+    };
+    println!("{}", api);
+    assert_eq!(
+        api.to_string(),
+        "Endpoint
+├── name: \"api\"
+├── port: 8443
+├── load: 0.8125
+├── protocol: Https
+│   └── cert: \"api.pem\"
+├── tags: [2]
+│   ├── \"public\"
+│   └── \"v2\"
+└── limits: {2}
+    ├── burst: -1
+    └── rps: 500"
+    );
+
+    // #: ASCII 的线; 精度和 + 作用于叶子
+    let ascii = format!("{:+#.2}", api);
+    println!("{}", ascii);
+    assert!(ascii.contains("|-- load: +0.81\n|-- protocol: Https\n|   `-- cert: \"ap\""));
+    assert!(ascii.contains("`-- limits: {2}\n    |-- burst: -1\n    `-- rps: +500"));
+
+    // 宽度、填充、对齐作用于整块, 每一行一样宽, 缩进结构不变
+    let centered = format!("{:*^30}", api);
+    println!("{}", centered);
+    let lines: Vec<&str> = centered.lines().collect();
+    assert!(lines.iter().all(|l| l.chars().count() == 30));
+    // 最宽的一行是 "│   └── cert: \"api.pem\"", 23 个字符, 左边 3 个 *, 右边 4 个 *
+    assert_eq!(lines[0], "***Endpoint*******************");
+    assert_eq!(lines[5], "***│   └── cert: \"api.pem\"****");
+    let right = format!("{:>25}", tree(&Protocol::Tcp(22)));
+    assert_eq!(
+        right,
+        "                   Tcp   \n                   └── 22"
+    );
+    // 比整块还窄的宽度不截断
+    assert_eq!(format!("{:2}", tree(&Protocol::Http)), "Http");
+
+    // 单独的叶子与标准库的类型表现一致
+    for spec_test in &[
+        (
+            format!("{:>8.2}", 1.23456),
+            format!("{:>8.2}", Scalar::from(1.23456)),
+        ),
+        (
+            format!("{:+08.1}", -2.5),
+            format!("{:+08.1}", Scalar::from(-2.5)),
+        ),
+        (
+            format!("{:_^9.3}", "abcdef"),
+            format!("{:_^9.3}", Scalar::from("abcdef")),
+        ),
+        (format!("{:<5}", true), format!("{:<5}", Scalar::from(true))),
+        (format!("{:+}", 7u8), format!("{:+}", Scalar::from(7u8))),
+    ] {
+        assert_eq!(spec_test.0, spec_test.1);
+    }
+
+    // 表格: 结构体的成员是列, 嵌套的值写成一行
+    let mut db = api.clone();
+    db.name = "db".to_string();
+    db.port = 5432;
+    db.load = -0.25;
+    db.protocol = Protocol::Tcp(5432);
+    db.tags = vec![];
+    db.limits.clear();
+    let endpoints = [api, db];
+    let table = Table::from_records(&endpoints);
+    println!("{}", table);
+    assert_eq!(
+        table.columns(),
+        ["name", "port", "load", "protocol", "tags", "limits"]
+    );
+    assert_eq!(
+        table.to_string(),
+        " name │ port │   load │ protocol                  │ tags             │ limits
+──────┼──────┼────────┼───────────────────────────┼──────────────────┼───────────────────────
+ api  │ 8443 │ 0.8125 │ Https { cert: \"api.pem\" } │ [\"public\", \"v2\"] │ {burst: -1, rps: 500}
+ db   │ 5432 │  -0.25 │ Tcp(5432)                 │ []               │ {}"
+    );
+
+    // 宽度是每一列的最小宽度, 填充和对齐作用于单元格, 0 用于数字
+    let mut small = Table::new(&["id", "score", "note"]);
+    small.push_row(vec![Tree::leaf(1), Tree::leaf(9.5), Tree::leaf("ok")]);
+    small.push_row(vec![Tree::leaf(12), Tree::leaf(-0.75)]);
+    // 精度同时截断字符串, 与 {:.1} 作用于 &str 相同
+    let text = format!("{:#.1}", small);
+    println!("{}", text);
+    assert_eq!(
+        text,
+        " id | score | note\n\
+         ----+-------+------\n  \
+         1 |   9.5 | o\n \
+         12 |  -0.8 |"
+    );
+    assert_eq!(
+        format!("{:#06.1}", small).lines().nth(3),
+        Some(" 000012 | -000.8 |")
+    );
+    assert_eq!(
+        format!("{:.^#6}", small).lines().nth(2),
+        Some(" ..1... | .9.5.. | ..ok..")
+    );
+
+    // 其它集合
+    let pairs = vec![("x", 1), ("y", 2)];
+    assert_eq!(
+        format!("{}", tree(&pairs)),
+        "[2]\n├── (2)\n│   ├── \"x\"\n│   └── 1\n└── (2)\n    ├── \"y\"\n    └── 2"
+    );
+    assert_eq!(
+        Table::from_records([1, 2, 30]).to_string(),
+        " value\n───────\n     1\n     2\n    30"
+    );
+    let maybe: Option<Option<i32>> = Some(None);
+    assert_eq!(tree(&maybe).to_string(), "Some\n└── None");
+}
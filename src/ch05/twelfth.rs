@@ -0,0 +1,330 @@
+#![allow(dead_code)]
+
+// 扩展 trait
+// third::learn_extension 中的 Double 只为 i32 实现, 想给 i64、f64 也加上 double 就得再写一遍。
+// 更好的做法是 blanket impl: 为所有满足某些约束的类型一次性实现扩展 trait
+// impl<T: Number> NumExt for T { ... }
+// 这样不仅所有的基本类型都有了这些方法, 用户自己的类型只要满足约束, 也会自动获得它们。
+// 1. NumExt: double、clamp_to、is_between、digits, 要求类型实现 Number
+// 2. StrExt: truncate_chars、indent、wrap_at, 所有 AsRef<str> 的类型, 包括 str、String、Cow<str>
+// 3. IterExt: group_by_key、counts, 所有 IntoIterator 的类型, 包括 Vec、数组、迭代器
+// 注意: 扩展 trait 必须 use 进来才能调用它的方法, 这也避免了不同库的同名方法互相冲突。
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+/// 数字需要满足的约束, 基本类型都已经实现, 用户的类型实现它之后就能使用 NumExt
+pub trait Number:
+    Copy
+    + PartialOrd
+    + fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+macro_rules! impl_number {
+    ($zero:expr, $one:expr => $($t:ty)*) => {
+        $(
+            impl Number for $t {
+                const ZERO: $t = $zero;
+                const ONE: $t = $one;
+            }
+        )*
+    };
+}
+
+impl_number!(0, 1 => i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+impl_number!(0.0, 1.0 => f32 f64);
+
+pub trait NumExt: Number {
+    /// 两倍, 整数溢出时与 self + self 的行为相同
+    fn double(self) -> Self {
+        self + self
+    }
+
+    /// 限制在 [min, max] 之内, 与标准库的 clamp 不同, 只要求 PartialOrd, 所以浮点数也能用;
+    /// min > max 时 panic
+    fn clamp_to(self, min: Self, max: Self) -> Self {
+        assert!(min <= max, "clamp_to: {:?} > {:?}", min, max);
+        if self < min {
+            min
+        } else if self > max {
+            max
+        } else {
+            self
+        }
+    }
+
+    /// 在闭区间 [low, high] 之内, 两端的顺序无所谓
+    fn is_between(self, low: Self, high: Self) -> bool {
+        let (low, high) = if low <= high {
+            (low, high)
+        } else {
+            (high, low)
+        };
+        low <= self && self <= high
+    }
+
+    /// 整数部分的十进制各位, 高位在前, 负数忽略符号; NaN 和无穷大返回空
+    fn digits(self) -> Vec<u8> {
+        let zero = Self::ZERO;
+        let one = Self::ONE;
+        // NaN 和无穷大减去自己不等于 0, 整数和普通的浮点数等于 0
+        #[allow(clippy::eq_op)]
+        let finite = self - self == zero;
+        if !finite {
+            return Vec::new();
+        }
+        let ten = (0..9).fold(one, |t, _| t + one);
+        let negative = self < zero;
+        // 浮点数去掉小数部分, 整数 % 1 总是 0
+        let mut n = self - self % one;
+        let mut digits = Vec::new();
+        loop {
+            // 负数的余数也是负的, 不先取绝对值是为了 i32::MIN 这样的数不会溢出
+            let r = n % ten;
+            let mut d = if negative { zero - r } else { r };
+            // 泛型代码不能把 T 转换成 u8, 只能一个一个数
+            let mut digit = 0;
+            while d >= one {
+                d = d - one;
+                digit += 1;
+            }
+            digits.push(digit);
+            n = (n - r) / ten;
+            if n == zero {
+                break;
+            }
+        }
+        digits.reverse();
+        digits
+    }
+}
+
+impl<T: Number> NumExt for T {}
+
+pub trait StrExt {
+    /// 最多保留前 n 个字符, 不会截断在 UTF-8 字符的中间
+    fn truncate_chars(&self, n: usize) -> &str;
+    /// 每个非空行的前面加上 prefix
+    fn indent(&self, prefix: &str) -> String;
+    /// 按单词折行, 每行最多 width 个字符; 比 width 还长的单词单独拆开
+    fn wrap_at(&self, width: usize) -> Vec<String>;
+}
+
+impl<S: AsRef<str> + ?Sized> StrExt for S {
+    fn truncate_chars(&self, n: usize) -> &str {
+        let s = self.as_ref();
+        match s.char_indices().nth(n) {
+            Some((i, _)) => &s[..i],
+            None => s,
+        }
+    }
+
+    fn indent(&self, prefix: &str) -> String {
+        let s = self.as_ref();
+        let mut out = String::with_capacity(s.len());
+        for line in s.split_inclusive('\n') {
+            if !line.trim().is_empty() {
+                out.push_str(prefix);
+            }
+            out.push_str(line);
+        }
+        out
+    }
+
+    fn wrap_at(&self, width: usize) -> Vec<String> {
+        assert!(width > 0, "wrap_at: width must be positive");
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut len = 0;
+        for word in self.as_ref().split_whitespace() {
+            let mut chars: Vec<char> = word.chars().collect();
+            // 当前行放不下这个单词, 先换行
+            if len > 0 && len + 1 + chars.len() > width {
+                lines.push(std::mem::take(&mut line));
+                len = 0;
+            }
+            // 比一整行还长的单词, 按宽度切开
+            while chars.len() > width {
+                let rest = chars.split_off(width);
+                lines.push(chars.into_iter().collect());
+                chars = rest;
+            }
+            if len > 0 {
+                line.push(' ');
+                len += 1;
+            }
+            len += chars.len();
+            line.extend(chars);
+        }
+        if len > 0 {
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+pub trait IterExt: IntoIterator + Sized {
+    /// 按 key 分组, 组内保持原来的顺序, 组按 key 排序
+    fn group_by_key<K: Ord, F: FnMut(&Self::Item) -> K>(
+        self,
+        mut key: F,
+    ) -> BTreeMap<K, Vec<Self::Item>> {
+        let mut groups: BTreeMap<K, Vec<Self::Item>> = BTreeMap::new();
+        for item in self {
+            groups.entry(key(&item)).or_default().push(item);
+        }
+        groups
+    }
+
+    /// 每个元素出现的次数
+    fn counts(self) -> BTreeMap<Self::Item, usize>
+    where
+        Self::Item: Ord,
+    {
+        let mut counts = BTreeMap::new();
+        for item in self {
+            *counts.entry(item).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl<I: IntoIterator> IterExt for I {}
+
+// ---------- 用户自己的类型 ----------
+
+/// 保留两位小数的定点数, 内部以"分"为单位
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Money(pub i64);
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Money {
+    type Output = Money;
+    fn mul(self, rhs: Money) -> Money {
+        Money(self.0 * rhs.0 / 100)
+    }
+}
+
+impl Div for Money {
+    type Output = Money;
+    fn div(self, rhs: Money) -> Money {
+        Money(self.0 * 100 / rhs.0)
+    }
+}
+
+impl Rem for Money {
+    type Output = Money;
+    fn rem(self, rhs: Money) -> Money {
+        Money(self.0 % rhs.0)
+    }
+}
+
+impl Number for Money {
+    const ZERO: Money = Money(0);
+    const ONE: Money = Money(100);
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(
+            f,
+            "{}{}.{:02}",
+            sign,
+            (self.0 / 100).abs(),
+            (self.0 % 100).abs()
+        )
+    }
+}
+
+pub fn learn_extension_traits() {
+    // 一份实现, 所有数字类型都能用
+    assert_eq!(10.double(), 20);
+    assert_eq!(1.5f64.double(), 3.0);
+    assert_eq!(200u8.clamp_to(0, 100), 100);
+    assert_eq!((-3i64).clamp_to(0, 10), 0);
+    assert_eq!(0.7f32.clamp_to(0.0, 0.5), 0.5);
+    assert!(5u32.is_between(10, 1));
+    assert!(!(-0.1f64).is_between(0.0, 1.0));
+    println!("{:?} {:?}", 9075u16.digits(), (-42i8).digits());
+    assert_eq!(9075u16.digits(), vec![9, 0, 7, 5]);
+    assert_eq!(0usize.digits(), vec![0]);
+    assert_eq!(i32::MIN.digits(), vec![2, 1, 4, 7, 4, 8, 3, 6, 4, 8]);
+    assert_eq!(u128::MAX.digits().len(), 39);
+    assert_eq!(123.9f64.digits(), vec![1, 2, 3]);
+    assert!(f64::NAN.digits().is_empty() && f32::INFINITY.digits().is_empty());
+
+    // 用户的类型实现了 Number, 自动获得全部的扩展方法
+    let price = Money(12_345);
+    println!("{} doubled {}", price, price.double());
+    assert_eq!(price.double(), Money(24_690));
+    assert_eq!(price.clamp_to(Money(0), Money(10_000)), Money(10_000));
+    assert!(price.is_between(Money(100), Money(20_000)));
+    // 整数部分 123 元
+    assert_eq!(price.digits(), vec![1, 2, 3]);
+
+    // 字符串: &str、String 都可以
+    let s = "你好, extension traits";
+    assert_eq!(s.truncate_chars(2), "你好");
+    assert_eq!(String::from("abc").truncate_chars(10), "abc");
+    let code = "fn main() {\n\n    run();\n}\n";
+    let indented = code.indent("  ");
+    println!("{}", indented);
+    assert_eq!(indented, "  fn main() {\n\n      run();\n  }\n");
+    let text = "Rust traits can be implemented for any type that meets the bounds, \
+                including supercalifragilistic ones";
+    let lines = text.wrap_at(16);
+    for line in &lines {
+        println!("|{:<16}|", line);
+    }
+    assert!(lines.iter().all(|l| l.chars().count() <= 16));
+    assert_eq!(lines[0], "Rust traits can");
+    assert_eq!(
+        lines
+            .join(" ")
+            .replace("supercalifragili stic", "supercalifragilistic"),
+        text
+    );
+
+    // 集合: Vec、数组、迭代器都可以
+    let words = vec![
+        "apple",
+        "avocado",
+        "banana",
+        "blueberry",
+        "cherry",
+        "apricot",
+    ];
+    let groups = words.clone().group_by_key(|w| w.chars().next().unwrap());
+    println!("{:?}", groups);
+    assert_eq!(groups[&'a'], ["apple", "avocado", "apricot"]);
+    assert_eq!(groups.len(), 3);
+    let by_rem = (1..=10).group_by_key(|n| n % 3);
+    assert_eq!(by_rem[&0], [3, 6, 9]);
+    let counts = "mississippi".chars().counts();
+    println!("{:?}", counts);
+    assert_eq!(counts[&'s'], 4);
+    assert_eq!(counts.get(&'p'), Some(&2));
+    assert_eq!([Money(1), Money(2), Money(1)].counts()[&Money(1)], 2);
+}
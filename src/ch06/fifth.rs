@@ -0,0 +1,801 @@
+#![allow(dead_code)]
+
+// 栈上的定长容器
+// first 中说数组的长度是编译期确定的, 需要变长的容器就用 Vec。两者之间还有一种常见的需求:
+// 元素个数会变, 但有一个编译期已知的上限, 这时可以把元素放在栈上的数组里, 不需要堆分配:
+// 1. ArrayVec<T, N>: 最多 N 个元素的 Vec, 元素连续存放, 可以 Deref 成 &[T]
+// 2. RingBuffer<T, N>: 最多 N 个元素的双端队列, 首尾相接, 两头都能 O(1) 地进出,
+//    元素可能绕回数组的开头, 分成两段, 所以用 as_slices 得到两个切片, make_contiguous 把它们拼成一个
+// 数组中只有一部分元素是初始化过的, 所以存储的类型是 [MaybeUninit<T>; N]:
+// 1. 读写元素需要 unsafe, 由 len 保证只访问初始化过的那一部分
+// 2. Drop 时只析构初始化过的元素, 没有初始化的位置不能析构
+// 容量满了时 push、insert 返回 Err(CapacityError(元素)), 把元素还给调用者, 而不是 panic;
+// 下标越界与 Vec 一样 panic。
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::ptr;
+use std::slice;
+
+/// 容器已满, 放不进去的元素原样还回来
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError<T>(pub T);
+
+impl<T> CapacityError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("insufficient capacity")
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for CapacityError<T> {}
+
+fn uninit_array<T, const N: usize>() -> [MaybeUninit<T>; N] {
+    std::array::from_fn(|_| MaybeUninit::uninit())
+}
+
+// ---------- ArrayVec ----------
+
+/// 最多 N 个元素的 Vec, 前 len 个元素是初始化过的
+pub struct ArrayVec<T, const N: usize> {
+    data: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    pub fn new() -> Self {
+        ArrayVec {
+            data: uninit_array(),
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    fn as_ptr(&self) -> *const T {
+        self.data.as_ptr() as *const T
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr() as *mut T
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // 前 len 个元素都初始化过了
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    pub fn push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
+        self.data[self.len].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // 先减小 len, 这个位置就不再属于容器, 读出来之后不会被析构两次
+        Some(unsafe { self.data[self.len].as_ptr().read() })
+    }
+
+    /// 在 index 处插入, 后面的元素向后移一位; index > len 时 panic
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), CapacityError<T>> {
+        assert!(
+            index <= self.len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            // 区间重叠, 用 copy 而不是 copy_nonoverlapping
+            ptr::copy(p, p.add(1), self.len - index);
+            p.write(value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// 删除 index 处的元素, 后面的元素向前移一位; 越界时返回 None
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            let value = p.read();
+            ptr::copy(p.add(1), p, self.len - index - 1);
+            self.len -= 1;
+            Some(value)
+        }
+    }
+
+    /// 用最后一个元素填补删除的位置, O(1), 但不保持顺序
+    pub fn swap_remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let last = self.len - 1;
+        self.as_mut_slice().swap(index, last);
+        self.pop()
+    }
+
+    /// 只保留前 len 个元素
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len {
+            return;
+        }
+        let tail = self.len - len;
+        // 先修改 len, 即使某个元素的 drop panic 了, 也不会再析构一次
+        self.len = len;
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.as_mut_ptr().add(len),
+                tail,
+            ));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// 放不下时停止, 已经放进去的元素保留, 放不下的那个元素在 Err 中返回
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), CapacityError<T>> {
+        for value in iter {
+            self.push(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        // 只析构前 len 个元素, 后面没有初始化的位置不能碰
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> Self {
+        let mut v = ArrayVec::new();
+        for x in self.iter() {
+            // 容量相同, 不会失败
+            let _ = v.push(x.clone());
+        }
+        v
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for ArrayVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for ArrayVec<T, N> {}
+
+impl<T: PartialEq, const N: usize> PartialEq<[T]> for ArrayVec<T, N> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<[T; M]> for ArrayVec<T, N> {
+    fn eq(&self, other: &[T; M]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+/// 按值遍历, 没有取走的元素在迭代器析构时析构
+pub struct IntoIter<T, const N: usize> {
+    data: [MaybeUninit<T>; N],
+    // [start, end) 是还没有取走的元素
+    start: usize,
+    end: usize,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        Some(unsafe { self.data[self.start - 1].as_ptr().read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.start;
+        (n, Some(n))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(unsafe { self.data[self.end].as_ptr().read() })
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+    fn drop(&mut self) {
+        unsafe {
+            let p = self.data.as_mut_ptr() as *mut T;
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                p.add(self.start),
+                self.end - self.start,
+            ));
+        }
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        // 元素的所有权转移给迭代器, ArrayVec 自己不能再析构它们
+        let this = ManuallyDrop::new(self);
+        IntoIter {
+            data: unsafe { ptr::read(&this.data) },
+            start: 0,
+            end: this.len,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> slice::IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// ---------- RingBuffer ----------
+
+/// 最多 N 个元素的双端队列; 第 i 个元素在 data[(head + i) % N]
+pub struct RingBuffer<T, const N: usize> {
+    data: [MaybeUninit<T>; N],
+    head: usize,
+    len: usize,
+}
+
+pub type RingIter<'a, T> = std::iter::Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
+pub type RingIterMut<'a, T> = std::iter::Chain<slice::IterMut<'a, T>, slice::IterMut<'a, T>>;
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    pub fn new() -> Self {
+        RingBuffer {
+            data: uninit_array(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// 第 i 个元素在数组中的位置, 只在 N > 0 时调用
+    fn slot(&self, i: usize) -> usize {
+        (self.head + i) % N
+    }
+
+    unsafe fn read(&self, slot: usize) -> T {
+        self.data[slot].as_ptr().read()
+    }
+
+    pub fn push_back(&mut self, value: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
+        let slot = self.slot(self.len);
+        self.data[slot].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn push_front(&mut self, value: T) -> Result<(), CapacityError<T>> {
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
+        self.head = (self.head + N - 1) % N;
+        self.data[self.head].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// 满了时挤掉最旧的元素并返回它, 用来保存"最近的 N 个"; N 为 0 时直接返回 value
+    pub fn push_overwrite(&mut self, value: T) -> Option<T> {
+        if N == 0 {
+            return Some(value);
+        }
+        let evicted = if self.is_full() {
+            self.pop_front()
+        } else {
+            None
+        };
+        // 刚刚腾出了位置
+        let _ = self.push_back(value);
+        evicted
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let slot = self.head;
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(unsafe { self.read(slot) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let slot = self.slot(self.len);
+        Some(unsafe { self.read(slot) })
+    }
+
+    /// 在第 index 个位置插入, 后面的元素向后移; index > len 时 panic
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), CapacityError<T>> {
+        assert!(
+            index <= self.len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+        if self.is_full() {
+            return Err(CapacityError(value));
+        }
+        // 元素可能跨过数组的末尾, 只能一个一个地移动
+        for i in (index..self.len).rev() {
+            let (from, to) = (self.slot(i), self.slot(i + 1));
+            unsafe {
+                let v = self.read(from);
+                self.data[to].write(v);
+            }
+        }
+        let slot = self.slot(index);
+        self.data[slot].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// 删除第 index 个元素, 后面的元素向前移; 越界时返回 None
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let value = unsafe { self.read(self.slot(index)) };
+        for i in index + 1..self.len {
+            let (from, to) = (self.slot(i), self.slot(i - 1));
+            unsafe {
+                let v = self.read(from);
+                self.data[to].write(v);
+            }
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        Some(unsafe { &*self.data[self.slot(index)].as_ptr() })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let slot = self.slot(index);
+        Some(unsafe { &mut *self.data[slot].as_mut_ptr() })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    /// 从 head 到数组末尾是第一段, 绕回数组开头的是第二段
+    fn split_lens(&self) -> (usize, usize) {
+        let first = self.len.min(N - self.head);
+        (first, self.len - first)
+    }
+
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (a, b) = self.split_lens();
+        let p = self.data.as_ptr() as *const T;
+        unsafe {
+            (
+                slice::from_raw_parts(p.add(self.head), a),
+                slice::from_raw_parts(p, b),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (a, b) = self.split_lens();
+        let p = self.data.as_mut_ptr() as *mut T;
+        // 两段不重叠
+        unsafe {
+            (
+                slice::from_raw_parts_mut(p.add(self.head), a),
+                slice::from_raw_parts_mut(p, b),
+            )
+        }
+    }
+
+    /// 旋转数组, 让元素从 data[0] 开始连续存放, 之后可以当作一个切片使用
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        // MaybeUninit 没有析构函数, 旋转只是移动字节
+        self.data.rotate_left(self.head);
+        self.head = 0;
+        self.as_mut_slices().0
+    }
+
+    pub fn iter(&self) -> RingIter<'_, T> {
+        let (a, b) = self.as_slices();
+        a.iter().chain(b.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> RingIterMut<'_, T> {
+        let (a, b) = self.as_mut_slices();
+        a.iter_mut().chain(b.iter_mut())
+    }
+
+    pub fn clear(&mut self) {
+        let (a, b) = self.split_lens();
+        let p = self.data.as_mut_ptr() as *mut T;
+        let head = self.head;
+        // 先清空, 析构时 panic 也不会重复析构
+        self.len = 0;
+        self.head = 0;
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(p.add(head), a));
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(p, b));
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for RingBuffer<T, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Index<usize> for RingBuffer<T, N> {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(v) => v,
+            None => panic!("index {} out of range for length {}", index, self.len),
+        }
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for RingBuffer<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+        match self.get_mut(index) {
+            Some(v) => v,
+            None => panic!("index {} out of range for length {}", index, len),
+        }
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for RingBuffer<T, N> {
+    fn clone(&self) -> Self {
+        let mut r = RingBuffer::new();
+        for x in self.iter() {
+            let _ = r.push_back(x.clone());
+        }
+        r
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for RingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// 元素的顺序相同就相等, 与它们在数组中的位置无关
+impl<T: PartialEq, const N: usize> PartialEq for RingBuffer<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for RingBuffer<T, N> {}
+
+/// 按值遍历, 从前往后取出元素
+pub struct RingIntoIter<T, const N: usize>(RingBuffer<T, N>);
+
+impl<T, const N: usize> Iterator for RingIntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for RingIntoIter<T, N> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for RingIntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for RingIntoIter<T, N> {}
+
+impl<T, const N: usize> IntoIterator for RingBuffer<T, N> {
+    type Item = T;
+    type IntoIter = RingIntoIter<T, N>;
+
+    fn into_iter(self) -> RingIntoIter<T, N> {
+        RingIntoIter(self)
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a RingBuffer<T, N> {
+    type Item = &'a T;
+    type IntoIter = RingIter<'a, T>;
+
+    fn into_iter(self) -> RingIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut RingBuffer<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = RingIterMut<'a, T>;
+
+    fn into_iter(self) -> RingIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+// ---------- 例子 ----------
+
+/// 析构时把计数器加一, 用来检查每个元素正好析构一次
+struct Tracked {
+    id: u32,
+    drops: std::rc::Rc<std::cell::Cell<u32>>,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+pub fn learn_array_vec() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // 数据就在结构体里, 没有堆分配
+    println!(
+        "size of ArrayVec<u8, 16>: {}, RingBuffer<u32, 8>: {}",
+        std::mem::size_of::<ArrayVec<u8, 16>>(),
+        std::mem::size_of::<RingBuffer<u32, 8>>()
+    );
+    assert_eq!(
+        std::mem::size_of::<ArrayVec<u64, 4>>(),
+        4 * 8 + std::mem::size_of::<usize>()
+    );
+
+    let mut v: ArrayVec<i32, 4> = ArrayVec::new();
+    for i in 1..=4 {
+        v.push(i * 10).unwrap();
+    }
+    // 满了, 元素原样还回来
+    let err = v.push(50).unwrap_err();
+    println!("{}: {:?}", err, err);
+    assert_eq!(err.into_inner(), 50);
+    assert!(v.is_full() && v.insert(0, 5).is_err());
+    assert_eq!(v.pop(), Some(40));
+    v.insert(1, 15).unwrap();
+    assert_eq!(v, [10, 15, 20, 30]);
+    assert_eq!(v.remove(0), Some(10));
+    assert_eq!(v.remove(9), None);
+    assert_eq!(v.swap_remove(0), Some(15));
+    assert_eq!(v, [30, 20]);
+
+    // Deref 成切片, 切片的方法都能用
+    v.sort();
+    assert_eq!(v.first(), Some(&20));
+    assert!(v.contains(&30));
+    assert_eq!(v.iter().sum::<i32>(), 50);
+    for x in &mut v {
+        *x += 1;
+    }
+    assert_eq!(&v[..], &[21, 31]);
+    let err = v.try_extend(vec![1, 2, 3]).unwrap_err();
+    assert_eq!((v.len(), err.0), (4, 3));
+    let collected: Vec<i32> = v.clone().into_iter().rev().collect();
+    assert_eq!(collected, [2, 1, 31, 21]);
+
+    // 环形缓冲区: 两头进出
+    let mut r: RingBuffer<char, 4> = RingBuffer::new();
+    r.push_back('b').unwrap();
+    r.push_back('c').unwrap();
+    r.push_front('a').unwrap();
+    assert_eq!(r.iter().collect::<String>(), "abc");
+    // 'a' 在数组的最后一个位置, 'b'、'c' 绕回了开头
+    assert_eq!(r.as_slices(), (&['a'][..], &['b', 'c'][..]));
+    r.insert(1, 'x').unwrap();
+    assert_eq!(r.push_back('d'), Err(CapacityError('d')));
+    assert_eq!(format!("{:?}", r), "['a', 'x', 'b', 'c']");
+    assert_eq!(r.remove(1), Some('x'));
+    assert_eq!((r.front(), r.back(), r[1]), (Some(&'a'), Some(&'c'), 'b'));
+    r.make_contiguous().reverse();
+    assert_eq!(r.as_slices().0, ['c', 'b', 'a']);
+    assert_eq!(r.pop_back(), Some('a'));
+    assert_eq!(r.pop_front(), Some('c'));
+
+    // 最近的 3 个值
+    let mut recent: RingBuffer<u32, 3> = RingBuffer::new();
+    let evicted: Vec<u32> = (1..=7).filter_map(|x| recent.push_overwrite(x)).collect();
+    assert_eq!(evicted, [1, 2, 3, 4]);
+    assert_eq!(recent.iter().copied().collect::<Vec<_>>(), [5, 6, 7]);
+    assert_eq!(recent.into_iter().rev().collect::<Vec<_>>(), [7, 6, 5]);
+    let mut none: RingBuffer<u32, 0> = RingBuffer::new();
+    assert_eq!(none.push_overwrite(1), Some(1));
+    assert_eq!(none.push_front(1), Err(CapacityError(1)));
+
+    // 只析构初始化过的元素, 每个正好一次
+    let drops = Rc::new(Cell::new(0));
+    let tracked = |id| Tracked {
+        id,
+        drops: drops.clone(),
+    };
+    {
+        let mut v: ArrayVec<Tracked, 8> = ArrayVec::new();
+        for id in 0..5 {
+            v.push(tracked(id)).ok().unwrap();
+        }
+        drop(v.remove(1));
+        assert_eq!(drops.get(), 1);
+        v.truncate(3);
+        assert_eq!(drops.get(), 2);
+        // 8 个位置中只有 3 个初始化过
+    }
+    assert_eq!(drops.get(), 5);
+    {
+        let mut v: ArrayVec<Tracked, 4> = ArrayVec::new();
+        for id in 0..4 {
+            v.push(tracked(id)).ok().unwrap();
+        }
+        // 放不进去的元素在 Err 中, 随 Err 一起析构
+        assert!(v.push(tracked(9)).is_err());
+        assert_eq!(drops.get(), 6);
+        let mut it = v.into_iter();
+        assert_eq!(it.next().map(|t| t.id), Some(0));
+        assert_eq!(it.next_back().map(|t| t.id), Some(3));
+        // 剩下的两个随迭代器析构
+    }
+    assert_eq!(drops.get(), 10);
+    {
+        let mut r: RingBuffer<Tracked, 3> = RingBuffer::new();
+        for id in 0..5 {
+            drop(r.push_overwrite(tracked(id)));
+        }
+        assert_eq!(drops.get(), 12);
+        drop(r.remove(0));
+        r.push_front(tracked(7)).ok().unwrap();
+        assert_eq!(r.iter().map(|t| t.id).collect::<Vec<_>>(), [7, 3, 4]);
+        assert_eq!(drops.get(), 13);
+    }
+    assert_eq!(drops.get(), 16);
+}
@@ -14,6 +14,7 @@ pub fn learn_array1() {
     println!("{:?}", xs);
     // println!("{:?}", ys);
 }
+// 元素个数可变但有上限时, 可以用栈上数组实现的 ArrayVec 和 RingBuffer, 见 fifth::learn_array_vec
 
 // 在 Rust 中，对于两个数组类型，只有元素类型和元素个数都完全相同，这两个数组
 // 才是同类型的。数组与指针之间不能隐式转换。同类型的数组之间可以互相赋值
@@ -53,6 +54,7 @@ pub fn learn_array4() {
         println!("{:?}", i);
     }
 }
+// 把多维数组包装成矩阵, 形状作为 const 泛型参数, 见 fourth::learn_matrix
 
 // 数组切片
 // 对数组取借用 borrow 操作，可以生成一个"数组切片"(Slice)。数组切片对数组没有"所有权"，
@@ -95,13 +97,12 @@ pub fn learn_array5() {
 // 函数的参数、返回值。但是，指向不定长数组的胖指针的大小是确定的，
 // &[T] 类型可以用做变量实例、函数参数、返回值。
 // &[T] 类型占用了两个指针大小的内存空间
+// 不需要 transmute, 拆开胖指针的方法见 third::slice_parts
 fn raw_slice(arr: &[i32]) {
-    unsafe {
-        let (val1, val2): (usize, usize) = std::mem::transmute(arr);
-        println!("Value in raw pointer:");
-        println!("value1: {:x}", val1);
-        println!("value2: {:x}", val2);
-    }
+    let parts = super::third::slice_parts(arr);
+    println!("Value in raw pointer:");
+    println!("value1: {:x}", parts.data as usize);
+    println!("value2: {:x}", parts.len);
 }
 // 胖指针内部的数据既包含了指向源数组的地址，又包含了该切片的长度
 // 对于 DST 类型，Rust 有如下限制:
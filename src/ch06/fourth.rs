@@ -0,0 +1,436 @@
+#![allow(dead_code)]
+
+// 用 const 泛型实现矩阵
+// first::learn_array4 中的 [[i32; 2]; 3] 只是被遍历了一遍。把它包装成 Matrix<T, R, C> 之后,
+// 行数和列数都是类型的一部分, 形状不匹配的运算在编译阶段就会报错:
+// 1. 加减法要求两个矩阵的形状相同: Matrix<T, R, C> + Matrix<T, R, C>
+// 2. 乘法要求左边的列数等于右边的行数: Matrix<T, R, K> * Matrix<T, K, C> = Matrix<T, R, C>
+// 3. 单位矩阵、迹、行列式和逆矩阵只对方阵 Matrix<T, N, N> 有定义
+// 元素类型用 ch05::twelfth::Number 约束, 整数、浮点数和用户自己的 Money 都可以;
+// 行列式和逆矩阵需要除法和比较大小来选主元, 只对浮点数实现。
+// 数据就是 [[T; C]; R], 没有堆分配, 矩阵本身也是 Copy 的。
+use crate::ch05::twelfth::Number;
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+/// R 行 C 列的矩阵
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch06::fourth::Matrix;
+///
+/// let a = Matrix::new([[1, 2, 3], [4, 5, 6]]);
+/// let b: Matrix<i32, 3, 1> = Matrix::new([[1], [0], [-1]]);
+/// assert_eq!(a * b, Matrix::new([[-2], [-2]]));
+/// ```
+///
+/// 形状不匹配的乘法不能编译:
+///
+/// ```compile_fail,E0308
+/// use dive_into_rust_of_fcc::ch06::fourth::Matrix;
+///
+/// let a = Matrix::new([[1, 2, 3], [4, 5, 6]]);
+/// let _ = a * a;
+/// ```
+///
+/// 只有方阵有单位矩阵:
+///
+/// ```compile_fail,E0599
+/// use dive_into_rust_of_fcc::ch06::fourth::Matrix;
+///
+/// let _ = Matrix::<f64, 2, 3>::identity();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix<T, const R: usize, const C: usize> {
+    rows: [[T; C]; R],
+}
+
+impl<T: Number, const R: usize, const C: usize> Matrix<T, R, C> {
+    pub fn new(rows: [[T; C]; R]) -> Self {
+        Matrix { rows }
+    }
+
+    pub fn zero() -> Self {
+        Matrix {
+            rows: [[T::ZERO; C]; R],
+        }
+    }
+
+    /// 用 f(行, 列) 生成每一个元素
+    pub fn from_fn<F: FnMut(usize, usize) -> T>(mut f: F) -> Self {
+        Matrix {
+            rows: std::array::from_fn(|r| std::array::from_fn(|c| f(r, c))),
+        }
+    }
+
+    pub const fn shape(&self) -> (usize, usize) {
+        (R, C)
+    }
+
+    pub fn rows(&self) -> &[[T; C]; R] {
+        &self.rows
+    }
+
+    pub fn into_rows(self) -> [[T; C]; R] {
+        self.rows
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<T> {
+        self.rows.get(row)?.get(col).copied()
+    }
+
+    /// 一行就是一个数组, 可以直接借用; 越界时 panic
+    pub fn row(&self, row: usize) -> &[T; C] {
+        &self.rows[row]
+    }
+
+    /// 一列的元素在内存中不连续, 只能复制出来; 越界时 panic
+    pub fn column(&self, col: usize) -> [T; R] {
+        assert!(col < C, "column {} out of range for {} columns", col, C);
+        std::array::from_fn(|r| self.rows[r][col])
+    }
+
+    /// 从 (row, col) 开始的 H 行 W 列, 超出范围时返回 None
+    pub fn slice<const H: usize, const W: usize>(
+        &self,
+        row: usize,
+        col: usize,
+    ) -> Option<Matrix<T, H, W>> {
+        // row、col 可能很大, 相加会溢出
+        if row.checked_add(H)? > R || col.checked_add(W)? > C {
+            return None;
+        }
+        Some(Matrix::from_fn(|r, c| self.rows[row + r][col + c]))
+    }
+
+    pub fn transpose(&self) -> Matrix<T, C, R> {
+        Matrix::from_fn(|r, c| self.rows[c][r])
+    }
+
+    pub fn map<U: Number, F: FnMut(T) -> U>(&self, mut f: F) -> Matrix<U, R, C> {
+        Matrix::from_fn(|r, c| f(self.rows[r][c]))
+    }
+
+    /// 每个元素乘以 k
+    pub fn scale(&self, k: T) -> Self {
+        self.map(|x| x * k)
+    }
+}
+
+impl<T: Number, const N: usize> Matrix<T, N, N> {
+    pub fn identity() -> Self {
+        Matrix::from_fn(|r, c| if r == c { T::ONE } else { T::ZERO })
+    }
+
+    /// 对角线元素之和
+    pub fn trace(&self) -> T {
+        (0..N).fold(T::ZERO, |sum, i| sum + self.rows[i][i])
+    }
+
+    /// 快速幂, pow(0) 是单位矩阵
+    pub fn pow(&self, mut n: u32) -> Self {
+        let mut result = Self::identity();
+        let mut base = *self;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * base;
+            }
+            n >>= 1;
+            // 最后一次平方用不到, 省掉它也避免了整数溢出
+            if n > 0 {
+                base = base * base;
+            }
+        }
+        result
+    }
+}
+
+impl<T: Number, const R: usize, const C: usize> Default for Matrix<T, R, C> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<T, const R: usize, const C: usize> Index<(usize, usize)> for Matrix<T, R, C> {
+    type Output = T;
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        &self.rows[row][col]
+    }
+}
+
+impl<T, const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<T, R, C> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        &mut self.rows[row][col]
+    }
+}
+
+impl<T: Number, const R: usize, const C: usize> Add for Matrix<T, R, C> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Matrix::from_fn(|r, c| self.rows[r][c] + rhs.rows[r][c])
+    }
+}
+
+impl<T: Number, const R: usize, const C: usize> Sub for Matrix<T, R, C> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Matrix::from_fn(|r, c| self.rows[r][c] - rhs.rows[r][c])
+    }
+}
+
+impl<T: Number, const R: usize, const C: usize> Neg for Matrix<T, R, C> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.map(|x| T::ZERO - x)
+    }
+}
+
+/// 左边的列数和右边的行数都是 K, 不相等时找不到这个 impl
+impl<T: Number, const R: usize, const K: usize, const C: usize> Mul<Matrix<T, K, C>>
+    for Matrix<T, R, K>
+{
+    type Output = Matrix<T, R, C>;
+    fn mul(self, rhs: Matrix<T, K, C>) -> Matrix<T, R, C> {
+        Matrix::from_fn(|r, c| {
+            (0..K).fold(T::ZERO, |sum, k| sum + self.rows[r][k] * rhs.rows[k][c])
+        })
+    }
+}
+
+/// 选主元需要绝对值, 判断奇异需要精度
+pub trait Float: Number + Neg<Output = Self> {
+    const EPSILON: Self;
+    fn abs(self) -> Self;
+}
+
+impl Float for f32 {
+    const EPSILON: f32 = f32::EPSILON;
+    fn abs(self) -> f32 {
+        f32::abs(self)
+    }
+}
+
+impl Float for f64 {
+    const EPSILON: f64 = f64::EPSILON;
+    fn abs(self) -> f64 {
+        f64::abs(self)
+    }
+}
+
+impl<T: Float, const R: usize, const C: usize> Matrix<T, R, C> {
+    fn max_abs(&self) -> T {
+        self.rows
+            .iter()
+            .flatten()
+            .fold(T::ZERO, |m, &x| if x.abs() > m { x.abs() } else { m })
+    }
+
+    /// 每个元素的差都不超过 eps
+    pub fn approx_eq(&self, other: &Self, eps: T) -> bool {
+        (*self - *other).max_abs() <= eps
+    }
+}
+
+impl<T: Float, const N: usize> Matrix<T, N, N> {
+    /// 高斯消元, 返回上三角矩阵和交换行带来的符号。
+    /// 每一行按它原来的最大绝对值缩放后再比较(按比例选主元), 主元相对于所在行的大小
+    /// 小到与舍入误差相当时认为矩阵奇异, 返回 None。
+    /// 不能用整个矩阵的最大值作为容差: [[1e10, 0], [0, 1e-7]] 的第二个主元虽然小, 但矩阵并不奇异
+    fn eliminate(&self, mut other: Option<&mut Self>) -> Option<(Self, T)> {
+        let mut a = *self;
+        let mut sign = T::ONE;
+        let mut scales: [T; N] = std::array::from_fn(|r| Matrix::new([self.rows[r]]).max_abs());
+        // N 次运算累积的舍入误差
+        let tolerance = (1..N).fold(T::EPSILON, |t, _| t + T::EPSILON);
+        for col in 0..N {
+            // 全零的行缩放后是 0, 不会被选中, 后面的检查会报告奇异
+            let scaled = |a: &Self, i: usize| {
+                if scales[i] == T::ZERO {
+                    T::ZERO
+                } else {
+                    a.rows[i][col].abs() / scales[i]
+                }
+            };
+            let pivot = (col..N)
+                .max_by(|&i, &j| {
+                    let (x, y) = (scaled(&a, i), scaled(&a, j));
+                    x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(col);
+            if scaled(&a, pivot) <= tolerance {
+                return None;
+            }
+            if pivot != col {
+                a.rows.swap(pivot, col);
+                scales.swap(pivot, col);
+                if let Some(o) = other.as_deref_mut() {
+                    o.rows.swap(pivot, col);
+                }
+                sign = -sign;
+            }
+            for row in col + 1..N {
+                let factor = a.rows[row][col] / a.rows[col][col];
+                for k in 0..N {
+                    a.rows[row][k] = a.rows[row][k] - factor * a.rows[col][k];
+                }
+                if let Some(o) = other.as_deref_mut() {
+                    for k in 0..N {
+                        o.rows[row][k] = o.rows[row][k] - factor * o.rows[col][k];
+                    }
+                }
+            }
+        }
+        Some((a, sign))
+    }
+
+    /// 奇异矩阵的行列式是 0
+    pub fn determinant(&self) -> T {
+        match self.eliminate(None) {
+            Some((upper, sign)) => (0..N).fold(sign, |d, i| d * upper.rows[i][i]),
+            None => T::ZERO,
+        }
+    }
+
+    /// 高斯-约当消元, 奇异矩阵没有逆矩阵
+    pub fn inverse(&self) -> Option<Self> {
+        let mut inv = Self::identity();
+        let (upper, _) = self.eliminate(Some(&mut inv))?;
+        // 从最后一行往上回代
+        for col in (0..N).rev() {
+            let pivot = upper.rows[col][col];
+            for k in 0..N {
+                inv.rows[col][k] = inv.rows[col][k] / pivot;
+            }
+            for row in 0..col {
+                let factor = upper.rows[row][col];
+                for k in 0..N {
+                    inv.rows[row][k] = inv.rows[row][k] - factor * inv.rows[col][k];
+                }
+            }
+        }
+        Some(inv)
+    }
+}
+
+/// 每一列右对齐, 宽度取这一列最宽的元素; 精度和 + 传给每个元素, 宽度是每个元素的最小宽度
+impl<T: fmt::Display, const R: usize, const C: usize> fmt::Display for Matrix<T, R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cell = |x: &T| match (f.precision(), f.sign_plus()) {
+            (Some(p), true) => format!("{:+.*}", p, x),
+            (Some(p), false) => format!("{:.*}", p, x),
+            (None, true) => format!("{:+}", x),
+            (None, false) => x.to_string(),
+        };
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(cell).collect())
+            .collect();
+        let widths: Vec<usize> = (0..C)
+            .map(|c| {
+                cells
+                    .iter()
+                    .map(|row| row[c].chars().count())
+                    .chain(f.width())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for (r, row) in cells.iter().enumerate() {
+            if r > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str("[")?;
+            for (c, text) in row.iter().enumerate() {
+                let sep = if c == 0 { "" } else { "  " };
+                write!(f, "{}{:>w$}", sep, text, w = widths[c])?;
+            }
+            f.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn learn_matrix() {
+    // 整数矩阵: 形状在类型中
+    let a = Matrix::new([[1, 2, 3], [4, 5, 6]]);
+    let at = a.transpose();
+    assert_eq!(a.shape(), (2, 3));
+    assert_eq!(at.shape(), (3, 2));
+    assert_eq!(at, Matrix::new([[1, 4], [2, 5], [3, 6]]));
+    let gram = a * at;
+    println!("{}\n", gram);
+    assert_eq!(gram, Matrix::new([[14, 32], [32, 77]]));
+    assert_eq!(gram.trace(), 91);
+    assert_eq!(Matrix::identity() * a, a);
+    assert_eq!(a + a, a.scale(2));
+    assert_eq!(a.scale(2) - a, a);
+    assert_eq!(a.scale(0), Matrix::zero());
+    assert_eq!(-a + a, Matrix::default());
+    assert_eq!(a[(1, 2)], 6);
+    assert_eq!(a.get(2, 0), None);
+
+    // 行和列, 以及子矩阵
+    assert_eq!(a.row(1), &[4, 5, 6]);
+    assert_eq!(a.column(2), [3, 6]);
+    assert_eq!(a.slice::<2, 2>(0, 1), Some(Matrix::new([[2, 3], [5, 6]])));
+    assert_eq!(
+        a.slice::<1, 3>(1, 0).map(|m| m.into_rows()),
+        Some([[4, 5, 6]])
+    );
+    assert_eq!(a.slice::<2, 2>(1, 0), None);
+    assert_eq!(a.slice::<1, 1>(usize::MAX, 0), None);
+    assert_eq!(a.slice::<1, 1>(0, usize::MAX), None);
+
+    // 斐波那契数列的矩阵快速幂, 与 ch04::second 中的 matrix 算法相同
+    let fib = Matrix::new([[1u64, 1], [1, 0]]).pow(90);
+    assert_eq!(fib[(0, 1)], 2_880_067_194_370_816_120);
+
+    // 浮点数: 行列式和逆矩阵
+    let m = Matrix::new([[4.0, 7.0], [2.0, 6.0]]);
+    assert!((m.determinant() - 10.0).abs() < 1e-12);
+    let inv = m.inverse().unwrap();
+    println!("{:.2}\n", inv);
+    assert!(inv.approx_eq(&Matrix::new([[0.6, -0.7], [-0.2, 0.4]]), 1e-12));
+    assert!((m * inv).approx_eq(&Matrix::identity(), 1e-12));
+
+    // 第一列的第一个元素是 0, 必须交换行才能消元
+    let p = Matrix::new([[0.0, 1.0, 2.0], [1.0, 0.0, 3.0], [4.0, -3.0, 8.0]]);
+    assert!((p.determinant() + 2.0).abs() < 1e-12);
+    let p_inv = p.inverse().unwrap();
+    println!("{:+.3}\n", p_inv);
+    assert!((p * p_inv).approx_eq(&Matrix::identity(), 1e-12));
+    assert!((p_inv * p).approx_eq(&Matrix::identity(), 1e-12));
+    assert!((p_inv.inverse().unwrap()).approx_eq(&p, 1e-12));
+
+    // 奇异矩阵
+    let singular = Matrix::new([[1.0f32, 2.0], [2.0, 4.0]]);
+    assert_eq!(singular.determinant(), 0.0);
+    assert!(singular.inverse().is_none());
+    assert!(Matrix::<f64, 3, 3>::zero().inverse().is_none());
+    // 数量级相差很大的对角矩阵并不奇异
+    let diagonal = Matrix::new([[1e10, 0.0], [0.0, 1e-7]]);
+    assert!((diagonal.determinant() - 1e3).abs() < 1e-9);
+    let diagonal_inv = diagonal.inverse().unwrap();
+    assert!((diagonal_inv[(1, 1)] - 1e7).abs() < 1e-3);
+    assert!((diagonal * diagonal_inv).approx_eq(&Matrix::identity(), 1e-12));
+    let identity = Matrix::<f64, 4, 4>::identity();
+    assert_eq!(identity.inverse(), Some(identity));
+    assert_eq!(identity.determinant(), 1.0);
+
+    // 元素类型只要实现了 Number: 单价 × 数量
+    use crate::ch05::twelfth::Money;
+    let prices = Matrix::new([[Money(1250), Money(300)]]);
+    let quantities = Matrix::new([[Money(200)], [Money(400)]]);
+    let total = prices * quantities;
+    println!("{}\n", total);
+    assert_eq!(total.to_string(), "[37.00]");
+
+    // 对齐输出
+    let text = Matrix::new([[1.5, -20.0], [300.25, 4.0]]).to_string();
+    println!("{}", text);
+    assert_eq!(text, "[   1.5  -20]\n[300.25    4]");
+    assert_eq!(
+        format!("{:6.1}", Matrix::new([[1.0, -2.0]])),
+        "[   1.0    -2.0]"
+    );
+}
@@ -1,2 +1,6 @@
+pub mod fifth;
 pub mod first;
+pub mod fourth;
 pub mod second;
+pub mod sixth;
+pub mod third;
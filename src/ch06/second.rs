@@ -27,9 +27,11 @@ pub fn learn_str1() {
     println!("{}", substr);
     println!("Size of pointer: {}", std::mem::size_of::<*const ()>());
     println!("Size of &str   : {}", std::mem::size_of::<&str>());
+    println!("{:?}", super::third::str_parts(substr));
     // 它内部实际上包含了一个指向字符串片段头部的指针和一个长度
     // 所以，它跟 C/C++ 的字符串不同： C/C++ 里面的字符串以 '\0' 结尾
     // 而 Rust 的字符串是可以中间包含 '\0' 字符的
+    // 按字节下标切片，切在多字节字符中间会 panic，按字符切片见 sixth::char_slice
 }
 
 // String
@@ -47,6 +49,7 @@ pub fn learn_string1() {
 // 这个类型实现了 Deref<Target=str> 的 trait
 // 在很多情况下，&String 类型可以被编译器自动转换为 &str 类型
 pub fn learn_string2() {
+    // 只处理 ASCII，Unicode 的大小写转换见 sixth::capitalize
     fn capitalize(substr: &mut str) {
         substr.make_ascii_uppercase();
     }
@@ -0,0 +1,438 @@
+#![allow(dead_code)]
+
+// 按字符处理 UTF-8 字符串
+// second::learn_str1 中的 &greeting[2..] 是按字节切片的, 对 "Hello" 没问题,
+// 但 "深入浅出" 的每个汉字占 3 个字节, &s[2..] 切在了字符的中间, 直接 panic。
+// second::learn_string2 中的 capitalize 用的是 make_ascii_uppercase, 只认识 ASCII。
+// 这里的函数都按字符或者"用户看到的字符"处理, 越界或者切不开时返回 None, 永远不会 panic:
+// 1. char_slice: 按 char 的下标切片; 按字节切片而不 panic 用标准库的 s.get(2..)
+// 2. graphemes / grapheme_slice: 近似的字素簇, 把 é(e + U+0301)、国旗、带肤色和 ZWJ 连接的 emoji
+//    当作一个整体; 完整的规则见 UAX #29, 需要 unicode-segmentation 这样的库
+// 3. capitalize / title_case: 用 char::to_uppercase, ß 变成 Ss, ǆ 变成 ǅ, 希腊文结尾的 σ 变成 ς
+// 4. reverse_chars / reverse_graphemes: 按 char 反转会把组合符号挂到别的字母上, 国旗也会变成别的国家
+// 5. str_width / Wide: 终端中汉字占两列, {:<10} 按 char 计数, 中英文混排时对不齐
+// 6. truncate_width: 按显示宽度截断, 不会切开字素簇, 末尾加上省略号
+// ch05::twelfth::StrExt::truncate_chars 按 char 计数截断, 不加省略号。
+use std::borrow::Cow;
+use std::fmt::{self, Alignment, Write};
+use std::ops::{Bound, RangeBounds};
+
+const ZWJ: char = '\u{200D}';
+
+/// 组合符号、变体选择符、emoji 肤色和标签字符, 它们附着在前一个字符上
+fn is_extend(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{0483}'..='\u{0489}'
+        | '\u{0591}'..='\u{05BD}'
+        | '\u{0610}'..='\u{061A}'
+        | '\u{064B}'..='\u{065F}'
+        | '\u{0900}'..='\u{0903}'
+        | '\u{093A}'..='\u{094F}'
+        | '\u{0951}'..='\u{0957}'
+        | '\u{0962}'..='\u{0963}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200C}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{302A}'..='\u{302F}'
+        | '\u{3099}'..='\u{309A}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}'
+        | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// 两个区域指示符组成一面国旗
+fn is_regional(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// 近似的字素簇, 见 graphemes
+pub struct Graphemes<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut prev = first;
+        let mut end = first.len_utf8();
+        let mut regionals = usize::from(is_regional(first));
+        for (i, c) in chars {
+            let join = if prev == '\r' {
+                c == '\n'
+            } else if prev.is_control() {
+                false
+            } else if prev == ZWJ || c == ZWJ || is_extend(c) {
+                true
+            } else {
+                // 第奇数个区域指示符与下一个组成国旗
+                is_regional(prev) && is_regional(c) && regionals % 2 == 1
+            };
+            if !join {
+                break;
+            }
+            if is_regional(c) {
+                regionals += 1;
+            }
+            prev = c;
+            end = i + c.len_utf8();
+        }
+        let (cluster, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(cluster)
+    }
+}
+
+/// 把字符串拆成"用户看到的字符"
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s }
+}
+
+/// 每个字素簇和它开始的字节位置
+pub fn grapheme_indices(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    graphemes(s).scan(0, |pos, g| {
+        let start = *pos;
+        *pos += g.len();
+        Some((start, g))
+    })
+}
+
+pub fn grapheme_count(s: &str) -> usize {
+    graphemes(s).count()
+}
+
+/// 按单位(字符或字素簇)的下标切片; starts 是每个单位开始的字节位置
+fn slice_units<R: RangeBounds<usize>>(
+    s: &str,
+    starts: impl Iterator<Item = usize>,
+    range: R,
+) -> Option<&str> {
+    let mut offsets: Vec<usize> = starts.collect();
+    offsets.push(s.len());
+    let count = offsets.len() - 1;
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1)?,
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => count,
+    };
+    if start > end || end > count {
+        return None;
+    }
+    s.get(offsets[start]..offsets[end])
+}
+
+/// 按 char 的下标切片, 越界或者 start > end 时返回 None
+pub fn char_slice<R: RangeBounds<usize>>(s: &str, range: R) -> Option<&str> {
+    slice_units(s, s.char_indices().map(|(i, _)| i), range)
+}
+
+/// 按字素簇的下标切片, 不会把 é 的 e 和重音符号分开
+pub fn grapheme_slice<R: RangeBounds<usize>>(s: &str, range: R) -> Option<&str> {
+    slice_units(s, grapheme_indices(s).map(|(i, _)| i), range)
+}
+
+/// 单词开头用的字母; 这几个双字母的标题形式与大写形式不同
+fn push_titlecase(out: &mut String, c: char) {
+    match c {
+        'Ǆ' | 'ǅ' | 'ǆ' => out.push('ǅ'),
+        'Ǉ' | 'ǈ' | 'ǉ' => out.push('ǈ'),
+        'Ǌ' | 'ǋ' | 'ǌ' => out.push('ǋ'),
+        'Ǳ' | 'ǲ' | 'ǳ' => out.push('ǲ'),
+        'ß' => out.push_str("Ss"),
+        // 一个字符的大写形式可能是好几个字符
+        _ => out.extend(c.to_uppercase()),
+    }
+}
+
+/// 第一个字符变成标题形式, 其余不变
+pub fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    let mut out = String::with_capacity(s.len());
+    if let Some(first) = chars.next() {
+        push_titlecase(&mut out, first);
+    }
+    out.push_str(chars.as_str());
+    out
+}
+
+/// 每个单词的第一个字母变成标题形式, 其余的变成小写;
+/// 单词由字母、数字、撇号和组合符号组成, 所以 don't 只有 d 大写
+pub fn title_case(s: &str) -> String {
+    let in_word = |c: char| c.is_alphanumeric() || c == '\'' || c == '’' || is_extend(c);
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find(|c: char| c.is_alphanumeric()) {
+        out.push_str(&rest[..start]);
+        let word = &rest[start..];
+        let len = word.find(|c: char| !in_word(c)).unwrap_or(word.len());
+        let mut chars = word[..len].chars();
+        if let Some(first) = chars.next() {
+            push_titlecase(&mut out, first);
+        }
+        // 整个单词一起转换, 希腊文结尾的 Σ 才会变成 ς
+        out.push_str(&chars.as_str().to_lowercase());
+        rest = &word[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 按 char 反转, 组合符号会挂到别的字母上
+pub fn reverse_chars(s: &str) -> String {
+    s.chars().rev().collect()
+}
+
+/// 按字素簇反转
+pub fn reverse_graphemes(s: &str) -> String {
+    let mut parts: Vec<&str> = graphemes(s).collect();
+    parts.reverse();
+    parts.concat()
+}
+
+/// 在终端中占几列: 汉字、假名、谚文、全角符号和 emoji 占两列, 组合符号和控制字符不占位置。
+/// emoji 只算默认以 emoji 样式显示的那些(Unicode 的 East_Asian_Width=W),
+/// 同一区段中默认按文字样式显示的符号(例如 ☀ ✂ 🛠)仍然占一列
+pub fn char_width(c: char) -> usize {
+    if c == ZWJ || c.is_control() || is_extend(c) {
+        return 0;
+    }
+    let wide = matches!(c,
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{303E}'
+        | '\u{3041}'..='\u{33FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{A000}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        | '\u{2614}'..='\u{2615}'
+        | '\u{2648}'..='\u{2653}'
+        | '\u{267F}'
+        | '\u{2693}'
+        | '\u{26A1}'
+        | '\u{26AA}'..='\u{26AB}'
+        | '\u{26BD}'..='\u{26BE}'
+        | '\u{26C4}'..='\u{26C5}'
+        | '\u{26CE}'
+        | '\u{26D4}'
+        | '\u{26EA}'
+        | '\u{26F2}'..='\u{26F3}'
+        | '\u{26F5}'
+        | '\u{26FA}'
+        | '\u{26FD}'
+        | '\u{2705}'
+        | '\u{270A}'..='\u{270B}'
+        | '\u{2728}'
+        | '\u{274C}'
+        | '\u{274E}'
+        | '\u{2753}'..='\u{2755}'
+        | '\u{2757}'
+        | '\u{2795}'..='\u{2797}'
+        | '\u{27B0}'
+        | '\u{27BF}'
+        | '\u{1F1E6}'..='\u{1F1FF}'
+        | '\u{1F300}'..='\u{1F64F}'
+        | '\u{1F680}'..='\u{1F6C5}'
+        | '\u{1F6CC}'
+        | '\u{1F6D0}'..='\u{1F6D2}'
+        | '\u{1F6D5}'..='\u{1F6D7}'
+        | '\u{1F6DC}'..='\u{1F6DF}'
+        | '\u{1F6EB}'..='\u{1F6EC}'
+        | '\u{1F6F4}'..='\u{1F6FC}'
+        | '\u{1F900}'..='\u{1F9FF}'
+        | '\u{1FA70}'..='\u{1FAFF}'
+        | '\u{20000}'..='\u{2FFFD}'
+        | '\u{30000}'..='\u{3FFFD}');
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+/// 显示宽度; 一个字素簇按其中最宽的字符计算, 所以国旗和 ZWJ 连起来的 emoji 都只占两列
+pub fn str_width(s: &str) -> usize {
+    graphemes(s)
+        .map(|g| g.chars().map(char_width).max().unwrap_or(0))
+        .sum()
+}
+
+/// 按显示宽度截断, 放不下时末尾加上 ellipsis, 总宽度不超过 max_width; 放得下时原样借用
+pub fn truncate_width<'a>(s: &'a str, max_width: usize, ellipsis: &str) -> Cow<'a, str> {
+    if str_width(s) <= max_width {
+        return Cow::Borrowed(s);
+    }
+    // 省略号本身都放不下时就不加了
+    let ellipsis = if str_width(ellipsis) <= max_width {
+        ellipsis
+    } else {
+        ""
+    };
+    let budget = max_width - str_width(ellipsis);
+    let mut end = 0;
+    let mut used = 0;
+    for g in graphemes(s) {
+        let w = str_width(g);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        end += g.len();
+    }
+    Cow::Owned(format!("{}{}", &s[..end], ellipsis))
+}
+
+/// 按显示宽度对齐的字符串: 宽度、填充和对齐与 {:<10} 相同, 但汉字算两列; 精度是最大显示宽度
+pub struct Wide<'a>(pub &'a str);
+
+impl fmt::Display for Wide<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match f.precision() {
+            Some(max) => truncate_width(self.0, max, ""),
+            None => Cow::Borrowed(self.0),
+        };
+        let pad = f.width().map_or(0, |w| w.saturating_sub(str_width(&s)));
+        let (before, after) = match f.align() {
+            Some(Alignment::Right) => (pad, 0),
+            Some(Alignment::Center) => (pad / 2, pad - pad / 2),
+            _ => (0, pad),
+        };
+        let fill = f.fill();
+        for _ in 0..before {
+            f.write_char(fill)?;
+        }
+        f.write_str(&s)?;
+        for _ in 0..after {
+            f.write_char(fill)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn learn_utf8_strings() {
+    // 按字节切片会切在汉字的中间
+    let title = "深入浅出 Rust";
+    assert!(!title.is_char_boundary(2));
+    assert_eq!(title.get(2..), None);
+    assert_eq!(char_slice(title, 2..4), Some("浅出"));
+    assert_eq!(char_slice(title, 5..), Some("Rust"));
+    assert_eq!(char_slice(title, ..=1), Some("深入"));
+    assert_eq!(char_slice(title, 9..9), Some(""));
+    assert_eq!(char_slice(title, 3..10), None);
+    // 下标来自运行时, start > end 时同样返回 None
+    let (from, to) = (3, 2);
+    assert_eq!(char_slice(title, from..to), None);
+    assert_eq!(char_slice("Hello", 2..), Some("llo"));
+
+    // 字素簇: é 由两个 char 组成, 国旗是两个区域指示符, 一家人是三个 emoji 用 ZWJ 连起来
+    let text = "cafe\u{301} 🇨🇳👍🏽👨\u{200D}👩\u{200D}👧\r\n!";
+    let clusters: Vec<&str> = graphemes(text).collect();
+    println!(
+        "{} chars, {} graphemes: {:?}",
+        text.chars().count(),
+        clusters.len(),
+        clusters
+    );
+    assert_eq!(text.chars().count(), 18);
+    assert_eq!(
+        clusters,
+        [
+            "c",
+            "a",
+            "f",
+            "e\u{301}",
+            " ",
+            "🇨🇳",
+            "👍🏽",
+            "👨\u{200D}👩\u{200D}👧",
+            "\r\n",
+            "!"
+        ]
+    );
+    assert_eq!(grapheme_slice(text, 3..4), Some("e\u{301}"));
+    // 按 char 切会把重音符号切掉
+    assert_eq!(char_slice(text, 3..4), Some("e"));
+    assert_eq!(grapheme_slice(text, 5..=7).map(grapheme_count), Some(3));
+    assert_eq!(grapheme_slice(text, 11..), None);
+    assert_eq!(grapheme_count("🇯🇵🇨🇳🇺"), 3);
+
+    // 反转
+    assert_eq!(reverse_chars("ab\u{301}c"), "c\u{301}ba");
+    assert_eq!(reverse_graphemes("ab\u{301}c"), "cb\u{301}a");
+    assert_eq!(reverse_chars("🇨🇳🇯🇵"), "🇵🇯🇳🇨");
+    assert_eq!(reverse_graphemes("🇨🇳🇯🇵"), "🇯🇵🇨🇳");
+    assert_eq!(reverse_graphemes("深入浅出"), "出浅入深");
+
+    // 大小写
+    let mut ascii = String::from("élan");
+    ascii.make_ascii_uppercase();
+    assert_eq!(ascii, "éLAN");
+    assert_eq!(capitalize("élan"), "Élan");
+    assert_eq!(capitalize("ßtraße"), "Sstraße");
+    assert_eq!(capitalize("ǆungla"), "ǅungla");
+    assert_eq!(capitalize("深入浅出"), "深入浅出");
+    assert_eq!(capitalize(""), "");
+    assert_eq!(
+        title_case("hello wORLD, don't PANIC-free"),
+        "Hello World, Don't Panic-Free"
+    );
+    assert_eq!(title_case("ΟΔΥΣΣΕΥΣ"), "Οδυσσευς");
+    assert_eq!(title_case("  élan vital  "), "  Élan Vital  ");
+
+    // 显示宽度
+    assert_eq!(str_width("Rust"), 4);
+    assert_eq!(str_width("深入浅出"), 8);
+    assert_eq!(str_width("e\u{301}"), 1);
+    assert_eq!(str_width("，"), 2);
+    assert_eq!(str_width("🇨🇳"), 2);
+    assert_eq!(str_width("🚀🪐"), 4);
+    assert_eq!(str_width("⚡✅"), 4);
+    assert_eq!(str_width("☀"), 1);
+    assert_eq!(str_width("👨\u{200D}👩\u{200D}👧"), 2);
+    // 标准库按 char 计数, 汉字多的那一行显得更宽
+    assert_eq!(format!("[{:<10}]", "深入浅出"), "[深入浅出      ]");
+    assert_eq!(format!("[{:<10}]", Wide("深入浅出")), "[深入浅出  ]");
+    assert_eq!(format!("{:^8}", Wide("中文")), "  中文  ");
+    assert_eq!(format!("{:*>6.3}", Wide("深入浅出")), "****深");
+    let rows = [("ch05", "trait"), ("ch06", "数组和字符串"), ("ch09", "宏")];
+    for (chapter, topic) in &rows {
+        let line = format!("{} | {:<12} |", chapter, Wide(topic));
+        println!("{}", line);
+        assert_eq!(str_width(&line), 21);
+    }
+
+    // 按宽度截断
+    let long = "深入浅出 Rust 笔记";
+    assert_eq!(truncate_width(long, 9, "…"), "深入浅出…");
+    assert_eq!(truncate_width(long, 8, "…"), "深入浅…");
+    assert!(matches!(
+        truncate_width("short", 10, "…"),
+        Cow::Borrowed("short")
+    ));
+    assert_eq!(
+        truncate_width("e\u{301}e\u{301}e\u{301}", 2, "…"),
+        "e\u{301}…"
+    );
+    assert_eq!(truncate_width("abcdef", 2, "..."), "ab");
+    assert_eq!(
+        truncate_width("👨\u{200D}👩\u{200D}👧 family", 4, "…"),
+        "👨\u{200D}👩\u{200D}👧 …"
+    );
+    for max in 0..=str_width(long) {
+        assert!(str_width(&truncate_width(long, max, "…")) <= max);
+    }
+}
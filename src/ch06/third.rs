@@ -0,0 +1,384 @@
+#![allow(dead_code)]
+
+// 胖指针里有什么
+// first::raw_slice 用 transmute 把 &[i32] 当成两个 usize 打印出来, second::learn_str1 只打印了 &str 的大小。
+// 这里把胖指针拆开看清楚:
+// 1. &[T] 和 &str: 数据指针 + 元素个数, 用 as_ptr() 和 len() 就能安全地拿到, 不需要 transmute
+// 2. &dyn Trait 和 Box<dyn Trait>: 数据指针 + 虚表(vtable)指针
+//    虚表开头三项是 drop_in_place 函数、类型的大小和对齐, 后面是 trait 的各个方法
+// 3. 反过来, 从拆开的两部分重新拼出引用, 拼之前检查空指针、对齐、长度、UTF-8 和虚表
+// 注意: 标准库还没有稳定拿到 trait object 元数据的接口(std::ptr::metadata),
+// 胖指针的内存布局和虚表的布局也不是语言保证的, 所以读取虚表的代码都集中在这个文件里,
+// 读出来的大小和对齐还会与 size_of_val / align_of_val 对比, 布局不符合预期时返回错误而不是乱读。
+// 即便如此, 虚表指针本身无法验证, 所以 dyn_parts 和 into_raw_parts 都是 unsafe 的。
+use std::fmt;
+use std::mem::{align_of, align_of_val, size_of, size_of_val};
+use std::ptr;
+use std::str::Utf8Error;
+
+const WORD: usize = size_of::<usize>();
+
+/// 数组切片或字符串切片拆开后的两部分
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SliceParts {
+    pub data: *const (),
+    /// 元素个数, 对 str 来说是字节数
+    pub len: usize,
+}
+
+pub fn slice_parts<T>(s: &[T]) -> SliceParts {
+    SliceParts {
+        data: s.as_ptr() as *const (),
+        len: s.len(),
+    }
+}
+
+pub fn str_parts(s: &str) -> SliceParts {
+    slice_parts(s.as_bytes())
+}
+
+/// 虚表的开头三项
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VTable {
+    pub addr: *const (),
+    /// 类型不需要析构时编译器不生成 drop_in_place, 这一项为空
+    pub drop_in_place: Option<*const ()>,
+    pub size: usize,
+    pub align: usize,
+}
+
+/// trait object 拆开后的两部分
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DynParts {
+    pub data: *const (),
+    pub vtable: VTable,
+}
+
+/// 标记 trait, 应该通过 trait_object! 宏来实现, 宏只接受 dyn 开头的类型
+///
+/// # Safety
+/// 实现它的类型必须是 dyn Trait, 也就是指向它的指针由数据指针和虚表指针组成。
+/// 如果为 [T] 或 str 实现它, 长度会被当成虚表地址去读
+pub unsafe trait TraitObject {}
+
+/// trait_object!(dyn Trait + Send) 为这个 trait object 类型实现 TraitObject
+#[macro_export]
+macro_rules! trait_object {
+    (dyn $($bounds:tt)+) => {
+        unsafe impl $crate::ch06::third::TraitObject for dyn $($bounds)+ {}
+    };
+}
+
+trait_object!(dyn std::any::Any);
+trait_object!(dyn std::any::Any + Send);
+trait_object!(dyn fmt::Debug);
+trait_object!(dyn fmt::Display);
+trait_object!(dyn std::error::Error);
+trait_object!(dyn Fn());
+
+/// 拆开或者拼回胖指针时发现的问题
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FatPointerError {
+    /// 胖指针的布局与预期不同, 比如不是两个字, 或者虚表中的大小和对齐对不上
+    UnknownLayout,
+    Null,
+    Misaligned {
+        addr: usize,
+        align: usize,
+    },
+    /// 总字节数超过 isize::MAX
+    TooLong(usize),
+    InvalidUtf8(Utf8Error),
+    /// 虚表的内容与 DynParts 中记录的不一致, 或者本身就不合理
+    BadVTable,
+}
+
+impl fmt::Display for FatPointerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FatPointerError::UnknownLayout => write!(f, "unexpected fat pointer layout"),
+            FatPointerError::Null => write!(f, "null data pointer"),
+            FatPointerError::Misaligned { addr, align } => {
+                write!(f, "address {:#x} is not aligned to {}", addr, align)
+            }
+            FatPointerError::TooLong(len) => write!(f, "length {} is too long", len),
+            FatPointerError::InvalidUtf8(e) => write!(f, "invalid utf-8: {}", e),
+            FatPointerError::BadVTable => write!(f, "vtable does not match"),
+        }
+    }
+}
+
+impl std::error::Error for FatPointerError {}
+
+fn check_aligned(addr: *const (), align: usize) -> Result<(), FatPointerError> {
+    if addr.is_null() {
+        return Err(FatPointerError::Null);
+    }
+    if !(addr as usize).is_multiple_of(align) {
+        return Err(FatPointerError::Misaligned {
+            addr: addr as usize,
+            align,
+        });
+    }
+    Ok(())
+}
+
+// 读取虚表的开头三项, 调用方保证 addr 指向一个虚表
+unsafe fn read_vtable(addr: *const ()) -> VTable {
+    let entries = addr as *const usize;
+    let drop_in_place = *entries as *const ();
+    VTable {
+        addr,
+        drop_in_place: if drop_in_place.is_null() {
+            None
+        } else {
+            Some(drop_in_place)
+        },
+        size: *entries.add(1),
+        align: *entries.add(2),
+    }
+}
+
+/// 把 trait object 引用拆成数据指针和虚表
+///
+/// # Safety
+/// 胖指针和虚表的布局都不是语言保证的, 这个函数假设 &T 是(数据指针, 虚表指针)两个字,
+/// 并且会从第二个字指向的地址读三个 usize。能确认的只有数据指针在前,
+/// 虚表指针是不是真的指向虚表无法检查, 调用方要保证当前编译器的布局与此相同。
+/// 只需要数据指针、大小和对齐时, 用安全的 `r as *const T as *const ()`、size_of_val 和 align_of_val
+pub unsafe fn dyn_parts<T: TraitObject + ?Sized>(r: &T) -> Result<DynParts, FatPointerError> {
+    if size_of::<&T>() != 2 * WORD {
+        return Err(FatPointerError::UnknownLayout);
+    }
+    // 把胖指针当成两个字读出来, 目前的编译器总是数据指针在前;
+    // 第一个字是不是数据指针, 可以用安全的转换(胖指针转成瘦指针)确认
+    let words: [*const (); 2] = ptr::read(&r as *const &T as *const [*const (); 2]);
+    let data = r as *const T as *const ();
+    if words[0] != data {
+        return Err(FatPointerError::UnknownLayout);
+    }
+    let addr = words[1];
+    check_aligned(addr, align_of::<usize>()).map_err(|_| FatPointerError::UnknownLayout)?;
+    // TraitObject 保证了第二个字是虚表指针
+    let vtable = read_vtable(addr);
+    if vtable.size != size_of_val(r) || vtable.align != align_of_val(r) {
+        return Err(FatPointerError::UnknownLayout);
+    }
+    Ok(DynParts { data, vtable })
+}
+
+/// 交出 Box 的所有权, 拆成两部分; 之后必须用 box_from_parts 拼回去, 否则内存会泄漏
+///
+/// # Safety
+/// 与 dyn_parts 相同
+pub unsafe fn into_raw_parts<T: TraitObject + ?Sized>(
+    b: Box<T>,
+) -> Result<DynParts, FatPointerError> {
+    let parts = dyn_parts(&*b)?;
+    let _ = Box::into_raw(b);
+    Ok(parts)
+}
+
+// 按 dyn_parts 确认过的顺序(数据在前)把两个字拼成胖指针
+unsafe fn join<T: TraitObject + ?Sized>(parts: &DynParts) -> *const T {
+    assert_eq!(size_of::<*const T>(), 2 * WORD);
+    let words = [parts.data, parts.vtable.addr];
+    ptr::read(&words as *const [*const (); 2] as *const *const T)
+}
+
+// 拼回之前检查虚表和数据指针
+unsafe fn check_dyn(parts: &DynParts) -> Result<(), FatPointerError> {
+    let addr = parts.vtable.addr;
+    if addr.is_null() || !(addr as usize).is_multiple_of(align_of::<usize>()) {
+        return Err(FatPointerError::BadVTable);
+    }
+    let vtable = read_vtable(addr);
+    if vtable != parts.vtable
+        || !vtable.align.is_power_of_two()
+        || !vtable.size.is_multiple_of(vtable.align)
+    {
+        return Err(FatPointerError::BadVTable);
+    }
+    check_aligned(parts.data, vtable.align)
+}
+
+/// 从数据指针和虚表拼回 &dyn Trait
+///
+/// # Safety
+/// parts 必须来自同一种 trait object 类型 T 的 dyn_parts, 数据在 'a 期间有效且没有被可变借用。
+/// 这里只能发现明显的错误(空指针、没对齐、虚表被改过), 不能发现悬垂指针
+pub unsafe fn dyn_from_parts<'a, T: TraitObject + ?Sized>(
+    parts: DynParts,
+) -> Result<&'a T, FatPointerError> {
+    check_dyn(&parts)?;
+    let r: &T = &*join::<T>(&parts);
+    // 拼回来的指针再拆一次, 结果应该完全相同
+    if dyn_parts(r)? != parts {
+        return Err(FatPointerError::UnknownLayout);
+    }
+    Ok(r)
+}
+
+/// 把 into_raw_parts 拆开的 Box 拼回去, 重新获得所有权
+///
+/// # Safety
+/// parts 必须来自 into_raw_parts::<T>, 并且只能拼回一次
+pub unsafe fn box_from_parts<T: TraitObject + ?Sized>(
+    parts: DynParts,
+) -> Result<Box<T>, FatPointerError> {
+    check_dyn(&parts)?;
+    Ok(Box::from_raw(join::<T>(&parts) as *mut T))
+}
+
+/// 从数据指针和长度拼回 &[T]
+///
+/// # Safety
+/// data 开始的 len 个元素必须在 'a 期间有效、已经初始化, 并且没有被可变借用
+pub unsafe fn slice_from_parts<'a, T>(parts: SliceParts) -> Result<&'a [T], FatPointerError> {
+    check_aligned(parts.data, align_of::<T>())?;
+    match parts.len.checked_mul(size_of::<T>()) {
+        Some(bytes) if bytes <= isize::MAX as usize => {}
+        _ => return Err(FatPointerError::TooLong(parts.len)),
+    }
+    Ok(std::slice::from_raw_parts(
+        parts.data as *const T,
+        parts.len,
+    ))
+}
+
+/// 从数据指针和字节数拼回 &str, 内容必须是合法的 UTF-8
+///
+/// # Safety
+/// 与 slice_from_parts::<u8> 相同
+pub unsafe fn str_from_parts<'a>(parts: SliceParts) -> Result<&'a str, FatPointerError> {
+    let bytes = slice_from_parts::<u8>(parts)?;
+    std::str::from_utf8(bytes).map_err(FatPointerError::InvalidUtf8)
+}
+
+impl fmt::Display for VTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "vtable@{:p} size {} align {} drop ",
+            self.addr, self.size, self.align
+        )?;
+        match self.drop_in_place {
+            Some(p) => write!(f, "{:p}", p),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+pub fn learn_fat_pointers() {
+    // 切片: 数据指针 + 长度, 子切片的指针向后偏移
+    let arr = [1i32, 2, 3, 4, 5];
+    let all = slice_parts(&arr[..]);
+    let tail = slice_parts(&arr[2..]);
+    println!("&arr[..]  {:?}", all);
+    println!("&arr[2..] {:?}", tail);
+    assert_eq!(all.data, arr.as_ptr() as *const ());
+    assert_eq!((all.len, tail.len), (5, 3));
+    assert_eq!(tail.data as usize - all.data as usize, 2 * size_of::<i32>());
+
+    // &str 的长度是字节数, 不是字符数
+    let s = "你好, rust";
+    let parts = str_parts(s);
+    println!("{:?} {:?}", s, parts);
+    assert_eq!(parts.len, 12);
+
+    // trait object: 数据指针 + 虚表
+    use std::fmt::Debug;
+    let x = 5i32;
+    let r: &dyn Debug = &x;
+    let parts = unsafe { dyn_parts(r) }.unwrap();
+    println!("&dyn Debug of i32    {:p} {}", parts.data, parts.vtable);
+    assert_eq!(parts.data, &x as *const i32 as *const ());
+    assert_eq!((parts.vtable.size, parts.vtable.align), (4, 4));
+    // 不读虚表也能安全地拿到数据指针、大小和对齐
+    assert_eq!(r as *const dyn Debug as *const (), parts.data);
+    assert_eq!((size_of_val(r), align_of_val(r)), (4, 4));
+
+    // String 需要析构, 虚表中有 drop_in_place
+    let b: Box<dyn Debug> = Box::new(String::from("boxed"));
+    let parts = unsafe { dyn_parts(&*b) }.unwrap();
+    println!("Box<dyn Debug> String {:p} {}", parts.data, parts.vtable);
+    assert_eq!(parts.vtable.size, size_of::<String>());
+    assert!(parts.vtable.drop_in_place.is_some());
+    // 零大小的类型没有真正分配内存, 数据指针只是一个对齐过的非空地址
+    let unit: &dyn Debug = &();
+    let parts = unsafe { dyn_parts(unit) }.unwrap();
+    println!("&dyn Debug of ()     {:p} {}", parts.data, parts.vtable);
+    assert_eq!((parts.vtable.size, parts.vtable.align), (0, 1));
+
+    // 拼回引用
+    let back: &[i32] = unsafe { slice_from_parts(tail) }.unwrap();
+    assert_eq!(back, [3, 4, 5]);
+    let misaligned = SliceParts {
+        data: (all.data as usize + 1) as *const (),
+        len: 1,
+    };
+    let err = unsafe { slice_from_parts::<i32>(misaligned) }.unwrap_err();
+    println!("{}", err);
+    assert!(matches!(err, FatPointerError::Misaligned { align: 4, .. }));
+    let huge = SliceParts {
+        data: all.data,
+        len: usize::MAX / 2,
+    };
+    let err = unsafe { slice_from_parts::<i32>(huge) }.unwrap_err();
+    assert_eq!(err, FatPointerError::TooLong(usize::MAX / 2));
+
+    let text = unsafe { str_from_parts(str_parts(&s[..6])) }.unwrap();
+    assert_eq!(text, "你好");
+    // 从一个汉字的中间截断
+    let cut = SliceParts {
+        len: 4,
+        ..str_parts(s)
+    };
+    let err = unsafe { str_from_parts(cut) }.unwrap_err();
+    println!("{}", err);
+    assert!(matches!(err, FatPointerError::InvalidUtf8(_)));
+
+    let parts = unsafe { dyn_parts(r) }.unwrap();
+    let back: &dyn Debug = unsafe { dyn_from_parts(parts) }.unwrap();
+    assert_eq!(format!("{:?}", back), "5");
+    // 篡改过的虚表和空指针都会被发现
+    let mut forged = parts;
+    forged.vtable.size = 8;
+    let err = unsafe { dyn_from_parts::<dyn Debug>(forged) }.unwrap_err();
+    println!("{}", err);
+    assert_eq!(err, FatPointerError::BadVTable);
+    let null = DynParts {
+        data: ptr::null(),
+        ..parts
+    };
+    let err = unsafe { dyn_from_parts::<dyn Debug>(null) }.unwrap_err();
+    assert_eq!(err, FatPointerError::Null);
+
+    // Box<dyn Trait> 拆开再拼回, 析构函数只执行一次
+    use std::rc::Rc;
+    let counter = Rc::new(());
+    let b: Box<dyn Debug> = Box::new(Rc::clone(&counter));
+    assert_eq!(Rc::strong_count(&counter), 2);
+    let parts = unsafe { into_raw_parts(b) }.unwrap();
+    assert!(parts.vtable.drop_in_place.is_some());
+    let b: Box<dyn Debug> = unsafe { box_from_parts(parts) }.unwrap();
+    drop(b);
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    // 用户自己的 trait 也可以
+    trait Named {
+        fn name(&self) -> &str;
+    }
+    trait_object!(dyn Named);
+    struct Cat;
+    impl Named for Cat {
+        fn name(&self) -> &str {
+            "cat"
+        }
+    }
+    let cat: &dyn Named = &Cat;
+    let parts = unsafe { dyn_parts(cat) }.unwrap();
+    let back: &dyn Named = unsafe { dyn_from_parts(parts) }.unwrap();
+    assert_eq!(back.name(), "cat");
+    println!("&dyn Named of Cat    {:p} {}", parts.data, parts.vtable);
+}
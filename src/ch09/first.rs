@@ -80,4 +80,6 @@ pub fn third() {
     // 一旦编译器内部有所变化，那么对应的宏就有可能出现编译错误
     // 因此 Rust 中的“宏”一直难以稳定
     // ...
+    // 后来稳定下来的是基于 TokenStream 的 proc_macro 接口, 它不依赖编译器的内部实现。
+    // 自己实现的 derive 和属性宏见 macros/src/lib.rs, 用法见 third.rs
 }
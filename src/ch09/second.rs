@@ -0,0 +1,276 @@
+#![allow(dead_code)]
+
+// 用宏生成 builder
+// ch02::third::learn_struct 中手写了 fn default() -> Point3D, 再用 ..default() 补全其他成员。
+// 成员多了以后, 手写 Default 和 builder 都是重复劳动, 正好交给宏:
+//
+// builder! {
+//     #[derive(Debug)]
+//     pub struct Server => ServerBuilder {
+//         pub host: String = "localhost".to_string(),
+//         pub port: u16 = 8080,
+//         pub name: String,
+//     }
+// }
+//
+// 会生成:
+// 1. struct Server, 成员和属性原样保留
+// 2. impl Default for Server, 只有所有成员都有默认值时才生成; 有必填成员时不生成,
+//    否则 Server::default() 会造出一个 build() 不接受的值
+// 3. struct ServerBuilder, 每个成员一个同名的 setter, 可以链式调用, 参数就是成员的类型,
+//    所以 .port(9000) 中的整数字面量能推断出类型; 成员不能叫 new、build 或 builder
+// 4. ServerBuilder::build(), 没有默认值的成员是必填的, 漏填时返回 BuildError, 列出所有缺少的成员
+// 5. Server::builder()
+// 与 ch03::fourth 中的 bitfield! 一样, builder 的名字需要显式给出, 因为 macro_rules! 不能拼接标识符。
+use std::fmt;
+
+/// build() 时缺少必填的成员
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildError {
+    pub type_name: &'static str,
+    pub missing: Vec<&'static str>,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields: Vec<String> = self.missing.iter().map(|m| format!("`{}`", m)).collect();
+        write!(
+            f,
+            "cannot build {}: missing required field{} {}",
+            self.type_name,
+            if self.missing.len() > 1 { "s" } else { "" },
+            fields.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// 生成结构体、Default 实现和 builder, 用法见本文件开头
+///
+/// 有必填成员的结构体不实现 Default, 否则 Default::default() 会绕过 build() 的检查:
+///
+/// ```compile_fail,E0599
+/// dive_into_rust_of_fcc::builder! {
+///     pub struct Name => NameBuilder {
+///         pub first: String,
+///         pub last: String = String::new(),
+///     }
+/// }
+/// let name = Name::default();
+/// ```
+///
+/// 成员不能叫 new 或 build, 也不能叫 builder, 它们是生成的方法的名字:
+///
+/// ```compile_fail
+/// dive_into_rust_of_fcc::builder! {
+///     pub struct Job => JobBuilder {
+///         pub build: u32 = 0,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! builder {
+    // 取出 builder 中的值: 有默认值的成员没有设置时用默认值
+    (@value $value:expr, $default:expr) => {
+        $value.unwrap_or_else(|| $default)
+    };
+    (@value $value:expr) => {
+        match $value {
+            ::std::option::Option::Some(v) => v,
+            ::std::option::Option::None => unreachable!("required fields are checked before"),
+        }
+    };
+    // 检查必填的成员, 有默认值的成员什么都不做
+    (@check $missing:ident, $value:expr, $field:ident, $default:expr) => {};
+    (@check $missing:ident, $value:expr, $field:ident) => {
+        if $value.is_none() {
+            $missing.push(stringify!($field));
+        }
+    };
+    // 成员的名字不能与生成的方法重名
+    (@name new) => {
+        compile_error!("builder!: a field cannot be named `new`");
+    };
+    (@name build) => {
+        compile_error!("builder!: a field cannot be named `build`");
+    };
+    (@name builder) => {
+        compile_error!("builder!: a field cannot be named `builder`");
+    };
+    (@name $field:ident) => {};
+    // 所有成员都有默认值: 先生成 Default, 其余的与下面相同
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident => $builder:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty = $default:expr
+            ),* $(,)?
+        }
+    ) => {
+        $crate::builder! {
+            @items
+            $(#[$meta])*
+            $vis struct $name => $builder {
+                $(
+                    $(#[$field_meta])*
+                    $field_vis $field: $ty = $default
+                ),*
+            }
+        }
+
+        impl ::std::default::Default for $name {
+            fn default() -> $name {
+                $name {
+                    $($field: $default,)*
+                }
+            }
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident => $builder:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty $(= $default:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $crate::builder! {
+            @items
+            $(#[$meta])*
+            $vis struct $name => $builder {
+                $(
+                    $(#[$field_meta])*
+                    $field_vis $field: $ty $(= $default)?
+                ),*
+            }
+        }
+    };
+    // 结构体和 builder
+    (
+        @items
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident => $builder:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty $(= $default:expr)?
+            ),*
+        }
+    ) => {
+        $($crate::builder!(@name $field);)*
+
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        #[derive(Default)]
+        $vis struct $builder {
+            $($field: ::std::option::Option<$ty>,)*
+        }
+
+        impl $name {
+            pub fn builder() -> $builder {
+                $builder::default()
+            }
+        }
+
+        impl $builder {
+            pub fn new() -> $builder {
+                $builder::default()
+            }
+
+            $(
+                pub fn $field(mut self, value: $ty) -> $builder {
+                    self.$field = ::std::option::Option::Some(value);
+                    self
+                }
+            )*
+
+            /// 所有必填的成员都设置过才能成功
+            pub fn build(self) -> ::std::result::Result<$name, $crate::ch09::second::BuildError> {
+                // 所有成员都有默认值时 missing 不会被修改
+                #[allow(unused_mut)]
+                let mut missing: ::std::vec::Vec<&'static str> = ::std::vec::Vec::new();
+                $($crate::builder!(@check missing, self.$field, $field $(, $default)?);)*
+                if !missing.is_empty() {
+                    return ::std::result::Result::Err($crate::ch09::second::BuildError {
+                        type_name: stringify!($name),
+                        missing,
+                    });
+                }
+                ::std::result::Result::Ok($name {
+                    $($field: $crate::builder!(@value self.$field $(, $default)?),)*
+                })
+            }
+        }
+    };
+}
+
+builder! {
+    /// 与 learn_struct 中的 Point3D 相同, 所有成员都有默认值
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Point3D => Point3DBuilder {
+        pub x: i32 = 0,
+        pub y: i32 = 0,
+        pub z: i32 = 0,
+    }
+}
+
+builder! {
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Server => ServerBuilder {
+        pub host: String = "localhost".to_string(),
+        pub port: u16 = 8080,
+        /// 必填
+        pub name: String,
+        /// 必填
+        pub workers: usize,
+        pub tags: Vec<String> = vec!["default".to_string()],
+    }
+}
+
+pub fn learn_builder() {
+    // 生成的 Default 可以像 learn_struct 中的 default() 一样用于 struct update 语法
+    let origin = Point3D {
+        x: 5,
+        ..Default::default()
+    };
+    assert_eq!(origin, Point3D { x: 5, y: 0, z: 0 });
+    let p = Point3D::builder().z(1).x(2).build().unwrap();
+    println!("{:?}", p);
+    assert_eq!(p, Point3D { x: 2, y: 0, z: 1 });
+
+    // 链式调用, 整数字面量的类型由 setter 的参数类型推断
+    let server = Server::builder()
+        .name("lesson".to_string())
+        .workers(4)
+        .port(9000)
+        .build()
+        .unwrap();
+    println!("{:?}", server);
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.tags, vec!["default"]);
+
+    // 漏掉必填的成员, 运行时得到一个清楚的错误
+    let err = ServerBuilder::new().port(1).build().unwrap_err();
+    println!("{}", err);
+    assert_eq!(err.missing, vec!["name", "workers"]);
+    assert_eq!(
+        err.to_string(),
+        "cannot build Server: missing required fields `name`, `workers`"
+    );
+    let err = Server::builder().workers(1).build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "cannot build Server: missing required field `name`"
+    );
+
+    // Server 有必填的成员, 所以没有实现 Default; Point3D 的 Default 与 builder 的默认值一致
+    assert_eq!(Point3D::default(), Point3D::builder().build().unwrap());
+}
@@ -0,0 +1,359 @@
+#![allow(dead_code)]
+
+// 过程宏
+// first::third 只介绍了过程宏的概念, ch05::sixth::learn_derive 也只用了编译器内置的 derive。
+// 这里用工作区中的 dive-macros(见 macros/src/lib.rs)实现三个自己的过程宏:
+// 1. #[derive(Describe)]: 生成 Describe 的实现, 列出每个成员的名字、类型和值
+// 2. #[derive(FieldCount)]: 生成 FieldCount 的实现, 成员的个数和名字都是编译期常量
+// 3. #[lesson("9.2")]: 把函数登记到课程列表, main.rs 的课程表中不需要再写这一课,
+//    只支持有初始化段的平台(Linux、Android、FreeBSD、macOS/iOS 和 Windows), 其他平台上编译失败
+// 过程宏 crate 只能导出宏, 不能导出 trait, 所以 trait 定义在这里, 宏和 trait 重名也没关系,
+// 它们在不同的名字空间中, 与 serde 的 Serialize 一样:
+//
+// use dive_into_rust_of_fcc::ch09::third::{Describe, FieldCount};
+// #[derive(Describe, FieldCount)]
+// struct Point { x: i32, y: i32 }
+//
+// 宏的测试写在文档注释中, cargo test 会编译运行这些例子; 标记为 compile_fail 的例子必须编译失败。
+use std::fmt::{self, Write};
+use std::sync::{Mutex, PoisonError};
+
+pub use dive_macros::{lesson, Describe, FieldCount};
+
+/// 一个成员的描述
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// 成员名, 元组结构体是 "0"、"1"...
+    pub name: &'static str,
+    /// 源代码中写的类型
+    pub ty: &'static str,
+    /// 值的 {:?} 输出
+    pub value: String,
+}
+
+/// 列出结构体的成员, 通常由 #[derive(Describe)] 实现, 每个成员的类型都要实现 Debug
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch09::third::Describe;
+/// use std::marker::PhantomData;
+///
+/// #[derive(Describe)]
+/// struct Named<'a, T: Clone, const N: usize = 2>
+/// where
+///     T: Default,
+/// {
+///     pub name: &'a str,
+///     r#type: [T; N],
+///     marker: PhantomData<T>,
+/// }
+///
+/// #[derive(Describe)]
+/// struct Tuple(u8, pub Vec<(i32, String)>);
+///
+/// #[derive(Describe)]
+/// struct Unit;
+///
+/// let named = Named { name: "n", r#type: [1u8, 2], marker: PhantomData };
+/// let fields = named.fields();
+/// assert_eq!(named.type_name(), "Named");
+/// assert_eq!(fields[0].ty, "&'a str");
+/// assert_eq!(fields[1].name, "type");
+/// assert_eq!(fields[1].ty, "[T; N]");
+/// assert_eq!(fields[1].value, "[1, 2]");
+/// assert_eq!(fields[2].ty, "PhantomData<T>");
+///
+/// let t = Tuple(7, vec![(1, "a".to_string())]);
+/// assert_eq!(t.fields()[1].name, "1");
+/// assert_eq!(t.fields()[1].ty, "Vec<(i32, String)>");
+/// assert_eq!(t.fields()[1].value, r#"[(1, "a")]"#);
+/// assert!(Unit.fields().is_empty());
+/// assert_eq!(Unit.describe(), "Unit (no fields)");
+/// ```
+///
+/// 只支持结构体:
+///
+/// ```compile_fail
+/// use dive_into_rust_of_fcc::ch09::third::Describe;
+/// #[derive(Describe)]
+/// enum Choice { A, B }
+/// ```
+///
+/// 成员的类型没有实现 Debug:
+///
+/// ```compile_fail,E0277
+/// use dive_into_rust_of_fcc::ch09::third::Describe;
+/// struct Opaque;
+/// #[derive(Describe)]
+/// struct Holder { inner: Opaque }
+/// ```
+pub trait Describe {
+    fn type_name(&self) -> &'static str;
+    fn fields(&self) -> Vec<FieldInfo>;
+
+    /// 报表: 第一行是类型名, 之后每个成员一行, 名字和类型对齐
+    fn describe(&self) -> String {
+        let fields = self.fields();
+        if fields.is_empty() {
+            return format!("{} (no fields)", self.type_name());
+        }
+        let name_width = fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        let ty_width = fields
+            .iter()
+            .map(|f| f.ty.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut out = self.type_name().to_string();
+        for f in &fields {
+            // 向 String 写入不会失败
+            let _ = write!(
+                out,
+                "\n  {:<nw$} : {:<tw$} = {}",
+                f.name,
+                f.ty,
+                f.value,
+                nw = name_width,
+                tw = ty_width
+            );
+        }
+        out
+    }
+}
+
+/// 结构体成员的个数和名字, 通常由 #[derive(FieldCount)] 实现
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch09::third::FieldCount;
+///
+/// #[derive(FieldCount)]
+/// struct Rgb(u8, u8, u8);
+///
+/// #[derive(FieldCount)]
+/// struct Wrapper<T> { inner: T, count: usize }
+///
+/// // 编译期常量, 可以用在 const 和数组长度中
+/// const _: () = assert!(Rgb::FIELD_COUNT == 3);
+/// let names: [&str; Wrapper::<()>::FIELD_COUNT] = ["inner", "count"];
+/// assert_eq!(Wrapper::<String>::FIELD_NAMES, names);
+/// ```
+///
+/// ```compile_fail
+/// use dive_into_rust_of_fcc::ch09::third::FieldCount;
+/// #[derive(FieldCount)]
+/// union Bits { i: u32, f: f32 }
+/// ```
+pub trait FieldCount {
+    const FIELD_COUNT: usize;
+    const FIELD_NAMES: &'static [&'static str];
+}
+
+/// #[lesson] 登记的一课
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch09::third::{lesson, registered_lessons};
+///
+/// #[lesson("99.1")]
+/// fn hello() {
+///     println!("hello");
+/// }
+///
+/// // 函数本身不受影响
+/// hello();
+/// let found = registered_lessons().unwrap().into_iter().find(|l| l.id == "99.1").unwrap();
+/// assert_eq!(found.name, "hello");
+/// (found.run)();
+/// ```
+///
+/// 同一个编号只能登记一次, 重复的编号在取课程列表时报告:
+///
+/// ```
+/// use dive_into_rust_of_fcc::ch09::third::{lesson, registered_lessons};
+///
+/// #[lesson("99.4")]
+/// fn first() {}
+/// #[lesson("99.4")]
+/// fn second() {}
+///
+/// let err = registered_lessons().unwrap_err();
+/// assert_eq!(err.id, "99.4");
+/// // 初始化段中函数的调用顺序没有保证, 哪个算先登记的不一定
+/// let mut names = [err.first, err.second];
+/// names.sort();
+/// assert_eq!(names, ["first", "second"]);
+/// println!("{}", err);
+/// ```
+///
+/// 编号必须是用 . 隔开的数字:
+///
+/// ```compile_fail
+/// use dive_into_rust_of_fcc::ch09::third::lesson;
+/// #[lesson("nine")]
+/// fn bad_id() {}
+/// ```
+///
+/// 必须给出编号:
+///
+/// ```compile_fail
+/// use dive_into_rust_of_fcc::ch09::third::lesson;
+/// #[lesson]
+/// fn no_id() {}
+/// ```
+///
+/// 课程由课程表调用, 不能有参数:
+///
+/// ```compile_fail
+/// use dive_into_rust_of_fcc::ch09::third::lesson;
+/// #[lesson("99.2")]
+/// fn with_args(n: i32) {}
+/// ```
+///
+/// 只能用于函数:
+///
+/// ```compile_fail
+/// use dive_into_rust_of_fcc::ch09::third::lesson;
+/// #[lesson("99.3")]
+/// struct NotAFunction;
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Lesson {
+    pub id: &'static str,
+    /// 函数名
+    pub name: &'static str,
+    pub run: fn(),
+}
+
+/// 两个函数登记了同一个编号
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicateLesson {
+    pub id: &'static str,
+    /// 先登记的函数名
+    pub first: &'static str,
+    pub second: &'static str,
+}
+
+impl fmt::Display for DuplicateLesson {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "lesson {} is registered by both {} and {}",
+            self.id, self.first, self.second
+        )
+    }
+}
+
+impl std::error::Error for DuplicateLesson {}
+
+static REGISTERED: Mutex<Vec<Lesson>> = Mutex::new(Vec::new());
+static DUPLICATES: Mutex<Vec<DuplicateLesson>> = Mutex::new(Vec::new());
+
+/// 由 #[lesson] 生成的代码在 main 之前调用。
+/// 这时还不能报告错误(panic 会直接终止程序), 重复的编号先记下来, 由 registered_lessons 返回
+#[doc(hidden)]
+pub fn register_lesson(lesson: Lesson) {
+    let mut registered = REGISTERED.lock().unwrap_or_else(PoisonError::into_inner);
+    match registered.iter().find(|l| l.id == lesson.id) {
+        Some(first) => DUPLICATES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(DuplicateLesson {
+                id: lesson.id,
+                first: first.name,
+                second: lesson.name,
+            }),
+        None => registered.push(lesson),
+    }
+}
+
+/// 编号按数字比较, 9.2 排在 10 前面
+pub fn lesson_order(id: &str) -> Vec<u32> {
+    id.split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// 所有用 #[lesson] 登记的课程, 按编号排序; 有编号被登记了两次时返回错误
+pub fn registered_lessons() -> Result<Vec<Lesson>, DuplicateLesson> {
+    let duplicates = DUPLICATES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(&duplicate) = duplicates.first() {
+        return Err(duplicate);
+    }
+    let mut lessons = REGISTERED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    lessons.sort_by_key(|l| lesson_order(l.id));
+    Ok(lessons)
+}
+
+// ---------- 例子 ----------
+
+#[derive(Debug, Describe, FieldCount)]
+pub struct Server {
+    pub host: String,
+    pub port: u16,
+    pub routes: std::collections::HashMap<String, Vec<u32>>,
+    pub handler: Option<fn(&str) -> usize>,
+}
+
+#[derive(Describe, FieldCount)]
+pub struct Pair<T>(pub T, pub T);
+
+#[derive(Describe, FieldCount)]
+pub struct Borrowed<'a> {
+    pub name: &'a str,
+    pub tags: Vec<&'a str>,
+}
+
+#[derive(Describe, FieldCount)]
+pub struct Buffer<const N: usize> {
+    pub data: [u8; N],
+}
+
+#[lesson("9.2")]
+pub fn learn_derive_macros() {
+    let server = Server {
+        host: "localhost".to_string(),
+        port: 8080,
+        routes: vec![("/".to_string(), vec![1, 2])].into_iter().collect(),
+        handler: None,
+    };
+    let report = server.describe();
+    println!("{}", report);
+    let types: Vec<&str> = server.fields().iter().map(|f| f.ty).collect();
+    assert_eq!(
+        types,
+        [
+            "String",
+            "u16",
+            "std::collections::HashMap<String, Vec<u32>>",
+            "Option<fn(&str) -> usize>"
+        ]
+    );
+    assert!(report.starts_with("Server\n  host    : String"));
+    assert!(report.contains("  port    : u16 ") && report.ends_with("= None"));
+    assert_eq!(Server::FIELD_COUNT, 4);
+    assert_eq!(Server::FIELD_NAMES, ["host", "port", "routes", "handler"]);
+
+    // 泛型、生命周期和 const 泛型
+    let pair = Pair(1.5, 2.5);
+    println!("{}", pair.describe());
+    assert_eq!(pair.fields()[1].value, "2.5");
+    assert_eq!(<Pair<f64>>::FIELD_NAMES, ["0", "1"]);
+    let owner = String::from("owner");
+    let borrowed = Borrowed {
+        name: &owner,
+        tags: vec!["a", "b"],
+    };
+    println!("{}", borrowed.describe());
+    assert_eq!(borrowed.fields()[1].ty, "Vec<&'a str>");
+    let buffer = Buffer { data: [0u8; 4] };
+    assert_eq!(buffer.fields()[0].ty, "[u8; N]");
+    assert_eq!(Buffer::<4>::FIELD_COUNT, 1);
+
+    // 这一课自己就是用 #[lesson] 登记的, main.rs 的课程表中没有它
+    let me = registered_lessons()
+        .unwrap()
+        .into_iter()
+        .find(|l| l.name == "learn_derive_macros");
+    println!("registered: {:?}", me.map(|l| l.id));
+    assert_eq!(me.map(|l| l.id), Some("9.2"));
+}
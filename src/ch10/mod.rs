@@ -1 +1,2 @@
-pub mod first;
\ No newline at end of file
+pub mod first;
+pub mod second;
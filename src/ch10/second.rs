@@ -0,0 +1,256 @@
+#![allow(dead_code)]
+
+// 查看一个值在内存中的样子
+// 学习过程中经常会问这几个问题:
+// 1. 类型推导最后选了什么类型 (ch02::first::infer_type)
+// 2. 这个类型占多大空间、按多少字节对齐 (ch06::first::learn_array5)
+// 3. 它存放在哪里, 栈、堆、静态数据区还是代码段 (first 中讲的几个"段")
+// inspect!(expr) 一次性把这些信息都打印出来。
+// 宏只对表达式取引用, 不会把值 move 走, 所以 inspect!(x) 之后还可以继续使用 x。
+// 如果表达式是一个临时值, 比如 inspect!(Some(&x)), 那么看到的是这个临时值的地址。
+use std::fmt;
+use std::sync::atomic::AtomicUsize;
+
+/// 一块内存所在的区域
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    Stack,
+    Heap,
+    /// 数据段、只读数据段和 bss 段, 即全局变量、静态变量、字符串字面量所在的地方
+    Static,
+    Code,
+    Unknown,
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Region::Stack => "stack",
+            Region::Heap => "heap",
+            Region::Static => "static",
+            Region::Code => "code",
+            Region::Unknown => "unknown",
+        };
+        f.pad(s)
+    }
+}
+
+// 初始值为 0 的静态变量会被放在 bss 段, 用它来识别 bss 所在的匿名映射
+static BSS_PROBE: AtomicUsize = AtomicUsize::new(0);
+// 有初始值的静态变量放在数据段
+static DATA_PROBE: AtomicUsize = AtomicUsize::new(1);
+
+/// 判断一个地址属于哪个内存区域
+/// 在 Linux 上通过 /proc/self/maps 查询准确的映射关系,
+/// 其他平台上退化为与几个已知位置的"距离"比较, 只是一个近似的结果
+#[inline(never)]
+pub fn region_of(addr: *const u8) -> Region {
+    // 当前线程栈上的一个局部变量
+    let local = 0u8;
+    let stack = &local as *const u8 as usize;
+    let addr = addr as usize;
+    if addr == 0 {
+        return Region::Unknown;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(r) = linux::region_of(addr, stack) {
+            return r;
+        }
+    }
+    nearest(addr, stack)
+}
+
+// 与已知位置比较, 取最接近的一个
+fn nearest(addr: usize, stack: usize) -> Region {
+    let heap = Box::new(0u8);
+    let candidates = [
+        (stack, Region::Stack),
+        (&*heap as *const u8 as usize, Region::Heap),
+        (&DATA_PROBE as *const AtomicUsize as usize, Region::Static),
+        (&BSS_PROBE as *const AtomicUsize as usize, Region::Static),
+        (region_of as fn(*const u8) -> Region as usize, Region::Code),
+    ];
+    candidates
+        .iter()
+        .min_by_key(|(p, _)| p.abs_diff(addr))
+        .map_or(Region::Unknown, |&(_, r)| r)
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{Region, BSS_PROBE};
+    use std::fs;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Mapping {
+        start: usize,
+        end: usize,
+        exec: bool,
+        path: String,
+    }
+
+    // 每一行的格式: 起始-结束 权限 偏移 设备 inode 路径
+    // 55d0c0a00000-55d0c0a21000 r-xp 00000000 08:01 1234  /path/to/exe
+    fn parse(line: &str) -> Option<Mapping> {
+        let mut parts = line.split_whitespace();
+        let mut range = parts.next()?.split('-');
+        let start = usize::from_str_radix(range.next()?, 16).ok()?;
+        let end = usize::from_str_radix(range.next()?, 16).ok()?;
+        let perms = parts.next()?;
+        let path = parts.nth(3).unwrap_or("").to_string();
+        Some(Mapping {
+            start,
+            end,
+            exec: perms.contains('x'),
+            path,
+        })
+    }
+
+    pub fn region_of(addr: usize, stack: usize) -> Option<Region> {
+        let maps = fs::read_to_string("/proc/self/maps").ok()?;
+        let mappings: Vec<Mapping> = maps.lines().filter_map(parse).collect();
+        let find = |a: usize| mappings.iter().position(|m| m.start <= a && a < m.end);
+        let i = match find(addr) {
+            Some(i) => i,
+            None => return Some(Region::Unknown),
+        };
+        let m = &mappings[i];
+        let r = if m.path == "[stack]" || Some(i) == find(stack) {
+            // 主线程的栈是 [stack], 其他线程的栈是匿名映射, 用当前栈上的地址来识别
+            Region::Stack
+        } else if m.path == "[heap]" {
+            Region::Heap
+        } else if m.exec {
+            Region::Code
+        } else if !m.path.is_empty() && !m.path.starts_with('[') {
+            // 可执行文件或者动态库的非可执行部分: .rodata .data
+            Region::Static
+        } else if Some(i) == find(&BSS_PROBE as *const AtomicUsize as usize) {
+            Region::Static
+        } else {
+            // 其余的匿名映射, 大块内存分配会直接 mmap, 也算作堆
+            Region::Heap
+        };
+        Some(r)
+    }
+}
+
+/// inspect! 的结果
+#[derive(Clone, Debug)]
+pub struct Inspection {
+    pub expr: &'static str,
+    pub value: String,
+    pub type_name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    pub addr: usize,
+    pub region: Region,
+}
+
+impl Inspection {
+    /// 供 inspect! 宏调用
+    pub fn new<T: fmt::Debug + ?Sized>(expr: &'static str, value: &T) -> Inspection {
+        let addr = value as *const T as *const u8;
+        let size = std::mem::size_of_val(value);
+        Inspection {
+            expr,
+            value: format!("{:?}", value),
+            type_name: std::any::type_name::<T>(),
+            size,
+            align: std::mem::align_of_val(value),
+            addr: addr as usize,
+            // 零大小类型不占内存, 它的地址只是一个对齐好的非空值, 不属于任何区域
+            region: if size == 0 {
+                Region::Unknown
+            } else {
+                region_of(addr)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // 值太长的时候截断, 保证一行能显示下
+        let value = if self.value.chars().count() > 40 {
+            let head: String = self.value.chars().take(37).collect();
+            format!("{}...", head)
+        } else {
+            self.value.clone()
+        };
+        write!(
+            f,
+            "{} = {} : {} (size {}, align {}) @ {:#x} [{}]",
+            self.expr, value, self.type_name, self.size, self.align, self.addr, self.region
+        )
+    }
+}
+
+/// 打印表达式的源码、值、类型、大小、对齐、地址和所在的内存区域, 并返回这些信息
+///
+/// ```ignore
+/// let x = 5;
+/// inspect!(x);
+/// // x = 5 : i32 (size 4, align 4) @ 0x7ffd5a3c8a4c [stack]
+/// ```
+#[macro_export]
+macro_rules! inspect {
+    ($e:expr) => {{
+        let inspection = $crate::ch10::second::Inspection::new(stringify!($e), &$e);
+        println!("{}", inspection);
+        inspection
+    }};
+}
+
+static GREETING: &str = "hello";
+static ZEROS: [u64; 4] = [0; 4];
+
+pub fn learn_inspect() {
+    // 栈上的局部变量, 推导出的类型是 i32
+    let x = 5;
+    let r = inspect!(x);
+    assert_eq!((r.type_name, r.size, r.region), ("i32", 4, Region::Stack));
+
+    // Box 本身在栈上, 它指向的内容在堆上
+    let b = Box::new([1u16, 2, 3]);
+    assert_eq!(inspect!(b).region, Region::Stack);
+    let r = inspect!(*b);
+    assert_eq!((r.size, r.align, r.region), (6, 2, Region::Heap));
+
+    // String 也是一样, 它的内容在堆上; 字符串字面量在静态数据区
+    let s = String::from("深入浅出");
+    inspect!(s);
+    assert_eq!(inspect!(*s).region, Region::Heap);
+    let r = inspect!(*GREETING);
+    assert_eq!((r.type_name, r.region), ("str", Region::Static));
+
+    // 全局变量
+    assert_eq!(inspect!(GREETING).region, Region::Static);
+    assert_eq!(inspect!(ZEROS).region, Region::Static);
+
+    // 函数在代码段
+    assert_eq!(region_of(learn_inspect as *const u8), Region::Code);
+
+    // 同样的值, 类型不同, 大小和对齐也不同
+    // 注意常量表达式的临时值会被编译器提升(promote)为静态变量, 所以 &5u8 指向静态数据区
+    inspect!(5u8);
+    inspect!(5u64);
+    inspect!(Some(5u8));
+    // 而依赖局部变量的临时值只能放在栈上
+    assert_eq!(inspect!(Some(&x)).region, Region::Stack);
+    let r = inspect!(());
+    assert_eq!((r.size, r.region), (0, Region::Unknown));
+
+    // 大块内存分配也在堆上
+    let big = vec![0u8; 1 << 22];
+    assert_eq!(inspect!(big[0]).region, Region::Heap);
+
+    // 其他线程的栈也能识别出来
+    std::thread::spawn(|| {
+        let y = 1.5f64;
+        assert_eq!(inspect!(y).region, Region::Stack);
+    })
+    .join()
+    .unwrap();
+}
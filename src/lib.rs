@@ -1,3 +1,7 @@
+// 过程宏生成的代码用 ::dive_into_rust_of_fcc::... 引用本 crate 中的 trait,
+// 这一行让同样的路径在本 crate 内部也能使用
+extern crate self as dive_into_rust_of_fcc;
+
 pub mod ch01;
 pub mod ch02;
 pub mod ch03;
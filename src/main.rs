@@ -35,6 +35,31 @@ fn main2_3() {
     third::learn_enum();
 }
 
+fn main2_4() {
+    use dive_into_rust_of_fcc::ch02::fourth;
+    fourth::learn_cast();
+}
+
+fn main2_5() {
+    use dive_into_rust_of_fcc::ch02::fifth;
+    fifth::learn_units();
+}
+
+fn main2_6() {
+    use dive_into_rust_of_fcc::ch02::sixth;
+    sixth::learn_wire();
+}
+
+fn main2_7() {
+    use dive_into_rust_of_fcc::ch02::seventh;
+    seventh::learn_recursive_types();
+}
+
+fn main2_8() {
+    use dive_into_rust_of_fcc::ch02::eighth;
+    eighth::learn_global_registry();
+}
+
 fn main3_1() {
     use dive_into_rust_of_fcc::ch03::first;
     first::learn_operator();
@@ -53,6 +78,16 @@ fn main3_2() {
     second::learn_for();
 }
 
+fn main3_3() {
+    use dive_into_rust_of_fcc::ch03::third;
+    third::learn_interpreter();
+}
+
+fn main3_4() {
+    use dive_into_rust_of_fcc::ch03::fourth;
+    fourth::learn_bitset();
+}
+
 fn main4_1() {
     use dive_into_rust_of_fcc::ch04::first;
     first::first();
@@ -63,6 +98,21 @@ fn main4_1() {
     first::learn_recursion();
 }
 
+fn main4_2() {
+    use dive_into_rust_of_fcc::ch04::second;
+    second::learn_fibonacci();
+}
+
+fn main4_3() {
+    use dive_into_rust_of_fcc::ch04::third;
+    third::learn_bigint();
+}
+
+fn main4_4() {
+    use dive_into_rust_of_fcc::ch04::fourth;
+    fourth::learn_const_tables();
+}
+
 fn main5_1() {
     use dive_into_rust_of_fcc::ch05::eighth;
     use dive_into_rust_of_fcc::ch05::fifth;
@@ -84,6 +134,41 @@ fn main5_1() {
     eighth::learn_float();
 }
 
+fn main5_2() {
+    use dive_into_rust_of_fcc::ch05::ninth;
+    ninth::learn_geometry();
+}
+
+fn main5_3() {
+    use dive_into_rust_of_fcc::ch05::tenth;
+    tenth::learn_svg();
+}
+
+fn main5_4() {
+    use dive_into_rust_of_fcc::ch05::eleventh;
+    eleventh::learn_plugin_registry();
+}
+
+fn main5_5() {
+    use dive_into_rust_of_fcc::ch05::twelfth;
+    twelfth::learn_extension_traits();
+}
+
+fn main5_6() {
+    use dive_into_rust_of_fcc::ch05::thirteenth;
+    thirteenth::learn_pretty_print();
+}
+
+fn main5_7() {
+    use dive_into_rust_of_fcc::ch05::fourteenth;
+    fourteenth::learn_callable_registry();
+}
+
+fn main5_8() {
+    use dive_into_rust_of_fcc::ch05::fifteenth;
+    fifteenth::learn_upcasting();
+}
+
 fn main6_1() {
     use dive_into_rust_of_fcc::ch06::first;
     use dive_into_rust_of_fcc::ch06::second;
@@ -103,6 +188,26 @@ fn main6_1() {
     second::learn_string2();
 }
 
+fn main6_2() {
+    use dive_into_rust_of_fcc::ch06::third;
+    third::learn_fat_pointers();
+}
+
+fn main6_3() {
+    use dive_into_rust_of_fcc::ch06::fourth;
+    fourth::learn_matrix();
+}
+
+fn main6_4() {
+    use dive_into_rust_of_fcc::ch06::fifth;
+    fifth::learn_array_vec();
+}
+
+fn main6_5() {
+    use dive_into_rust_of_fcc::ch06::sixth;
+    sixth::learn_utf8_strings();
+}
+
 fn main7_1() {
     use dive_into_rust_of_fcc::ch07::first;
     use dive_into_rust_of_fcc::ch07::second;
@@ -134,6 +239,16 @@ fn main_9() {
     first::third();
 }
 
+fn main_9_1() {
+    use dive_into_rust_of_fcc::ch09::second;
+    second::learn_builder();
+}
+
+fn main_10() {
+    use dive_into_rust_of_fcc::ch10::second;
+    second::learn_inspect();
+}
+
 fn main_11() {
     use dive_into_rust_of_fcc::ch11::first;
     use dive_into_rust_of_fcc::ch11::second;
@@ -191,6 +306,381 @@ fn main_16() {
     first::fifth();
 }
 
-fn main() {
-    main_16();
+// 以下是命令行入口
+// 用法见 USAGE, 不依赖第三方库, 参数、环境变量和退出码的用法见 ch04::first::learn_main_fn
+use std::cell::RefCell;
+use std::panic;
+use std::process::{ExitCode, Termination};
+
+/// 所有的课程, 编号中的 "." 前面是章节号; 用 #[lesson] 登记的课程不需要写在这里
+const LESSONS: &[(&str, fn())] = &[
+    ("1", main1),
+    ("2.1", main2_1),
+    ("2.2", main2_2),
+    ("2.3", main2_3),
+    ("2.4", main2_4),
+    ("2.5", main2_5),
+    ("2.6", main2_6),
+    ("2.7", main2_7),
+    ("2.8", main2_8),
+    ("3.1", main3_1),
+    ("3.2", main3_2),
+    ("3.3", main3_3),
+    ("3.4", main3_4),
+    ("4.1", main4_1),
+    ("4.2", main4_2),
+    ("4.3", main4_3),
+    ("4.4", main4_4),
+    ("5.1", main5_1),
+    ("5.2", main5_2),
+    ("5.3", main5_3),
+    ("5.4", main5_4),
+    ("5.5", main5_5),
+    ("5.6", main5_6),
+    ("5.7", main5_7),
+    ("5.8", main5_8),
+    ("6.1", main6_1),
+    ("6.2", main6_2),
+    ("6.3", main6_3),
+    ("6.4", main6_4),
+    ("6.5", main6_5),
+    ("7.1", main7_1),
+    ("8", main_8),
+    ("9", main_9),
+    ("9.1", main_9_1),
+    ("10", main_10),
+    ("11", main_11),
+    ("11.1", main_11_1),
+    ("12", main_12),
+    ("13", main_13),
+    ("14", main_14),
+    ("15", main_15),
+    ("16", main_16),
+];
+
+const USAGE: &str = "\
+usage: dive-into-rust-of-fcc [options] [command] [lesson...]
+
+commands:
+    run [lesson...]   run the given lessons or chapters (the default command)
+    list              list all lessons
+    all               run every lesson
+    help              show this message
+
+options:
+    -c, --color <when>  colorize output: always, never or auto
+    -k, --keep-going    keep running the remaining lessons after a failure
+    -h, --help          show this message
+    -V, --version       show the version
+
+environment:
+    DIVE_CHAPTER  lessons to run when none are given, e.g. \"2.4 3\" (default: 16)
+    DIVE_COLOR    the same as --color
+    NO_COLOR      disable colors when set to any value
+
+exit codes:
+    0 success, 1 a lesson assertion failed, 2 usage error, 101 a lesson panicked";
+
+/// 程序的退出状态, 实现 Termination 之后可以直接作为 main 的返回值
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Outcome {
+    Success,
+    LessonFailed,
+    Panicked,
+    Usage,
+}
+
+impl Termination for Outcome {
+    fn report(self) -> ExitCode {
+        ExitCode::from(match self {
+            Outcome::Success => 0,
+            Outcome::LessonFailed => 1,
+            Outcome::Usage => 2,
+            Outcome::Panicked => 101,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Always,
+    Never,
+    Auto,
+}
+
+impl std::str::FromStr for Color {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Color, String> {
+        match s {
+            "always" | "on" | "1" => Ok(Color::Always),
+            "never" | "off" | "0" => Ok(Color::Never),
+            "auto" => Ok(Color::Auto),
+            _ => Err(format!(
+                "invalid color {:?}, expected always, never or auto",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Run(Vec<String>),
+    List,
+    All,
+    Help,
+    Version,
+}
+
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    color: bool,
+    keep_going: bool,
+}
+
+// 解析命令行参数, 选项可以出现在任意位置; 没有给出的设置从环境变量中读取
+fn parse_args<I: Iterator<Item = String>>(
+    mut args: I,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<Options, String> {
+    let mut command: Option<String> = None;
+    let mut lessons = Vec::new();
+    let mut color = None;
+    let mut keep_going = false;
+    while let Some(arg) = args.next() {
+        // 同时支持 --color=never 和 --color never 两种写法
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (arg.clone(), None),
+        };
+        match flag.as_str() {
+            "-h" | "--help" => command = Some("help".to_string()),
+            "-V" | "--version" => command = Some("version".to_string()),
+            "-k" | "--keep-going" => keep_going = true,
+            "-c" | "--color" => {
+                let value = inline
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} requires a value", flag))?;
+                color = Some(value.parse::<Color>()?);
+            }
+            f if f.starts_with('-') && f.len() > 1 => {
+                return Err(format!("unknown option {}", f));
+            }
+            _ if command.is_none() && lessons.is_empty() && !looks_like_lesson(&arg) => {
+                command = Some(arg);
+            }
+            _ => lessons.push(arg),
+        }
+    }
+    let command = match command.as_deref() {
+        None | Some("run") => {
+            if lessons.is_empty() {
+                let default = env("DIVE_CHAPTER").unwrap_or_else(|| "16".to_string());
+                lessons = default.split_whitespace().map(String::from).collect();
+            }
+            Command::Run(lessons)
+        }
+        Some(c) if !lessons.is_empty() => {
+            return Err(format!("{} does not take lessons", c));
+        }
+        Some("list") => Command::List,
+        Some("all") => Command::All,
+        Some("help") => Command::Help,
+        Some("version") => Command::Version,
+        Some(c) => return Err(format!("unknown command {}", c)),
+    };
+    let color = match color {
+        Some(c) => c,
+        None => match env("DIVE_COLOR") {
+            Some(v) => v.parse().map_err(|e| format!("DIVE_COLOR: {}", e))?,
+            None => Color::Auto,
+        },
+    };
+    let color = match color {
+        Color::Always => true,
+        Color::Never => false,
+        // 约定俗成: NO_COLOR 存在时不输出颜色, 输出被重定向时也不输出颜色
+        Color::Auto => {
+            use std::io::IsTerminal;
+            env("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+        }
+    };
+    Ok(Options {
+        command,
+        color,
+        keep_going,
+    })
+}
+
+fn looks_like_lesson(arg: &str) -> bool {
+    arg.starts_with(|c: char| c.is_ascii_digit())
+}
+
+// LESSONS 加上用 #[lesson] 登记的课程(见 ch09::third), 按编号排序; 编号不能重复
+fn all_lessons() -> Result<Vec<(&'static str, fn())>, String> {
+    use dive_into_rust_of_fcc::ch09::third;
+    let registered = third::registered_lessons().map_err(|e| e.to_string())?;
+    let mut lessons = LESSONS.to_vec();
+    for l in &registered {
+        if LESSONS.iter().any(|(id, _)| *id == l.id) {
+            return Err(format!(
+                "lesson {} is registered by {} but is also in LESSONS",
+                l.id, l.name
+            ));
+        }
+        lessons.push((l.id, l.run));
+    }
+    lessons.sort_by_key(|(id, _)| third::lesson_order(id));
+    Ok(lessons)
+}
+
+// "2" 选中第二章的所有课程, "2.4" 只选中一课
+fn select(lessons: &[(&'static str, fn())], pattern: &str) -> Vec<(&'static str, fn())> {
+    lessons
+        .iter()
+        .copied()
+        .filter(|(id, _)| {
+            *id == pattern || id.strip_prefix(pattern).is_some_and(|r| r.starts_with('.'))
+        })
+        .collect()
+}
+
+fn paint(color: bool, code: &str, text: &str) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+thread_local! {
+    // 本线程最近一次 panic 的位置: 文件、行、列
+    static PANIC_AT: RefCell<Option<(String, u32, u32)>> = const { RefCell::new(None) };
+}
+
+// 在默认的 panic hook 之前记下 panic 的位置, 默认 hook 照常打印信息
+fn install_panic_hook() {
+    let default = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let at = info
+            .location()
+            .map(|l| (l.file().to_string(), l.line(), l.column()));
+        PANIC_AT.with(|p| *p.borrow_mut() = at);
+        default(info);
+    }));
+}
+
+// panic 发生的那一行是不是 assert!、assert_eq!、assert_ne! 或者它们的 debug_ 版本。
+// assert!(cond, "自定义信息") 的 panic 信息就是自定义信息本身, 不能靠信息的文字区分,
+// 只能看 panic 的位置; 源文件读不到时(例如程序被复制到了别处)当作普通的 panic
+fn is_assertion(file: &str, line: u32) -> bool {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(file);
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(_) => return false,
+    };
+    let text = match source.lines().nth(line.saturating_sub(1) as usize) {
+        Some(text) => text,
+        None => return false,
+    };
+    ["assert!(", "assert_eq!(", "assert_ne!("]
+        .iter()
+        .any(|m| text.contains(m))
+}
+
+// 运行一课, 捕获其中的 panic
+// 课程用 assert! 检查结果, 断言失败算作课程失败, 其他的 panic 算作程序错误
+fn run_lesson(id: &str, lesson: fn(), color: bool) -> Outcome {
+    println!("{}", paint(color, "1;36", &format!("== lesson {} ==", id)));
+    PANIC_AT.with(|p| p.borrow_mut().take());
+    match panic::catch_unwind(lesson) {
+        Ok(()) => Outcome::Success,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            let at = PANIC_AT.with(|p| p.borrow_mut().take());
+            let outcome = match &at {
+                Some((file, line, _)) if is_assertion(file, *line) => Outcome::LessonFailed,
+                _ => Outcome::Panicked,
+            };
+            let place = at
+                .map(|(file, line, column)| format!(" at {}:{}:{}", file, line, column))
+                .unwrap_or_default();
+            let summary = format!("lesson {} failed{}: {}", id, place, message);
+            println!("{}", paint(color, "1;31", &summary));
+            outcome
+        }
+    }
+}
+
+fn run(options: Options) -> Outcome {
+    // 课程编号重复是程序本身的错误
+    let all = match all_lessons() {
+        Ok(all) => all,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return Outcome::Panicked;
+        }
+    };
+    let lessons = match options.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Outcome::Success;
+        }
+        Command::Version => {
+            println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            return Outcome::Success;
+        }
+        Command::List => {
+            for (id, _) in all {
+                println!("{}", id);
+            }
+            return Outcome::Success;
+        }
+        Command::All => all,
+        Command::Run(patterns) => {
+            let mut lessons = Vec::new();
+            for p in &patterns {
+                let selected = select(&all, p);
+                if selected.is_empty() {
+                    eprintln!("error: no lesson matches {:?}, see `list`", p);
+                    return Outcome::Usage;
+                }
+                lessons.extend(selected);
+            }
+            lessons
+        }
+    };
+    let mut worst = Outcome::Success;
+    let mut failed = Vec::new();
+    for (id, lesson) in lessons {
+        let outcome = run_lesson(id, lesson, options.color);
+        if outcome != Outcome::Success {
+            failed.push(id);
+            worst = worst.max(outcome);
+            if !options.keep_going {
+                break;
+            }
+        }
+    }
+    if !failed.is_empty() {
+        let summary = format!("failed lessons: {}", failed.join(" "));
+        println!("{}", paint(options.color, "1;31", &summary));
+    }
+    worst
+}
+
+fn main() -> Outcome {
+    install_panic_hook();
+    match parse_args(std::env::args().skip(1), |key| std::env::var(key).ok()) {
+        Ok(options) => run(options),
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            Outcome::Usage
+        }
+    }
 }
@@ -0,0 +1,110 @@
+// 课程运行器的命令行: 选项、环境变量和退出码
+// 每个测试都启动一次程序, 环境变量只设置给子进程, 测试之间互不影响。
+use std::process::{Command, Output};
+
+fn run(args: &[&str], env: &[(&str, &str)]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_dive-into-rust-of-fcc"));
+    cmd.args(args);
+    for var in &["DIVE_CHAPTER", "DIVE_COLOR", "NO_COLOR"] {
+        cmd.env_remove(var);
+    }
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    cmd.output().expect("failed to start the lesson runner")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn help_and_version() {
+    for flag in &["-h", "--help", "help"] {
+        let out = run(&[flag], &[]);
+        assert_eq!(out.status.code(), Some(0));
+        assert!(stdout(&out).starts_with("usage:"));
+    }
+    for flag in &["-V", "--version", "version"] {
+        let out = run(&[flag], &[]);
+        assert_eq!(out.status.code(), Some(0));
+        assert!(stdout(&out).starts_with("dive-into-rust-of-fcc "));
+    }
+}
+
+#[test]
+fn list_and_select() {
+    let out = run(&["list"], &[]);
+    assert_eq!(out.status.code(), Some(0));
+    let ids: Vec<String> = stdout(&out).lines().map(String::from).collect();
+    assert!(ids.contains(&"1".to_string()));
+    assert!(ids.contains(&"2.4".to_string()));
+
+    // 选项可以放在课程编号之后
+    let out = run(&["run", "1", "--color", "never"], &[]);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout(&out).starts_with("== lesson 1 =="));
+}
+
+#[test]
+fn default_lessons_come_from_dive_chapter() {
+    let out = run(&[], &[("DIVE_CHAPTER", "1 2.1")]);
+    assert_eq!(out.status.code(), Some(0));
+    let headers: Vec<String> = stdout(&out)
+        .lines()
+        .filter(|l| l.starts_with("== lesson"))
+        .map(String::from)
+        .collect();
+    assert_eq!(headers, ["== lesson 1 ==", "== lesson 2.1 =="]);
+    // 命令行给出的课程优先
+    let out = run(&["1"], &[("DIVE_CHAPTER", "2.1")]);
+    assert!(!stdout(&out).contains("== lesson 2.1 =="));
+}
+
+#[test]
+fn color_from_flags_and_environment() {
+    let colored = |out: &Output| stdout(out).contains('\x1b');
+    assert!(colored(&run(&["--color", "always", "1"], &[])));
+    assert!(colored(&run(&["--color=on", "1"], &[])));
+    assert!(!colored(&run(&["-c", "never", "1"], &[])));
+    assert!(colored(&run(&["1"], &[("DIVE_COLOR", "always")])));
+    // 命令行优先于环境变量
+    assert!(!colored(&run(
+        &["-c", "never", "1"],
+        &[("DIVE_COLOR", "always")]
+    )));
+    // auto: NO_COLOR 或者输出不是终端时都不输出颜色
+    assert!(!colored(&run(&["1"], &[("NO_COLOR", "1")])));
+    assert!(!colored(&run(&["-c", "auto", "1"], &[])));
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let cases: &[(&[&str], &[(&str, &str)], &str)] = &[
+        (&["--bogus"], &[], "unknown option --bogus"),
+        (&["--color"], &[], "--color requires a value"),
+        (&["--color=rainbow"], &[], "invalid color \"rainbow\""),
+        (
+            &["1"],
+            &[("DIVE_COLOR", "rainbow")],
+            "DIVE_COLOR: invalid color",
+        ),
+        (&["list", "1"], &[], "list does not take lessons"),
+        (&["frobnicate"], &[], "unknown command frobnicate"),
+        (&["99"], &[], "no lesson matches \"99\""),
+    ];
+    for (args, env, message) in cases {
+        let out = run(args, env);
+        assert_eq!(out.status.code(), Some(2), "{:?}", args);
+        assert!(
+            stderr(&out).contains(message),
+            "{:?}: {}",
+            args,
+            stderr(&out)
+        );
+    }
+}
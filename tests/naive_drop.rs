@@ -0,0 +1,41 @@
+// 2.7 编译器生成的递归析构在长链表上会栈溢出
+// 栈溢出会直接终止整个进程, 无法用 catch_unwind 捕获, 所以这个测试没有使用测试框架,
+// 而是用自己的 main 再启动自己: 子进程只在一个小栈线程中销毁一个 NaiveList, 父进程检查它的退出状态。
+use dive_into_rust_of_fcc::ch02::seventh::NaiveList;
+use std::process::{self, Command, Output};
+use std::thread;
+
+const LEN_ENV: &str = "DIVE_NAIVE_DROP";
+
+// 子进程: 在一个栈只有 256KB 的线程中销毁长度为 n 的 NaiveList
+fn drop_naive_list(n: usize) -> ! {
+    let list = NaiveList::with_len(n);
+    let dropped = thread::Builder::new()
+        .name("naive-drop".to_string())
+        .stack_size(256 << 10)
+        .spawn(move || drop(list))
+        .map(|handle| handle.join().is_ok())
+        .unwrap_or(false);
+    process::exit(if dropped { 0 } else { 1 });
+}
+
+fn in_child(n: usize) -> Output {
+    Command::new(std::env::current_exe().unwrap())
+        .env(LEN_ENV, n.to_string())
+        .output()
+        .expect("failed to start the child process")
+}
+
+fn main() {
+    if let Some(n) = std::env::var(LEN_ENV).ok().and_then(|v| v.parse().ok()) {
+        drop_naive_list(n);
+    }
+    // 一百个节点没有问题
+    assert!(in_child(100).status.success());
+    // 一百万个节点就会栈溢出, 进程被异常终止
+    let output = in_child(1_000_000);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("has overflowed its stack"), "{}", stderr);
+    println!("naive drop of 1000000 nodes: {}", output.status);
+}